@@ -0,0 +1,39 @@
+use aoc2024::day18::blocking::{walled_bytes, Strategy};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+
+/// Every blocking strategy on maps of growing size, each with a tenth of its
+/// cells hit by falling bytes.
+fn blocking_strategies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day18_blocking");
+    group.sample_size(10);
+    for side in [71, 200, 1000] {
+        let size = (side, side);
+        let bytes = walled_bytes(size, side * side / 10, 18).unwrap();
+        let answer = *bytes.last().unwrap();
+        for strategy in Strategy::ALL {
+            // Incremental reruns its path finder for most bytes and takes
+            // minutes on the largest map.
+            if side > 200 && matches!(strategy, Strategy::Incremental) {
+                continue;
+            }
+            let solver = strategy.solver();
+            group.bench_with_input(
+                BenchmarkId::new(strategy.to_string(), side),
+                &bytes,
+                |b, bytes| {
+                    b.iter(|| {
+                        assert_eq!(
+                            solver.first_blocking(size, black_box(bytes)).unwrap(),
+                            answer
+                        )
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, blocking_strategies);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+use aoc2024::day18::{blocking::walled_bytes, matrix::combinations, Scenario};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+
+/// Every map backend with every path finder, on generated maps the size of
+/// the puzzle and twice that.
+fn map_backends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day18_maps");
+    group.sample_size(10);
+    for side in [71, 141] {
+        let size = (side, side);
+        // About as crowded as the puzzle input when the path is cut off.
+        let bytes = walled_bytes(size, side * side * 2 / 3, 35).unwrap();
+        let scenario = Scenario {
+            size,
+            fall_count: bytes.len() / 3,
+        };
+        for combination in combinations() {
+            // Vec backed maps search the whole list for every cell visited.
+            if side > 71 && combination.map == "vec" {
+                continue;
+            }
+            let name = combination.name();
+            group.bench_with_input(
+                BenchmarkId::new(format!("part1/{name}"), side),
+                &bytes,
+                |b, bytes| b.iter(|| combination.part1(black_box(bytes), scenario).unwrap()),
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("part2/{name}"), side),
+                &bytes,
+                |b, bytes| b.iter(|| combination.part2(black_box(bytes), scenario).unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, map_backends);
+criterion_main!(benches);
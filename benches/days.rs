@@ -0,0 +1,266 @@
+//! Every day's parse and both parts on the puzzle input, and both parts on
+//! generated inputs of growing size.
+//!
+//! Criterion keeps its own history under `target/criterion`, which is lost
+//! with the build directory.  `benches/baseline.json` keeps the mean time of
+//! every benchmark in the tree instead:
+//!
+//! ```sh
+//! cargo bench --bench days                        # compare with the baseline
+//! BENCH_BASELINE=save cargo bench --bench days    # update the baseline
+//! cargo bench --bench days -- day05               # one day only
+//! ```
+//!
+//! Only benchmarks run by this invocation are compared or saved, so a run
+//! filtered to one day leaves the others in the baseline alone.
+
+use aoc2024::*;
+use criterion::{criterion_group, BenchmarkId, Criterion};
+use serde_json::Value;
+use std::{collections::BTreeMap, hint::black_box, path::Path, time::Duration};
+
+/// A day's public entry points, with the answer as a string.
+struct Day {
+    day: u32,
+    parse: fn(&str),
+    part1: fn(&str) -> String,
+    part2: fn(&str) -> String,
+}
+
+macro_rules! day {
+    ($day:ident) => {
+        Day {
+            day: $day::DAY,
+            parse: |s| drop(black_box($day::parse_input(s).unwrap())),
+            part1: |s| $day::part1(s).to_string(),
+            part2: |s| $day::part2(s).to_string(),
+        }
+    };
+    // Days whose parts return a Result instead of panicking.
+    ($day:ident?) => {
+        Day {
+            day: $day::DAY,
+            parse: |s| drop(black_box($day::parse_input(s).unwrap())),
+            part1: |s| $day::part1(s).unwrap().to_string(),
+            part2: |s| $day::part2(s).unwrap().to_string(),
+        }
+    };
+}
+
+fn days() -> [Day; 25] {
+    [
+        day!(day1),
+        day!(day2?),
+        day!(day3),
+        day!(day4),
+        day!(day5),
+        day!(day6),
+        day!(day7),
+        day!(day8),
+        day!(day9),
+        day!(day10),
+        day!(day11),
+        day!(day12),
+        day!(day13),
+        day!(day14),
+        day!(day15),
+        day!(day16),
+        day!(day17),
+        day!(day18),
+        day!(day19),
+        day!(day20),
+        day!(day21),
+        day!(day22),
+        day!(day23),
+        day!(day24),
+        day!(day25),
+    ]
+}
+
+/// Input sizes for the scaling benchmarks, up to the puzzle's own.
+fn scales(day: u32) -> Vec<usize> {
+    match day {
+        // Programs only have 5 to 10 instructions.
+        17 => vec![5, 7, 10],
+        // Adders need at least 16 bits.
+        24 => vec![80, 150, 222],
+        _ => {
+            let size = gen::puzzle_size(day).unwrap();
+            vec![(size / 4).max(1), (size / 2).max(1), size]
+        }
+    }
+}
+
+fn puzzle_inputs(c: &mut Criterion) {
+    for day in days() {
+        let path = format!("input/2024/day{}.txt", day.day);
+        let input = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{path}: {e}"));
+        // aoc-runner trims the input before handing it over.
+        let input = input.trim_end();
+
+        let mut group = c.benchmark_group(format!("day{:02}", day.day));
+        group
+            .sample_size(10)
+            .warm_up_time(Duration::from_secs(1))
+            .measurement_time(Duration::from_secs(2));
+        group.bench_function("parse", |b| b.iter(|| (day.parse)(black_box(input))));
+        group.bench_function("part1", |b| b.iter(|| (day.part1)(black_box(input))));
+        group.bench_function("part2", |b| b.iter(|| (day.part2)(black_box(input))));
+        group.finish();
+    }
+}
+
+fn scaling(c: &mut Criterion) {
+    for day in days() {
+        let mut group = c.benchmark_group(format!("day{:02}_scaling", day.day));
+        group
+            .sample_size(10)
+            .warm_up_time(Duration::from_secs(1))
+            .measurement_time(Duration::from_secs(2));
+        for size in scales(day.day) {
+            let input = gen::generate(day.day, 0, size).unwrap();
+            group.bench_with_input(BenchmarkId::new("part1", size), &input, |b, input| {
+                b.iter(|| (day.part1)(black_box(input)))
+            });
+            // Random robots never draw the tree day 14's part 2 looks for.
+            if day.day != 14 {
+                group.bench_with_input(BenchmarkId::new("part2", size), &input, |b, input| {
+                    b.iter(|| (day.part2)(black_box(input)))
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, puzzle_inputs, scaling);
+
+fn main() {
+    let started = std::time::SystemTime::now();
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    baseline::update(started);
+}
+
+mod baseline {
+    use super::*;
+    use std::{fs, time::SystemTime};
+
+    const PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/baseline.json");
+    /// Changes smaller than this are noise.
+    const THRESHOLD: f64 = 0.10;
+
+    /// Compare the benchmarks run since `started` with the baseline, or save
+    /// them into it when `BENCH_BASELINE=save`.
+    pub fn update(started: SystemTime) {
+        let save = match std::env::var("BENCH_BASELINE").as_deref() {
+            Ok("save") => true,
+            Ok("compare") | Err(_) => false,
+            Ok(other) => panic!("BENCH_BASELINE={other}, expected save or compare"),
+        };
+        let target = std::env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".into());
+        let mut measured = BTreeMap::new();
+        collect(
+            &Path::new(&target).join("criterion"),
+            started,
+            &mut measured,
+        );
+        if measured.is_empty() {
+            // Nothing ran, as with `--test` or `--list`.
+            return;
+        }
+
+        let mut baseline = match fs::read_to_string(PATH) {
+            Ok(json) => serde_json::from_str::<BTreeMap<String, f64>>(&json)
+                .unwrap_or_else(|e| panic!("{PATH}: {e}")),
+            Err(_) => BTreeMap::new(),
+        };
+        if save {
+            let count = measured.len();
+            // Nanosecond digits are noise anyway.
+            baseline.extend(measured.into_iter().map(|(id, ns)| (id, ns.round())));
+            let json = serde_json::to_string_pretty(&baseline).unwrap();
+            fs::write(PATH, json + "\n").unwrap_or_else(|e| panic!("{PATH}: {e}"));
+            println!("saved {count} benchmarks to {PATH}");
+            return;
+        }
+
+        let mut unchanged = 0;
+        for (id, now) in &measured {
+            let Some(before) = baseline.get(id) else {
+                println!("{id:<32} {:>12}  not in baseline", format_ns(*now));
+                continue;
+            };
+            let change = now / before - 1.0;
+            if change.abs() < THRESHOLD {
+                unchanged += 1;
+                continue;
+            }
+            let verdict = if change > 0.0 {
+                "REGRESSED"
+            } else {
+                "improved"
+            };
+            println!(
+                "{id:<32} {:>12} -> {:>12} {:+7.1}%  {verdict}",
+                format_ns(*before),
+                format_ns(*now),
+                change * 100.0
+            );
+        }
+        println!(
+            "{unchanged} of {} benchmarks within {:.0}% of the baseline",
+            measured.len(),
+            THRESHOLD * 100.0
+        );
+    }
+
+    /// Mean time in nanoseconds of each benchmark of this file that Criterion
+    /// measured since `started`, by full id.
+    fn collect(dir: &Path, started: SystemTime, measured: &mut BTreeMap<String, f64>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // `base` and `change` hold older runs, `report` only HTML.
+            if path.ends_with("new") {
+                if let Some((id, mean)) = read(&path, started) {
+                    measured.insert(id, mean);
+                }
+            } else if path.is_dir() && !path.ends_with("report") {
+                collect(&path, started, measured);
+            }
+        }
+    }
+
+    fn read(dir: &Path, started: SystemTime) -> Option<(String, f64)> {
+        let estimates = dir.join("estimates.json");
+        if fs::metadata(&estimates).ok()?.modified().ok()? < started {
+            return None;
+        }
+        let json = |file: &Path| -> Option<Value> {
+            serde_json::from_str(&fs::read_to_string(file).ok()?).ok()
+        };
+        let id = json(&dir.join("benchmark.json"))?["full_id"]
+            .as_str()?
+            .to_string();
+        // Other bench files write here too.
+        let group = id.split('/').next()?;
+        let day = group.strip_suffix("_scaling").unwrap_or(group);
+        if !(day.len() == 5 && day.starts_with("day") && day[3..].parse::<u32>().is_ok()) {
+            return None;
+        }
+        let mean = json(&estimates)?["mean"]["point_estimate"].as_f64()?;
+        Some((id, mean))
+    }
+
+    fn format_ns(ns: f64) -> String {
+        match ns {
+            ns if ns < 1e3 => format!("{ns:.1} ns"),
+            ns if ns < 1e6 => format!("{:.1} µs", ns / 1e3),
+            ns if ns < 1e9 => format!("{:.1} ms", ns / 1e6),
+            ns => format!("{:.2} s", ns / 1e9),
+        }
+    }
+}
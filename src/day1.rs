@@ -50,6 +50,21 @@ fn count_value<T: PartialEq>(data: &[T], value: T) -> usize {
     data.iter().filter(|&v| v == &value).count()
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    read_data(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(&read_data(input)?)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(&read_data(input)?)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> u32 {
     let (left, right) = read_data(input).unwrap();
@@ -122,7 +122,7 @@ impl Data {
     }
 
     /// Provide an iterator that is all of the trail heads in this grid.
-    fn trail_heads(&self) -> impl Iterator<Item = Cell> + '_ {
+    fn trail_heads(&self) -> impl Iterator<Item = Cell<'_>> + '_ {
         let grid = &self.grid;
         // This is simply a nested row, column flat map with a filter.
         grid.iter().enumerate().flat_map(move |(y, row)| {
@@ -172,7 +172,7 @@ impl Cell<'_> {
     /// The next trail positions are defined as the cells that are
     /// adjacent (left,right,up,down) to this cell and have a height
     /// that is one greater
-    fn next_trail_positions(&self) -> impl Iterator<Item = Cell> {
+    fn next_trail_positions(&self) -> impl Iterator<Item = Cell<'_>> {
         let cur_height = self.height();
 
         // Filter the directions to only those that are valid.
@@ -194,6 +194,21 @@ impl Cell<'_> {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
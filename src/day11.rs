@@ -9,12 +9,14 @@ pub const DAY: u32 = 11;
 fn blink_stone(stone: u64) -> Result<Vec<u64>> {
     let newstone = if stone == 0 {
         vec![1]
-    } else if stone.to_string().len() % 2 == 0 {
+    } else if stone.to_string().len().is_multiple_of(2) {
         let stone = stone.to_string();
         let (a, b) = stone.split_at(stone.to_string().len() / 2);
         vec![a.parse::<u64>()?, b.parse::<u64>()?]
     } else {
-        vec![stone * 2024]
+        vec![stone
+            .checked_mul(2024)
+            .ok_or_else(|| anyhow::anyhow!("stone {stone} grew too large"))?]
     };
     Ok(newstone)
 }
@@ -114,6 +116,21 @@ impl Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -269,6 +269,21 @@ impl Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
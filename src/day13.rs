@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use crate::Result;
+use crate::{Result, SumResults as _};
 use anyhow::Context as _;
 use aoc_runner_derive::aoc;
 use tracing::info;
@@ -9,30 +9,45 @@ use z3::ast::Ast as _;
 pub const DAY: u32 = 13;
 
 fn solve_part1_impl(input: &Data) -> Result<usize> {
-    Ok(input
+    input
         .machines
         .iter()
-        .filter_map(|m| solve_machine(m).ok())
-        .map(|(a, b)| a * 3 + b)
-        .sum())
+        .filter_map(|m| solve_machine(m).transpose())
+        .map(|presses| cost(presses?))
+        .sum_results()
 }
 
 fn solve_part2_impl(input: &Data) -> Result<usize> {
     const ADD_PRICE: usize = 10000000000000;
-    Ok(input
-        .machines
-        .iter()
-        .map(|m| Machine {
+    let mut total = 0usize;
+    for m in &input.machines {
+        let far = |v: usize| {
+            v.checked_add(ADD_PRICE)
+                .ok_or_else(|| anyhow::anyhow!("prize {v} is too far away"))
+        };
+        let m = Machine {
             button_a: m.button_a,
             button_b: m.button_b,
-            prize: (m.prize.0 + ADD_PRICE, m.prize.1 + ADD_PRICE),
-        })
-        .filter_map(|m| solve_machine(&m).ok())
-        .map(|(a, b)| a * 3 + b)
-        .sum())
+            prize: (far(m.prize.0)?, far(m.prize.1)?),
+        };
+        if let Some(presses) = solve_machine(&m)? {
+            total = total
+                .checked_add(cost(presses)?)
+                .ok_or_else(|| anyhow::anyhow!("total cost overflows"))?;
+        }
+    }
+    Ok(total)
+}
+
+/// Tokens spent on `a` presses of A and `b` presses of B.
+fn cost((a, b): (usize, usize)) -> Result<usize> {
+    a.checked_mul(3)
+        .and_then(|a| a.checked_add(b))
+        .ok_or_else(|| anyhow::anyhow!("{a} A and {b} B presses cost too much"))
 }
 
-fn solve_machine(m: &Machine) -> Result<(usize, usize)> {
+/// The cheapest presses that win the prize, or None if it can't be won.
+fn solve_machine(m: &Machine) -> Result<Option<(usize, usize)>> {
     use z3::{Config, Context};
     let cfg = Config::new();
     let ctx = Context::new(&cfg);
@@ -44,13 +59,16 @@ fn solve_machine(m: &Machine) -> Result<(usize, usize)> {
         z3::ast::Int::new_const(&ctx, "bpress"),
     );
 
-    let axmovement = z3::ast::Int::from_i64(&ctx, m.button_a.0 as i64);
-    let aymovement = z3::ast::Int::from_i64(&ctx, m.button_a.1 as i64);
-    let bxmovement = z3::ast::Int::from_i64(&ctx, m.button_b.0 as i64);
-    let bymovement = z3::ast::Int::from_i64(&ctx, m.button_b.1 as i64);
+    // z3 takes i64s, and a wrapped cast would solve a different machine.
+    let int =
+        |v: usize| -> Result<z3::ast::Int> { Ok(z3::ast::Int::from_i64(&ctx, i64::try_from(v)?)) };
+    let axmovement = int(m.button_a.0)?;
+    let aymovement = int(m.button_a.1)?;
+    let bxmovement = int(m.button_b.0)?;
+    let bymovement = int(m.button_b.1)?;
 
-    let aprize = z3::ast::Int::from_i64(&ctx, m.prize.0 as i64);
-    let bprize = z3::ast::Int::from_i64(&ctx, m.prize.1 as i64);
+    let aprize = int(m.prize.0)?;
+    let bprize = int(m.prize.1)?;
 
     let acost = z3::ast::Int::from_i64(&ctx, 3);
     let bcost = z3::ast::Int::from_i64(&ctx, 1);
@@ -62,6 +80,10 @@ fn solve_machine(m: &Machine) -> Result<(usize, usize)> {
 
     solver.assert(&first._eq(&aprize));
     solver.assert(&second._eq(&bprize));
+    // Buttons can't be pressed a negative number of times.
+    let zero = z3::ast::Int::from_i64(&ctx, 0);
+    solver.assert(&apress.ge(&zero));
+    solver.assert(&bpress.ge(&zero));
     solver.minimize(&total_cost);
 
     info!("solver: {:?}", solver);
@@ -75,13 +97,16 @@ fn solve_machine(m: &Machine) -> Result<(usize, usize)> {
                 .ok_or_else(|| anyhow::anyhow!("no model"))?;
             info!("model: {:?}", model);
             info!("stats: {:?}", solver.get_statistics());
-            let aans = model.eval(&apress, true).unwrap().as_i64().unwrap();
-            let bans = model.eval(&bpress, true).unwrap().as_i64().unwrap();
-            Ok((aans as usize, bans as usize))
-        }
-        z3::SatResult::Unsat => {
-            anyhow::bail!("unsat")
+            let presses = |press: &z3::ast::Int| -> Result<usize> {
+                let n = model
+                    .eval(press, true)
+                    .and_then(|n| n.as_i64())
+                    .ok_or_else(|| anyhow::anyhow!("no press count in model"))?;
+                Ok(usize::try_from(n)?)
+            };
+            Ok(Some((presses(&apress)?, presses(&bpress)?)))
         }
+        z3::SatResult::Unsat => Ok(None),
         z3::SatResult::Unknown => {
             anyhow::bail!("unknown")
         }
@@ -119,7 +144,7 @@ impl Data {
         let s = s.lines();
 
         let mut machines = vec![];
-        _ = read_machines(s, &mut machines);
+        read_machines(s, &mut machines)?;
 
         info!("Parsed data: {:?}", machines);
 
@@ -159,6 +184,21 @@ fn read_machines<'a>(
     Ok(())
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -188,4 +228,26 @@ mod tests {
             875318608908
         );
     }
+
+    #[test]
+    fn huge_values() {
+        // A press per unit of i64::MAX costs more than fits.
+        let input = "Button A: X+1, Y+1\nButton B: X+0, Y+0\n\
+                     Prize: X=9223372036854775807, Y=9223372036854775807";
+        assert!(solve_part1(input).is_err());
+        // Moving the prize out overflows.
+        let input = "Button A: X+1, Y+1\nButton B: X+1, Y+2\n\
+                     Prize: X=18446744073709551615, Y=1";
+        assert!(solve_part2(input).is_err());
+        // Doesn't fit the solver's i64s.
+        let input = "Button A: X+1, Y+1\nButton B: X+1, Y+2\n\
+                     Prize: X=9223372036854775808, Y=1";
+        assert!(solve_part1(input).is_err());
+    }
+
+    #[test]
+    fn garbage() {
+        assert!(solve_part1("garbage").is_err());
+        assert!(solve_part2("garbage").is_err());
+    }
 }
@@ -3,6 +3,8 @@ use anyhow::Context as _;
 use aoc_runner_derive::aoc;
 use std::{collections::HashSet, fmt::Display};
 
+pub mod frames;
+
 pub const DAY: u32 = 14;
 
 fn solve_part1_impl(input: &Data) -> Result<usize> {
@@ -33,52 +35,42 @@ fn solve_part1_impl(input: &Data) -> Result<usize> {
             .count()
     });
 
-    Ok(robots_in_quads
-        .inspect(|count| println!("{}", count))
-        .product())
-}
-
-fn print_board<'a>(robots: impl Iterator<Item = &'a Robot> + Clone, board_size: &Point) {
-    for y in 0..board_size.1 {
-        for x in 0..board_size.0 {
-            let robot_count = robots.clone().filter(|r| r.pos == (x, y)).count();
-            if robot_count > 0 {
-                print!("{}", robot_count);
-            } else {
-                print!(".");
-            }
-        }
-        println!();
-    }
+    Ok(robots_in_quads.product())
 }
 
 fn solve_part2_impl(input: &Data) -> Result<usize> {
     let robots = &input.robots;
     let board_size = (101, 103);
 
+    // Look for `any` for that has ROBOT_COUNT robots in a row
+    const ROBOT_COUNT: usize = 20;
+    anyhow::ensure!(
+        robots.len() >= ROBOT_COUNT,
+        "{} robots can never line up {ROBOT_COUNT} in a row",
+        robots.len()
+    );
     let mut robots = robots.clone();
-    for s in 1..50000 {
+    // Every robot is back where it started after this many seconds.
+    let period = board_size.0 * board_size.1;
+    for s in 1..=period {
         robots = robots
             .iter()
             .map(|r| r.step(&board_size, 1))
             .collect::<Result<Vec<_>>>()?;
-        // Look for `any` for that has ROBOT_COUNT robots in a row
         let mut all_rows = 0..board_size.1;
         let found = all_rows.any(|y| {
             let robots_in_row = robots.iter().filter(|r| r.pos.1 == y);
             let occupied_x_locations = robots_in_row.map(|r| r.pos.0).collect::<HashSet<_>>();
 
-            const ROBOT_COUNT: usize = 20;
             // Walk across the row, create a ROBOT_COUNT window, and check if a robot is in each
             (0..board_size.0 - ROBOT_COUNT)
                 .any(|x| (x..x + ROBOT_COUNT).all(|i| occupied_x_locations.contains(&(i))))
         });
         if found {
-            println!("Step {}", s);
-            print_board(robots.iter(), &board_size);
+            return Ok(s);
         }
     }
-    Ok(1)
+    anyhow::bail!("no tree within {period} seconds")
 }
 
 /// Solution to part 1
@@ -95,16 +87,17 @@ fn solve_part2(input: &str) -> Result<usize> {
     solve_part2_impl(&input)
 }
 
-type Point = (usize, usize);
-type Direction = (isize, isize);
+pub type Point = (usize, usize);
+pub type Direction = (isize, isize);
 
 #[derive(Clone, Debug)]
-struct Robot {
-    pos: Point,
-    vel: Direction,
+pub struct Robot {
+    pub pos: Point,
+    pub vel: Direction,
 }
 impl Robot {
-    fn step(&self, board_size: &Point, t: usize) -> Result<Robot> {
+    /// The robot as it will be after `t` seconds on a wrapping board.
+    pub fn step(&self, board_size: &Point, t: usize) -> Result<Robot> {
         let pos = (
             move_value(self.pos.0, self.vel.0, board_size.0, t).context("x")?,
             move_value(self.pos.1, self.vel.1, board_size.1, t).context("y")?,
@@ -118,9 +111,14 @@ pub fn move_value(value: usize, direction: isize, limit: usize, steps: usize) ->
     // It can go negative, so the wrapping needs to be done properly
     let value = isize::try_from(value)?;
     let limit = isize::try_from(limit)?;
+    anyhow::ensure!(limit > 0, "nowhere to move in a board of size 0");
     let steps = isize::try_from(steps)?;
 
-    Ok(((value + direction * steps) % limit + limit) as usize % limit as usize)
+    let moved = direction
+        .checked_mul(steps)
+        .and_then(|d| d.checked_add(value))
+        .ok_or_else(|| anyhow::anyhow!("moved too far"))?;
+    Ok(moved.rem_euclid(limit) as usize)
 }
 
 /// Problem input
@@ -133,7 +131,7 @@ impl Data {
     fn parse(s: &str) -> Result<Self> {
         // line looks like
         // p=7,6 v=-1,-3
-        let linere = regex::Regex::new(r"p=(-?\d+),(-?\d+) v=(-?\d+),(-?\d+)").unwrap();
+        let linere = regex::Regex::new(r"p=(-?\d+),(-?\d+) v=(-?\d+),(-?\d+)")?;
 
         let s = s.lines();
         let robots = s
@@ -152,6 +150,26 @@ impl Data {
     }
 }
 
+/// Parse the puzzle input into its robots, e.g. to hand to [`frames::FrameExporter`].
+pub fn parse_robots(input: &str) -> Result<Vec<Robot>> {
+    Ok(Data::parse(input)?.robots)
+}
+
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -179,7 +197,8 @@ mod tests {
 
     #[test]
     fn part2_example() {
-        //        assert_eq!(solve_part2(&test_data(super::DAY).unwrap()).unwrap(), 0);
+        // Too few robots to ever draw the tree.
+        assert!(solve_part2(&test_data(super::DAY).unwrap()).is_err());
     }
 
     #[test]
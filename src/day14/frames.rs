@@ -0,0 +1,553 @@
+//! Render robot swarms to image frames.
+//!
+//! Everything here is written by hand so the crate doesn't pick up any image
+//! dependencies.  Frames can be written as single PBM/PGM images, as a numbered
+//! strip of those images in a directory, or as one looping animated GIF.
+
+use super::{Point, Robot};
+use crate::Result;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// How many robots stacked on one tile before the tile is drawn fully black.
+const SATURATION: usize = 4;
+
+/// Format used for each image of a frame strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// Binary portable bitmap, a tile is black if any robot is on it.
+    Pbm,
+    /// Binary portable graymap, darker tiles have more robots on them.
+    Pgm,
+}
+
+impl FrameFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            FrameFormat::Pbm => "pbm",
+            FrameFormat::Pgm => "pgm",
+        }
+    }
+}
+
+/// A single rendered image.
+///
+/// Pixels hold an ink level, 0 is an empty (white) pixel and 255 is fully
+/// black.  The individual file formats map this onto their own representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    fn new(width: usize, height: usize) -> Self {
+        Frame {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    /// Ink level at the given pixel.
+    pub fn ink(&self, xy: &Point) -> Option<u8> {
+        if xy.0 >= self.width {
+            return None;
+        }
+        self.pixels.get(xy.1 * self.width + xy.0).copied()
+    }
+
+    fn fill(&mut self, xy: &Point, size: &Point, ink: u8) {
+        for y in xy.1..(xy.1 + size.1).min(self.height) {
+            for x in xy.0..(xy.0 + size.0).min(self.width) {
+                self.pixels[y * self.width + x] = ink;
+            }
+        }
+    }
+
+    /// Write the frame as a binary (P4) PBM.
+    pub fn write_pbm(&self, mut w: impl Write) -> Result<()> {
+        write!(w, "P4\n{} {}\n", self.width, self.height)?;
+        for row in self.pixels.chunks(self.width.max(1)) {
+            // Each row is padded out to a whole number of bytes, MSB first.
+            let packed = row
+                .chunks(8)
+                .map(|bits| {
+                    bits.iter()
+                        .enumerate()
+                        .filter(|(_, ink)| **ink > 0)
+                        .fold(0u8, |byte, (i, _)| byte | (0x80 >> i))
+                })
+                .collect::<Vec<_>>();
+            w.write_all(&packed)?;
+        }
+        Ok(())
+    }
+
+    /// Write the frame as a binary (P5) PGM.
+    pub fn write_pgm(&self, mut w: impl Write) -> Result<()> {
+        write!(w, "P5\n{} {}\n255\n", self.width, self.height)?;
+        let gray = self.pixels.iter().map(|ink| 255 - ink).collect::<Vec<_>>();
+        w.write_all(&gray)?;
+        Ok(())
+    }
+
+    /// Write the frame in the given format.
+    pub fn write(&self, format: FrameFormat, w: impl Write) -> Result<()> {
+        match format {
+            FrameFormat::Pbm => self.write_pbm(w),
+            FrameFormat::Pgm => self.write_pgm(w),
+        }
+    }
+}
+
+/// Renders robots on a board into frames.
+#[derive(Debug, Clone)]
+pub struct FrameExporter {
+    /// Size of the board the robots wrap around on.
+    pub board_size: Point,
+    /// Every board tile is drawn as a `scale` x `scale` block of pixels.
+    pub scale: usize,
+    /// Draw a `t=<time>` label above the board.
+    pub time_labels: bool,
+}
+
+impl FrameExporter {
+    pub fn new(board_size: Point) -> Self {
+        FrameExporter {
+            board_size,
+            scale: 1,
+            time_labels: false,
+        }
+    }
+
+    pub fn with_scale(self, scale: usize) -> Self {
+        FrameExporter { scale, ..self }
+    }
+
+    pub fn with_time_labels(self, time_labels: bool) -> Self {
+        FrameExporter {
+            time_labels,
+            ..self
+        }
+    }
+
+    /// Height in pixels of the label strip drawn above the board.
+    fn label_height(&self) -> usize {
+        if self.time_labels {
+            (GLYPH_SIZE.1 + 2) * self.scale
+        } else {
+            0
+        }
+    }
+
+    /// Render the robots as they are after `t` seconds.
+    pub fn render(&self, robots: &[Robot], t: usize) -> Result<Frame> {
+        anyhow::ensure!(self.scale > 0, "scale must be at least 1");
+        anyhow::ensure!(
+            self.board_size.0 > 0 && self.board_size.1 > 0,
+            "board must not be empty"
+        );
+
+        let mut counts = vec![0usize; self.board_size.0 * self.board_size.1];
+        for robot in robots {
+            let robot = robot.step(&self.board_size, t)?;
+            counts[robot.pos.1 * self.board_size.0 + robot.pos.0] += 1;
+        }
+
+        let label_height = self.label_height();
+        let mut frame = Frame::new(
+            self.board_size.0 * self.scale,
+            self.board_size.1 * self.scale + label_height,
+        );
+
+        if self.time_labels {
+            draw_text(&mut frame, &format!("t={}", t), self.scale);
+        }
+
+        for (i, count) in counts.into_iter().enumerate().filter(|(_, c)| *c > 0) {
+            let (x, y) = (i % self.board_size.0, i / self.board_size.0);
+            let ink = (count.min(SATURATION) * 255 / SATURATION) as u8;
+            frame.fill(
+                &(x * self.scale, y * self.scale + label_height),
+                &(self.scale, self.scale),
+                ink,
+            );
+        }
+        Ok(frame)
+    }
+
+    /// Write one image per time into `dir`, named `frame_<t>.<ext>`.
+    /// Returns the paths written in the same order as `times`.
+    pub fn write_strip(
+        &self,
+        robots: &[Robot],
+        times: impl IntoIterator<Item = usize>,
+        format: FrameFormat,
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        times
+            .into_iter()
+            .map(|t| {
+                let path = dir.join(format!("frame_{:06}.{}", t, format.extension()));
+                let file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                self.render(robots, t)?.write(format, file)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// Write an animated, looping GIF with one frame per time.
+    /// `delay` is the time each frame is shown in hundredths of a second.
+    pub fn write_gif(
+        &self,
+        robots: &[Robot],
+        times: impl IntoIterator<Item = usize>,
+        delay: u16,
+        mut w: impl Write,
+    ) -> Result<()> {
+        let mut times = times.into_iter().peekable();
+        let first = times
+            .peek()
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("no frames to write"))?;
+        let first = self.render(robots, first)?;
+        let width = u16::try_from(first.width)?;
+        let height = u16::try_from(first.height)?;
+
+        w.write_all(b"GIF89a")?;
+        w.write_all(&width.to_le_bytes())?;
+        w.write_all(&height.to_le_bytes())?;
+        // Global color table of 2^GIF_BITS entries, 8 bit color resolution.
+        w.write_all(&[0x80 | 0x70 | (GIF_BITS - 1), 0, 0])?;
+        for i in 0..GIF_COLORS {
+            let gray = 255 - i * (255 / (GIF_COLORS - 1));
+            w.write_all(&[gray, gray, gray])?;
+        }
+        // Loop forever.
+        w.write_all(&[0x21, 0xff, 0x0b])?;
+        w.write_all(b"NETSCAPE2.0")?;
+        w.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        for t in times {
+            let frame = self.render(robots, t)?;
+            let [d0, d1] = delay.to_le_bytes();
+            // Graphic control extension carrying the frame delay.
+            w.write_all(&[0x21, 0xf9, 0x04, 0x00, d0, d1, 0x00, 0x00])?;
+            // Image descriptor covering the whole screen.
+            w.write_all(&[0x2c, 0, 0, 0, 0])?;
+            w.write_all(&width.to_le_bytes())?;
+            w.write_all(&height.to_le_bytes())?;
+            w.write_all(&[0x00, GIF_BITS])?;
+
+            let indices = frame
+                .pixels
+                .iter()
+                .map(|ink| ink / (255 / (GIF_COLORS - 1)));
+            for block in lzw_encode(indices, GIF_BITS).chunks(255) {
+                w.write_all(&[block.len() as u8])?;
+                w.write_all(block)?;
+            }
+            w.write_all(&[0x00])?;
+        }
+        w.write_all(&[0x3b])?;
+        Ok(())
+    }
+}
+
+/// Number of bits per palette index in the GIF output.
+const GIF_BITS: u8 = 4;
+const GIF_COLORS: u8 = 1 << GIF_BITS;
+
+/// GIF flavoured LZW compression of palette indices.
+fn lzw_encode(indices: impl Iterator<Item = u8>, min_code_size: u8) -> Vec<u8> {
+    const MAX_CODE: u16 = 4096;
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut out = BitWriter::default();
+    let mut table = std::collections::HashMap::<(u16, u8), u16>::new();
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end + 1;
+
+    out.write(clear, code_size);
+
+    // Emit a code, growing the code size once the decoder's table will have
+    // filled the current width.
+    let emit = |out: &mut BitWriter, code: u16, code_size: &mut u8, next_code: u16| {
+        out.write(code, *code_size);
+        if next_code >= 1 << *code_size && *code_size < 12 {
+            *code_size += 1;
+        }
+    };
+
+    let mut current: Option<u16> = None;
+    for k in indices {
+        let Some(prefix) = current else {
+            current = Some(k as u16);
+            continue;
+        };
+        if let Some(&code) = table.get(&(prefix, k)) {
+            current = Some(code);
+            continue;
+        }
+        emit(&mut out, prefix, &mut code_size, next_code);
+        if next_code < MAX_CODE {
+            table.insert((prefix, k), next_code);
+            next_code += 1;
+        } else {
+            out.write(clear, code_size);
+            table.clear();
+            code_size = min_code_size + 1;
+            next_code = end + 1;
+        }
+        current = Some(k as u16);
+    }
+    if let Some(prefix) = current {
+        emit(&mut out, prefix, &mut code_size, next_code);
+    }
+    out.write(end, code_size);
+    out.finish()
+}
+
+/// Packs variable width codes least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Size of a glyph in the label font, in board tiles.
+const GLYPH_SIZE: Point = (3, 5);
+
+/// 3x5 bitmap font, one row per entry with the MSB of 3 bits on the left.
+fn glyph(c: char) -> Option<[u8; 5]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        't' => [0b010, 0b111, 0b010, 0b010, 0b011],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        _ => return None,
+    })
+}
+
+/// Draw text in the top left corner with a one tile margin.
+/// Characters without a glyph are skipped, as is anything that runs off the
+/// right of the frame.
+fn draw_text(frame: &mut Frame, text: &str, scale: usize) {
+    let glyphs = text.chars().filter_map(glyph);
+    for (i, rows) in glyphs.enumerate() {
+        let left = (1 + i * (GLYPH_SIZE.0 + 1)) * scale;
+        for (y, row) in rows.iter().enumerate() {
+            for x in 0..GLYPH_SIZE.0 {
+                if row & (0b100 >> x) != 0 {
+                    frame.fill(&(left + x * scale, (1 + y) * scale), &(scale, scale), 255);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestRng;
+
+    /// A GIF LZW decoder written from the spec, independent of the encoder.
+    /// Returns the indices and how many clear codes it read.
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> (Vec<u8>, usize) {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let initial = (0..clear)
+            .map(|i| vec![i as u8])
+            .chain([vec![], vec![]])
+            .collect::<Vec<_>>();
+        let mut table = initial.clone();
+        let mut code_size = min_code_size + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let (mut out, mut clears) = (vec![], 0);
+        let mut bit = 0;
+        loop {
+            let code = (0..code_size as usize).fold(0, |code, i| {
+                let b = (data[(bit + i) / 8] >> ((bit + i) % 8)) & 1;
+                code | (b as usize) << i
+            });
+            bit += code_size as usize;
+            if code == clear {
+                table = initial.clone();
+                code_size = min_code_size + 1;
+                prev = None;
+                clears += 1;
+                continue;
+            }
+            if code == end {
+                return (out, clears);
+            }
+            let entry = match (table.get(code), &prev) {
+                (Some(entry), _) => entry.clone(),
+                // The code the encoder is adding as it emits it.
+                (None, Some(p)) if code == table.len() => [p.as_slice(), &p[..1]].concat(),
+                _ => panic!("code {code} not in a table of {}", table.len()),
+            };
+            out.extend(&entry);
+            if let Some(p) = prev {
+                if table.len() < 4096 {
+                    table.push([p.as_slice(), &entry[..1]].concat());
+                }
+            }
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            prev = Some(entry);
+        }
+    }
+
+    fn robots() -> Vec<Robot> {
+        vec![
+            Robot {
+                pos: (0, 0),
+                vel: (1, 0),
+            },
+            Robot {
+                pos: (2, 1),
+                vel: (0, 0),
+            },
+            Robot {
+                pos: (2, 1),
+                vel: (0, 0),
+            },
+        ]
+    }
+
+    #[test]
+    fn render_scaled() {
+        let frame = FrameExporter::new((4, 2))
+            .with_scale(2)
+            .render(&robots(), 1)
+            .unwrap();
+        assert_eq!((frame.width, frame.height), (8, 4));
+        // First robot has moved one tile right.
+        assert_eq!(frame.ink(&(0, 0)), Some(0));
+        assert_eq!(frame.ink(&(2, 0)), Some(255 / SATURATION as u8));
+        assert_eq!(frame.ink(&(3, 1)), Some(255 / SATURATION as u8));
+        // Two robots stacked are darker.
+        assert_eq!(frame.ink(&(4, 2)), Some((2 * 255 / SATURATION) as u8));
+    }
+
+    #[test]
+    fn pbm_and_pgm() {
+        let frame = FrameExporter::new((4, 2)).render(&robots(), 0).unwrap();
+
+        let mut pbm = vec![];
+        frame.write_pbm(&mut pbm).unwrap();
+        assert_eq!(pbm, b"P4\n4 2\n\x80\x20");
+
+        let mut pgm = vec![];
+        frame.write_pgm(&mut pgm).unwrap();
+        assert_eq!(&pgm[..11], b"P5\n4 2\n255\n");
+        assert_eq!(pgm.len(), 11 + 8);
+    }
+
+    #[test]
+    fn time_label() {
+        let exporter = FrameExporter::new((20, 3)).with_time_labels(true);
+        let frame = exporter.render(&[], 7).unwrap();
+        assert_eq!(frame.height, 3 + GLYPH_SIZE.1 + 2);
+        // Top left pixel of the `t` glyph is empty, its stem is inked.
+        assert_eq!(frame.ink(&(1, 1)), Some(0));
+        assert_eq!(frame.ink(&(2, 1)), Some(255));
+    }
+
+    #[test]
+    fn gif_framing() {
+        let mut gif = vec![];
+        FrameExporter::new((101, 103))
+            .write_gif(&robots(), 0..3, 10, &mut gif)
+            .unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..10], &[101, 0, 103, 0]);
+        assert_eq!(gif.last(), Some(&0x3b));
+
+        // Each frame's image data decodes back to the rendered frame.
+        let exporter = FrameExporter::new((101, 103));
+        let mut at = 13 + 3 * GIF_COLORS as usize + 19;
+        for t in 0..3 {
+            // Graphic control extension, image descriptor, min code size.
+            at += 8 + 10;
+            assert_eq!(gif[at], GIF_BITS);
+            at += 1;
+            let mut data = vec![];
+            while gif[at] != 0 {
+                let len = gif[at] as usize;
+                data.extend(&gif[at + 1..at + 1 + len]);
+                at += 1 + len;
+            }
+            at += 1;
+            let frame = exporter.render(&robots(), t).unwrap();
+            let expected = frame
+                .pixels
+                .iter()
+                .map(|ink| ink / (255 / (GIF_COLORS - 1)))
+                .collect::<Vec<_>>();
+            assert_eq!(lzw_decode(&data, GIF_BITS).0, expected, "frame {t}");
+        }
+        assert_eq!(at, gif.len() - 1);
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let mut rng = TestRng(26);
+        let random = (0..200_000)
+            .map(|_| rng.below(16) as u8)
+            .collect::<Vec<_>>();
+        let cases = [
+            vec![],
+            vec![3],
+            vec![0; 10_000],
+            (0..5000).map(|i| (i % 7) as u8).collect(),
+            random,
+        ];
+        for indices in cases {
+            let encoded = lzw_encode(indices.iter().copied(), GIF_BITS);
+            let (decoded, clears) = lzw_decode(&encoded, GIF_BITS);
+            assert_eq!(decoded, indices);
+            if indices.len() == 200_000 {
+                // Random data fills the 4096 code table many times over.
+                assert!(clears > 10, "{clears} clears");
+            }
+        }
+    }
+}
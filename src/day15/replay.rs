@@ -0,0 +1,247 @@
+//! Step through a warehouse script one move at a time.
+//!
+//! A [`Simulation`] keeps a log of what every applied move did, so it can be
+//! walked backwards with undo, forwards again with redo, or jumped to any move
+//! without rerunning the script from scratch.
+
+use super::{
+    warehouse::{MoveResult, ObjectId, Warehouse},
+    Movement,
+};
+use crate::{parse_line, Result};
+use std::io::Write;
+
+/// What happened when a single move was applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveRecord {
+    /// Index of the move in the script.
+    pub index: usize,
+    pub movement: Movement,
+    pub result: MoveResult,
+}
+
+impl MoveRecord {
+    pub fn succeeded(&self) -> bool {
+        self.result.moved()
+    }
+
+    /// Crates that moved along with the robot.
+    pub fn pushed(&self) -> &[ObjectId] {
+        match &self.result {
+            MoveResult::Blocked => &[],
+            MoveResult::Moved { pushed } => pushed,
+        }
+    }
+}
+
+/// A warehouse and the script of moves being applied to it.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    warehouse: Warehouse,
+    movements: Vec<Movement>,
+    /// One record per applied move, so `log.len()` is the current position.
+    log: Vec<MoveRecord>,
+}
+
+impl Simulation {
+    pub fn new(warehouse: Warehouse, movements: Vec<Movement>) -> Self {
+        Simulation {
+            warehouse,
+            movements,
+            log: vec![],
+        }
+    }
+
+    /// Parse a puzzle input: a map, a blank line and then the moves.
+    pub fn parse(s: &str) -> Result<Self> {
+        let (map, movements) = s
+            .split_once("\n\n")
+            .ok_or_else(|| anyhow::anyhow!("missing blank line"))?;
+        let movements = movements
+            .lines()
+            .flat_map(parse_line)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Simulation::new(Warehouse::parse(map)?, movements))
+    }
+
+    pub fn warehouse(&self) -> &Warehouse {
+        &self.warehouse
+    }
+
+    pub fn movements(&self) -> &[Movement] {
+        &self.movements
+    }
+
+    /// Number of moves applied so far.
+    pub fn position(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Records of every move applied so far.
+    pub fn log(&self) -> &[MoveRecord] {
+        &self.log
+    }
+
+    /// Apply the next move of the script, returning None at the end.
+    pub fn redo(&mut self) -> Option<&MoveRecord> {
+        let index = self.position();
+        let movement = *self.movements.get(index)?;
+        let result = self.warehouse.push(&movement.direction());
+        self.log.push(MoveRecord {
+            index,
+            movement,
+            result,
+        });
+        self.log.last()
+    }
+
+    /// Take back the last applied move, returning None at the start.
+    pub fn undo(&mut self) -> Result<Option<MoveRecord>> {
+        let Some(record) = self.log.pop() else {
+            return Ok(None);
+        };
+        self.warehouse
+            .undo(&record.movement.direction(), &record.result)?;
+        Ok(Some(record))
+    }
+
+    /// Replace the rest of the script with `movement` and apply it.
+    /// Like typing after an undo in an editor, the old redo history is lost.
+    pub fn apply(&mut self, movement: Movement) -> &MoveRecord {
+        self.movements.truncate(self.position());
+        self.movements.push(movement);
+        self.redo().expect("a move was just added")
+    }
+
+    /// Undo or redo until exactly `n` moves have been applied.
+    pub fn seek(&mut self, n: usize) -> Result<()> {
+        anyhow::ensure!(
+            n <= self.movements.len(),
+            "move {} is past the end of the script ({} moves)",
+            n,
+            self.movements.len()
+        );
+        while self.position() > n {
+            self.undo()?;
+        }
+        while self.position() < n {
+            self.redo();
+        }
+        Ok(())
+    }
+
+    /// Apply every remaining move.
+    pub fn run_to_end(&mut self) {
+        while self.redo().is_some() {}
+    }
+
+    /// Write the log as tab separated text, one applied move per line:
+    /// index, movement, `moved` or `blocked` and the comma separated ids of
+    /// pushed crates.
+    pub fn write_log(&self, mut w: impl Write) -> Result<()> {
+        for record in self.log.iter() {
+            let pushed = record
+                .pushed()
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            let status = if record.succeeded() {
+                "moved"
+            } else {
+                "blocked"
+            };
+            writeln!(
+                w,
+                "{}\t{}\t{}\t{}",
+                record.index, record.movement, status, pushed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The first move at which two simulations stop agreeing, either because the
+/// moves had different results or left the warehouses in different states.
+///
+/// Both are replayed from the start.  Returns None if they agree all the way
+/// to the end of both scripts.
+pub fn first_divergence(a: &Simulation, b: &Simulation) -> Result<Option<usize>> {
+    let mut a = a.clone();
+    let mut b = b.clone();
+    a.seek(0)?;
+    b.seek(0)?;
+    if a.warehouse != b.warehouse {
+        return Ok(Some(0));
+    }
+
+    loop {
+        let index = a.position();
+        let ra = a.redo().map(|r| r.result.clone());
+        let rb = b.redo().map(|r| r.result.clone());
+        match (ra, rb) {
+            (None, None) => return Ok(None),
+            (Some(ra), Some(rb)) if ra == rb && a.warehouse == b.warehouse => {}
+            _ => return Ok(Some(index)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day15::warehouse::gps, test_data};
+
+    fn sample() -> Simulation {
+        Simulation::parse(&test_data(super::super::DAY).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn undo_to_start() {
+        let mut sim = sample();
+        let start = sim.warehouse().clone();
+        sim.run_to_end();
+        assert_eq!(sim.warehouse().score(gps), 10092);
+        assert_eq!(sim.position(), sim.movements().len());
+
+        sim.seek(0).unwrap();
+        assert_eq!(sim.warehouse(), &start);
+        assert!(sim.undo().unwrap().is_none());
+    }
+
+    #[test]
+    fn seek_matches_replay() {
+        let mut sim = sample();
+        sim.seek(300).unwrap();
+        let mid = sim.warehouse().clone();
+        sim.run_to_end();
+        sim.seek(300).unwrap();
+        assert_eq!(sim.warehouse(), &mid);
+
+        let mut fresh = sample();
+        for _ in 0..300 {
+            fresh.redo();
+        }
+        assert_eq!(fresh.warehouse(), &mid);
+        assert!(sim.seek(sim.movements().len() + 1).is_err());
+    }
+
+    #[test]
+    fn log_and_divergence() {
+        let mut sim = Simulation::parse("#####\n#@O.#\n#####\n\n>>><").unwrap();
+        sim.run_to_end();
+        let mut log = vec![];
+        sim.write_log(&mut log).unwrap();
+        assert_eq!(
+            String::from_utf8(log).unwrap(),
+            "0\t>\tmoved\t0\n1\t>\tblocked\t\n2\t>\tblocked\t\n3\t<\tmoved\t\n"
+        );
+
+        let mut edited = sim.clone();
+        edited.seek(2).unwrap();
+        edited.apply(Movement::Left);
+        assert_eq!(first_divergence(&sim, &sim).unwrap(), None);
+        // The third move is blocked in one and moves in the other.
+        assert_eq!(first_divergence(&sim, &edited).unwrap(), Some(2));
+    }
+}
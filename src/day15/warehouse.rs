@@ -0,0 +1,494 @@
+//! A push-block warehouse where crates can be any polyomino.
+//!
+//! The part 1 and part 2 maps are special cases of this: `O` is a one cell
+//! crate and `[]` is a two cell crate.  Level files can also describe crates of
+//! any shape with letters, where all orthogonally connected cells that share a
+//! letter make up one crate.
+//!
+//! ```text
+//! #######
+//! #.aa..#
+//! #.a.bbb
+//! #@....#
+//! #######
+//! ```
+
+use super::Movement;
+use crate::{add_xy, Direction, GetCell as _, Position, Result};
+use std::{collections::VecDeque, fmt::Display};
+
+/// Identifier for a crate, an index into [`Warehouse::objects`].
+pub type ObjectId = usize;
+
+/// What is in a single position of the warehouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Wall,
+    Empty,
+    Robot,
+    Object(ObjectId),
+}
+
+/// A crate made up of one or more cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Object {
+    pub id: ObjectId,
+    /// Absolute positions of every cell of the crate.
+    pub cells: Vec<Position>,
+}
+
+impl Object {
+    /// Top left corner of the crate's bounding box.
+    pub fn corner(&self) -> Position {
+        let x = self.cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let y = self.cells.iter().map(|c| c.1).min().unwrap_or(0);
+        (x, y)
+    }
+}
+
+/// The puzzle's GPS coordinate, measured from the top left corner of a crate.
+pub fn gps(object: &Object) -> usize {
+    let (x, y) = object.corner();
+    x + 100 * y
+}
+
+/// Result of asking the robot to move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveResult {
+    /// Something (a wall or the edge of the map) stopped the move; nothing changed.
+    Blocked,
+    /// The robot moved, pushing these crates along with it.
+    Moved { pushed: Vec<ObjectId> },
+}
+
+impl MoveResult {
+    pub fn moved(&self) -> bool {
+        matches!(self, MoveResult::Moved { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warehouse {
+    tiles: Vec<Vec<Tile>>,
+    objects: Vec<Object>,
+    robot: Position,
+}
+
+impl Warehouse {
+    /// Parse a map made of `#`, `.`, `@`, `O`, `[]` and lettered crates.
+    pub fn parse(s: &str) -> Result<Self> {
+        let chars = s
+            .lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut tiles = chars
+            .iter()
+            .map(|row| vec![Tile::Empty; row.len()])
+            .collect::<Vec<_>>();
+        let mut objects = vec![];
+        let mut robot = None;
+
+        for (y, row) in chars.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                if tiles[y][x] != Tile::Empty {
+                    // Already claimed as part of a larger crate.
+                    continue;
+                }
+                let cells = match c {
+                    '#' => {
+                        tiles[y][x] = Tile::Wall;
+                        continue;
+                    }
+                    '.' => continue,
+                    '@' => {
+                        anyhow::ensure!(robot.is_none(), "more than one robot");
+                        tiles[y][x] = Tile::Robot;
+                        robot = Some((x, y));
+                        continue;
+                    }
+                    'O' => vec![(x, y)],
+                    '[' => {
+                        anyhow::ensure!(
+                            row.get(x + 1) == Some(&']'),
+                            "unmatched [ at {:?}",
+                            (x, y)
+                        );
+                        vec![(x, y), (x + 1, y)]
+                    }
+                    ']' => anyhow::bail!("unmatched ] at {:?}", (x, y)),
+                    c if c.is_ascii_alphabetic() => connected_cells(&chars, (x, y)),
+                    c => anyhow::bail!("invalid cell {}", c),
+                };
+                let id = objects.len();
+                for cell in cells.iter() {
+                    tiles[cell.1][cell.0] = Tile::Object(id);
+                }
+                objects.push(Object { id, cells });
+            }
+        }
+
+        let robot = robot.ok_or_else(|| anyhow::anyhow!("robot not found"))?;
+        Ok(Warehouse {
+            tiles,
+            objects,
+            robot,
+        })
+    }
+
+    pub fn robot(&self) -> Position {
+        self.robot
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    pub fn tile(&self, xy: &Position) -> Option<Tile> {
+        self.tiles.as_slice().get_cell(xy).copied()
+    }
+
+    /// Stretch the warehouse by a whole factor in each axis.
+    ///
+    /// Every cell becomes a `factor.0` by `factor.1` block.  Walls and crates
+    /// fill their whole block while the robot keeps to the top left of its
+    /// block.  Part 2 of the puzzle is `scale((2, 1))`.
+    pub fn scale(&self, factor: (usize, usize)) -> Result<Self> {
+        anyhow::ensure!(factor.0 > 0 && factor.1 > 0, "scale must be at least 1");
+
+        let block = |xy: &Position| {
+            let origin = (xy.0 * factor.0, xy.1 * factor.1);
+            (0..factor.1)
+                .flat_map(move |dy| (0..factor.0).map(move |dx| (origin.0 + dx, origin.1 + dy)))
+        };
+
+        let mut tiles = self
+            .tiles
+            .iter()
+            .flat_map(|row| std::iter::repeat_n(vec![Tile::Empty; row.len() * factor.0], factor.1))
+            .collect::<Vec<_>>();
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if matches!(tile, Tile::Wall | Tile::Object(_)) {
+                    for (bx, by) in block(&(x, y)) {
+                        tiles[by][bx] = *tile;
+                    }
+                }
+            }
+        }
+
+        let robot = (self.robot.0 * factor.0, self.robot.1 * factor.1);
+        tiles[robot.1][robot.0] = Tile::Robot;
+
+        let objects = self
+            .objects
+            .iter()
+            .map(|o| Object {
+                id: o.id,
+                cells: o.cells.iter().flat_map(block).collect(),
+            })
+            .collect();
+
+        Ok(Warehouse {
+            tiles,
+            objects,
+            robot,
+        })
+    }
+
+    /// Every crate that would have to move if the robot moved in `direction`,
+    /// or None if the push is blocked.
+    ///
+    /// This is a breadth first search over crates that touch each other in
+    /// the direction of the push.  Most moves are into an empty tile or a
+    /// wall, and those return before anything is allocated.
+    fn pushed_by(&self, direction: &Direction) -> Option<Vec<ObjectId>> {
+        let next = add_xy(&self.robot, direction)?;
+        match self.tile(&next)? {
+            Tile::Wall => return None,
+            Tile::Empty | Tile::Robot => return Some(vec![]),
+            Tile::Object(_) => {}
+        }
+
+        // Pushes move a handful of crates, so a linear scan of `pushed`
+        // beats hashing.
+        let mut pushed = vec![];
+        let mut frontier = VecDeque::from([next]);
+        while let Some(xy) = frontier.pop_front() {
+            match self.tile(&xy)? {
+                Tile::Wall => return None,
+                Tile::Empty | Tile::Robot => {}
+                Tile::Object(id) => {
+                    if !pushed.contains(&id) {
+                        pushed.push(id);
+                        for cell in self.objects[id].cells.iter() {
+                            frontier.push_back(add_xy(cell, direction)?);
+                        }
+                    }
+                }
+            }
+        }
+        Some(pushed)
+    }
+
+    /// Try to move the robot one step, pushing any crates in its way.
+    ///
+    /// Either the robot and every crate it pushes moves, or nothing does.
+    pub fn push(&mut self, direction: &Direction) -> MoveResult {
+        let Some(pushed) = self.pushed_by(direction) else {
+            return MoveResult::Blocked;
+        };
+
+        // Every destination was checked by pushed_by, so this can't fail.
+        self.shift(&pushed, direction)
+            .expect("destinations checked by pushed_by");
+
+        MoveResult::Moved { pushed }
+    }
+
+    /// Reverse a move that [`Warehouse::push`] made in `direction`.
+    ///
+    /// `result` must be what that push returned, and the warehouse must not
+    /// have changed since.
+    pub fn undo(&mut self, direction: &Direction, result: &MoveResult) -> Result<()> {
+        let MoveResult::Moved { pushed } = result else {
+            return Ok(());
+        };
+        let back = (-direction.0, -direction.1);
+        let robot = self.robot;
+        let behind = add_xy(&robot, &back)
+            .ok_or_else(|| anyhow::anyhow!("robot cannot step back from {robot:?}"))?;
+        // Lift the robot first: a crate wrapped around it moves back onto its
+        // tile, and may only clear the tile behind it by moving.
+        self.tiles[robot.1][robot.0] = Tile::Empty;
+        if self.shift_objects(pushed, &back).is_none() {
+            self.tiles[robot.1][robot.0] = Tile::Robot;
+            anyhow::bail!("crates cannot move back");
+        }
+        if self.tile(&behind) != Some(Tile::Empty) {
+            self.shift_objects(pushed, direction)
+                .expect("crates were just moved from there");
+            self.tiles[robot.1][robot.0] = Tile::Robot;
+            anyhow::bail!("robot cannot step back from {robot:?}");
+        }
+        self.robot = behind;
+        self.tiles[behind.1][behind.0] = Tile::Robot;
+        Ok(())
+    }
+
+    /// Move the given crates and then the robot one step in `direction`.
+    /// Destinations must already be known to be free.
+    fn shift(&mut self, ids: &[ObjectId], direction: &Direction) -> Option<()> {
+        let robot = self.robot;
+        let next = add_xy(&robot, direction)?;
+        // Lift the robot first: a crate wrapped around it moves onto its tile.
+        self.tiles[robot.1][robot.0] = Tile::Empty;
+        if self.shift_objects(ids, direction).is_none() {
+            self.tiles[robot.1][robot.0] = Tile::Robot;
+            return None;
+        }
+        self.robot = next;
+        self.tiles[next.1][next.0] = Tile::Robot;
+        Some(())
+    }
+
+    fn shift_objects(&mut self, ids: &[ObjectId], direction: &Direction) -> Option<()> {
+        let moved = ids
+            .iter()
+            .map(|id| {
+                self.objects[*id]
+                    .cells
+                    .iter()
+                    .map(|cell| add_xy(cell, direction).filter(|xy| self.tile(xy).is_some()))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        // Lift every crate off the map before putting them back down so
+        // crates can't overwrite each other.
+        for id in ids.iter() {
+            for cell in self.objects[*id].cells.iter() {
+                self.tiles[cell.1][cell.0] = Tile::Empty;
+            }
+        }
+        for (id, cells) in ids.iter().zip(moved) {
+            for cell in cells.iter() {
+                self.tiles[cell.1][cell.0] = Tile::Object(*id);
+            }
+            self.objects[*id].cells = cells;
+        }
+        Some(())
+    }
+
+    /// Apply every movement in order.
+    pub fn run<'a>(&mut self, movements: impl IntoIterator<Item = &'a Movement>) {
+        for m in movements {
+            self.push(&m.direction());
+        }
+    }
+
+    /// Sum a score over every crate, e.g. [`gps`].
+    pub fn score(&self, scoring: impl Fn(&Object) -> usize) -> usize {
+        self.objects.iter().map(scoring).sum()
+    }
+}
+
+/// All cells orthogonally connected to `start` with the same character.
+fn connected_cells(chars: &[Vec<char>], start: Position) -> Vec<Position> {
+    let c = chars[start.1][start.0];
+    let mut cells = vec![start];
+    let mut frontier = vec![start];
+    while let Some(xy) = frontier.pop() {
+        for d in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+            let Some(next) = add_xy(&xy, &d) else {
+                continue;
+            };
+            if chars.get_cell(&next) == Some(&c) && !cells.contains(&next) {
+                cells.push(next);
+                frontier.push(next);
+            }
+        }
+    }
+    cells.sort_by_key(|(x, y)| (*y, *x));
+    cells
+}
+
+impl Display for Warehouse {
+    /// Renders single cells as `O` and horizontal pairs as `[]`.  Other shapes
+    /// are lettered by id, so two neighbouring crates may share a letter.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.tiles.iter() {
+            for (x, tile) in row.iter().enumerate() {
+                let c = match tile {
+                    Tile::Wall => '#',
+                    Tile::Empty => '.',
+                    Tile::Robot => '@',
+                    Tile::Object(id) => {
+                        let cells = &self.objects[*id].cells;
+                        match cells.as_slice() {
+                            [_] => 'O',
+                            [a, b] if a.1 == b.1 && a.0 + 1 == b.0 => {
+                                if a.0 == x {
+                                    '['
+                                } else {
+                                    ']'
+                                }
+                            }
+                            _ => (b'a' + (*id % 26) as u8) as char,
+                        }
+                    }
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day15::Data, test_data};
+
+    fn sample() -> (Warehouse, Vec<Movement>) {
+        let data = Data::parse(&test_data(super::super::DAY).unwrap()).unwrap();
+        (data.warehouse, data.movements)
+    }
+
+    #[test]
+    fn matches_part1() {
+        let (mut warehouse, movements) = sample();
+        warehouse.run(movements.iter());
+        assert_eq!(warehouse.score(gps), 10092);
+    }
+
+    #[test]
+    fn matches_part2() {
+        let (warehouse, movements) = sample();
+        let mut warehouse = warehouse.scale((2, 1)).unwrap();
+        warehouse.run(movements.iter());
+        assert_eq!(warehouse.score(gps), 9021);
+    }
+
+    #[test]
+    fn polyomino_push() {
+        let mut warehouse = Warehouse::parse(
+            "\
+##########
+#........#
+#.@aa....#
+#...abbb.#
+#........#
+##########",
+        )
+        .unwrap();
+        // The L touches the 3-wide crate, so both are pushed together.
+        assert_eq!(
+            warehouse.push(&(1, 0)),
+            MoveResult::Moved { pushed: vec![0, 1] }
+        );
+        assert_eq!(
+            warehouse.to_string(),
+            "\
+##########
+#........#
+#..@aa...#
+#....abbb#
+#........#
+##########
+"
+        );
+        // Now the 3-wide crate is against the wall and holds the L back.
+        assert_eq!(warehouse.push(&(1, 0)), MoveResult::Blocked);
+        assert_eq!(warehouse.robot(), (3, 2));
+        // Nothing is in the way going down.
+        assert_eq!(
+            warehouse.push(&(0, 1)),
+            MoveResult::Moved { pushed: vec![] }
+        );
+    }
+
+    #[test]
+    fn crate_wrapped_around_robot() {
+        let start = Warehouse::parse(
+            "\
+#######
+#aaa..#
+#a@a..#
+#######",
+        )
+        .unwrap();
+        let mut warehouse = start.clone();
+        let first = warehouse.push(&(1, 0));
+        assert_eq!(first, MoveResult::Moved { pushed: vec![0] });
+        // The crate's left arm moved onto the robot's old tile.
+        assert_eq!(warehouse.tile(&(2, 2)), Some(Tile::Object(0)));
+        let second = warehouse.push(&(1, 0));
+        assert!(second.moved());
+        assert_eq!(
+            warehouse.to_string(),
+            "\
+#######
+#..aaa#
+#..a@a#
+#######
+"
+        );
+        assert_eq!(warehouse.score(gps), 103);
+
+        warehouse.undo(&(1, 0), &second).unwrap();
+        warehouse.undo(&(1, 0), &first).unwrap();
+        assert_eq!(warehouse, start);
+    }
+
+    #[test]
+    fn scale_wide_crates() {
+        let warehouse = Warehouse::parse("#@bbb.#").unwrap();
+        let wide = warehouse.scale((3, 2)).unwrap();
+        assert_eq!(wide.objects()[0].cells.len(), 18);
+        assert_eq!(wide.robot(), (3, 0));
+        assert_eq!(wide.tile(&(3, 1)), Some(Tile::Empty));
+        assert_eq!(wide.tile(&(14, 1)), Some(Tile::Object(0)));
+    }
+}
@@ -32,14 +32,137 @@ fn start_pos(maze: &Maze) -> Result<Orientation> {
     })
 }
 
-fn solve_part1_impl(input: &Data) -> Result<(Vec<Orientation>, usize)> {
+/// Costs and freedoms of a walker in the maze.
+///
+/// The puzzle's reindeer is [`MovementModel::reindeer`], which is also the
+/// default.  Other models are used for robots with different drive trains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovementModel {
+    /// Cost of a step to the next cell in a cardinal direction.
+    pub step: usize,
+    /// Cost of a diagonal step.  With diagonals the walker has eight headings
+    /// and turns are 45 degrees, otherwise it has four and turns are 90.
+    pub diagonal_step: Option<usize>,
+    pub turn_left: usize,
+    pub turn_right: usize,
+    /// Cost of turning around in place, None if it must turn twice instead.
+    pub reverse: Option<usize>,
+    /// Extra cost of stepping onto a terrain cell, indexed by its digit.
+    pub terrain: [usize; 10],
+}
+
+impl MovementModel {
+    pub fn reindeer() -> Self {
+        MovementModel {
+            step: 1,
+            diagonal_step: None,
+            turn_left: 1000,
+            turn_right: 1000,
+            reverse: None,
+            terrain: [0; 10],
+        }
+    }
+
+    /// Eighths of a full circle turned by a single left or right turn.
+    fn turn_size(&self) -> usize {
+        if self.diagonal_step.is_some() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Cost of stepping onto `cell` along `direction`, None if it can't be entered.
+    fn step_cost(&self, direction: &Direction, cell: &Cell) -> Option<usize> {
+        let step = if direction.0 != 0 && direction.1 != 0 {
+            self.diagonal_step?
+        } else {
+            self.step
+        };
+        match cell {
+            Cell::Wall => None,
+            Cell::Terrain(t) => Some(step + self.terrain[*t as usize]),
+            Cell::Empty | Cell::Start | Cell::End => Some(step),
+        }
+    }
+
+    /// A lower bound of the cost from `from` to the nearest of `ends`.
+    ///
+    /// Each step changes each coordinate by at most one, and reaching a cell
+    /// that isn't straight ahead takes at least one turn without diagonals.
+    /// A step forward never brings an end straight ahead, so the bound is
+    /// consistent as well as admissible and A* expands each node once.
+    fn heuristic(&self, from: &Orientation, ends: &[Position]) -> usize {
+        let cheapest_step = self.diagonal_step.map_or(self.step, |d| d.min(self.step));
+        let cheapest_turn = self
+            .turn_left
+            .min(self.turn_right)
+            .min(self.reverse.unwrap_or(usize::MAX));
+        let (x, y) = from.position;
+        ends.iter()
+            .map(|end| {
+                let dx = x.abs_diff(end.0);
+                let dy = y.abs_diff(end.1);
+                let ahead = (
+                    (end.0 as isize - x as isize).signum(),
+                    (end.1 as isize - y as isize).signum(),
+                ) == from.direction;
+                if self.diagonal_step.is_some() {
+                    dx.max(dy) * cheapest_step
+                } else if dx + dy != 0 && !ahead {
+                    (dx + dy) * cheapest_step + cheapest_turn
+                } else {
+                    (dx + dy) * cheapest_step
+                }
+            })
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl Default for MovementModel {
+    fn default() -> Self {
+        MovementModel::reindeer()
+    }
+}
+
+/// Every heading, clockwise from east.  A right turn moves forward in this
+/// list and a left turn backwards.
+const HEADINGS: [Direction; 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// Rotate a heading clockwise by the given number of eighths of a circle.
+fn rotate(direction: &Direction, eighths: usize) -> Direction {
+    let index = HEADINGS
+        .iter()
+        .position(|h| h == direction)
+        .unwrap_or_default();
+    HEADINGS[(index + eighths) % HEADINGS.len()]
+}
+
+fn end_positions(maze: &Maze) -> Vec<Position> {
+    crate::enumerate_grid(maze.iter())
+        .filter(|(_, _, cell)| **cell == Cell::End)
+        .map(|(x, y, _)| (x, y))
+        .collect()
+}
+
+fn solve_part1_impl(input: &Data, model: &MovementModel) -> Result<(Vec<Orientation>, usize)> {
     let maze = &input.maze;
     // find the start
     let start_pos = start_pos(maze)?;
 
     let shortest = pathfinding::directed::dijkstra::dijkstra(
         &start_pos,
-        |xy| maze_moves(xy, maze),
+        |xy| maze_moves(xy, maze, model),
         |xy| maze[xy.position.1][xy.position.0] == Cell::End,
     )
     .ok_or_else(|| anyhow::anyhow!("No path found"))?;
@@ -49,143 +172,56 @@ fn solve_part1_impl(input: &Data) -> Result<(Vec<Orientation>, usize)> {
 
 // maze_moves returns a generated Iterator rather than a static
 // vec so that we're not allocating.
-fn maze_moves(o: &Orientation, maze: &Maze) -> impl Iterator<Item = (Orientation, usize)> {
-    // We can always turn
-    let turns = [
+fn maze_moves(
+    o: &Orientation,
+    maze: &Maze,
+    model: &MovementModel,
+) -> impl Iterator<Item = (Orientation, usize)> {
+    let turned = |eighths, cost| {
         (
             Orientation {
                 position: o.position,
-                direction: (o.direction.1, -o.direction.0),
+                direction: rotate(&o.direction, eighths),
             },
-            1000,
-        ),
-        (
-            Orientation {
-                position: o.position,
-                direction: (-o.direction.1, o.direction.0),
-            },
-            1000,
-        ),
+            cost,
+        )
+    };
+    // We can always turn
+    let turns = [
+        Some(turned(HEADINGS.len() - model.turn_size(), model.turn_left)),
+        Some(turned(model.turn_size(), model.turn_right)),
+        model.reverse.map(|cost| turned(HEADINGS.len() / 2, cost)),
     ]
-    .into_iter();
+    .into_iter()
+    .flatten();
 
-    let _step_forward = (|| {
-        let xy = add_xy(&o.position, &o.direction)?;
+    // Compute xy, get the maze cell, and step onto it if the model allows it
+    let step_forward = add_xy(&o.position, &o.direction).and_then(|xy| {
         let cell = maze.get(xy.1)?.get(xy.0)?;
-        (cell != &Cell::Wall).then_some((
+        let cost = model.step_cost(&o.direction, cell)?;
+        Some((
             Orientation {
                 position: xy,
                 direction: o.direction,
             },
-            1,
-        ))
-    })();
-
-    let _step_forward = (|| {
-        let next_xy = add_xy(&o.position, &o.direction)?;
-        let forward_cell = maze.get(next_xy.1)?.get(next_xy.0);
-        let non_wall = forward_cell.filter(|cell| cell != &&Cell::Wall);
-        non_wall.map(|_| {
-            (
-                Orientation {
-                    position: add_xy(&o.position, &o.direction).unwrap(),
-                    direction: o.direction,
-                },
-                1,
-            )
-        })
-    })();
-
-    let _step_forward = (|| {
-        let next_xy = add_xy(&o.position, &o.direction)?;
-        let forward_cell = maze.get(next_xy.1)?.get(next_xy.0)?;
-        let non_wall = forward_cell != &Cell::Wall;
-        non_wall.then_some((
-            Orientation {
-                position: add_xy(&o.position, &o.direction).unwrap(),
-                direction: o.direction,
-            },
-            1,
-        ))
-    })();
-
-    let _step_forward = add_xy(&o.position, &o.direction).and_then(|next_xy| {
-        let forward_cell = maze.get(next_xy.1)?.get(next_xy.0)?;
-        let non_wall = forward_cell != &Cell::Wall;
-        non_wall.then_some((
-            Orientation {
-                position: add_xy(&o.position, &o.direction).unwrap(),
-                direction: o.direction,
-            },
-            1,
+            cost,
         ))
     });
 
-    // Compute xy, get the maze cell, check if it's not a wall, and return the new orientation
-    let _step_forward = add_xy(&o.position, &o.direction)
-        .and_then(|xy| maze.get(xy.1)?.get(xy.0))
-        .filter(|cell| cell != &&Cell::Wall)
-        .map(|_| {
-            (
-                Orientation {
-                    position: add_xy(&o.position, &o.direction).unwrap(),
-                    direction: o.direction,
-                },
-                1,
-            )
-        });
-
-    let _step_forward = add_xy(&o.position, &o.direction)
-        .and_then(|forward_xy| maze.get(forward_xy.1)?.get(forward_xy.0))
-        .map(|forward_cell| forward_cell != &Cell::Wall)
-        .filter(|not_wall| *not_wall)
-        .map(|_| {
-            (
-                Orientation {
-                    position: add_xy(&o.position, &o.direction).unwrap(),
-                    direction: o.direction,
-                },
-                1,
-            )
-        });
-
-    let step_forward = if let Some(xy) = add_xy(&o.position, &o.direction) {
-        if let Some(row) = maze.get(xy.1) {
-            if let Some(cell) = row.get(xy.0) {
-                if cell != &Cell::Wall {
-                    Some((
-                        Orientation {
-                            position: xy,
-                            direction: o.direction,
-                        },
-                        1,
-                    ))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
     turns.chain(step_forward)
 }
 
-fn solve_part2_impl(input: &Data) -> Result<usize> {
+fn solve_part2_impl(input: &Data, model: &MovementModel) -> Result<usize> {
     let maze = &input.maze;
     // find the start
     let start_pos = start_pos(maze)?;
+    let ends = end_positions(maze);
 
     // use astar this time
     let astar = pathfinding::directed::astar::astar_bag(
         &start_pos,
-        |xy| maze_moves(xy, maze),
-        |_| 0,
+        |xy| maze_moves(xy, maze, model),
+        |xy| model.heuristic(xy, &ends),
         |xy| maze[xy.position.1][xy.position.0] == Cell::End,
     )
     .ok_or_else(|| anyhow::anyhow!("Could not construct astar solver"))?;
@@ -200,6 +236,18 @@ fn solve_part2_impl(input: &Data) -> Result<usize> {
     Ok(found.len())
 }
 
+/// Lowest cost from the start to the end of the maze under any movement model.
+pub fn lowest_cost(input: &str, model: &MovementModel) -> Result<usize> {
+    let input = Data::parse(input).context("input parsing")?;
+    Ok(solve_part1_impl(&input, model)?.1)
+}
+
+/// Number of cells on any of the lowest cost paths under any movement model.
+pub fn best_path_tiles(input: &str, model: &MovementModel) -> Result<usize> {
+    let input = Data::parse(input).context("input parsing")?;
+    solve_part2_impl(&input, model)
+}
+
 #[allow(dead_code)]
 fn print_path(maze: &Maze, path: &HashSet<Position>) {
     for (y, row) in maze.iter().enumerate() {
@@ -218,14 +266,14 @@ fn print_path(maze: &Maze, path: &HashSet<Position>) {
 #[aoc(day16, part1)]
 fn solve_part1(input: &str) -> Result<usize> {
     let input = Data::parse(input).context("input parsing")?;
-    Ok(solve_part1_impl(&input)?.1)
+    Ok(solve_part1_impl(&input, &MovementModel::reindeer())?.1)
 }
 
 /// Solution to part 2
 #[aoc(day16, part2)]
 fn solve_part2(input: &str) -> Result<usize> {
     let input = Data::parse(input).context("input parsing")?;
-    solve_part2_impl(&input)
+    solve_part2_impl(&input, &MovementModel::reindeer())
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -234,6 +282,8 @@ enum Cell {
     Empty,
     Start,
     End,
+    /// Open cell with a terrain class from 0 to 9, see [`MovementModel::terrain`].
+    Terrain(u8),
 }
 impl TryFrom<char> for Cell {
     type Error = anyhow::Error;
@@ -244,6 +294,7 @@ impl TryFrom<char> for Cell {
             '.' => Cell::Empty,
             'S' => Cell::Start,
             'E' => Cell::End,
+            '0'..='9' => Cell::Terrain(value as u8 - b'0'),
             _ => anyhow::bail!("Invalid maze char {value}"),
         })
     }
@@ -255,6 +306,7 @@ impl From<&Cell> for char {
             Cell::Empty => '.',
             Cell::Start => 'S',
             Cell::End => 'E',
+            Cell::Terrain(t) => (b'0' + t) as char,
         }
     }
 }
@@ -298,6 +350,21 @@ impl Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -311,6 +378,7 @@ pub fn part2(input: &str) -> impl Display {
 #[cfg(test)]
 mod tests {
     use crate::test_data;
+    use proptest::prelude::*;
     use test_log::test;
 
     use super::*;
@@ -324,241 +392,164 @@ mod tests {
     fn part2_example() {
         assert_eq!(solve_part2(&test_data(super::DAY).unwrap()).unwrap(), 64);
     }
-}
 
-// Playing around with implementing my own search algorithm.
-#[cfg(test)]
-#[allow(dead_code)]
-mod mydi {
-    use std::collections::{BTreeSet, HashMap, HashSet};
-
-    use crate::{
-        add_xy,
-        day16::{start_pos, Cell},
-        parse_grid, test_data, Position,
-    };
-
-    use super::Maze;
-    struct Visit<C, N>
-    where
-        C: pathfinding::num_traits::Zero + Ord + Copy,
-        N: Eq + std::hash::Hash + Clone,
-    {
-        total_cost: C,
-        node: N,
-    }
-    impl<C, N> PartialEq for Visit<C, N>
-    where
-        C: pathfinding::num_traits::Zero + Ord + Copy,
-        N: Eq + std::hash::Hash + Clone,
-    {
-        fn eq(&self, other: &Self) -> bool {
-            self.total_cost == other.total_cost
-        }
-    }
-    impl<C, N> PartialOrd for Visit<C, N>
-    where
-        C: pathfinding::num_traits::Zero + Ord + Copy,
-        N: Eq + std::hash::Hash + Clone,
-    {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            Some(self.total_cost.cmp(&other.total_cost))
-        }
-    }
-    impl<C, N> Eq for Visit<C, N>
-    where
-        C: pathfinding::num_traits::Zero + Ord + Copy,
-        N: Eq + std::hash::Hash + Clone,
-    {
-    }
-    impl<C, N> Ord for Visit<C, N>
-    where
-        C: pathfinding::num_traits::Zero + Ord + Copy,
-        N: Eq + std::hash::Hash + Clone,
-    {
-        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            self.total_cost.cmp(&other.total_cost)
-        }
+    #[test]
+    fn free_turns() {
+        // With free turns the cost is the plain shortest path length.
+        let model = MovementModel {
+            turn_left: 0,
+            turn_right: 0,
+            ..MovementModel::reindeer()
+        };
+        assert_eq!(
+            lowest_cost(&test_data(super::DAY).unwrap(), &model).unwrap(),
+            40
+        );
     }
 
-    struct Marking<N, C>
-    where
-        N: Eq + std::hash::Hash + Clone,
-        C: pathfinding::num_traits::Zero + Ord + Copy,
-    {
-        cost: C,
-        prev_node: N,
+    #[test]
+    fn terrain_and_diagonals() {
+        let maze = "\
+#####
+#.9E#
+#...#
+#S..#
+#####";
+        let straight = MovementModel {
+            turn_left: 10,
+            turn_right: 10,
+            terrain: [0, 0, 0, 0, 0, 0, 0, 0, 0, 100],
+            ..MovementModel::reindeer()
+        };
+        // Right 2, turn, up 2 avoids the expensive terrain.
+        assert_eq!(lowest_cost(maze, &straight).unwrap(), 4 + 10);
+        let diagonal = MovementModel {
+            diagonal_step: Some(1),
+            ..straight.clone()
+        };
+        // Turn 45 degrees and step diagonally twice.
+        assert_eq!(lowest_cost(maze, &diagonal).unwrap(), 10 + 2);
+        assert_eq!(best_path_tiles(maze, &diagonal).unwrap(), 3);
     }
 
-    fn mydijkstra<N, C, FN, IN, FS>(
-        start: &N,
-        mut successors: FN,
-        mut success: FS,
-    ) -> Option<(Vec<N>, C)>
-    where
-        N: Eq + std::hash::Hash + Clone,
-        C: pathfinding::num_traits::Zero + Ord + Copy,
-        FN: FnMut(&N) -> IN,
-        IN: IntoIterator<Item = (N, C)>,
-        FS: FnMut(&N) -> bool,
-    {
-        let mut visit_queue = Vec::new();
-        visit_queue.push(start.clone());
-
-        let mut markings: HashMap<N, Marking<N, C>> = HashMap::new();
-        markings.insert(
-            start.clone(),
-            Marking {
-                cost: C::zero(),
-                prev_node: start.clone(),
-            },
-        );
-
-        let mut end_node = None;
-        let mut visited = HashSet::new();
+    #[test]
+    fn heuristic_prunes() {
+        let input = Data::parse(&test_data(super::DAY).unwrap()).unwrap();
+        let model = MovementModel::reindeer();
+        let start = start_pos(&input.maze).unwrap();
+        let ends = end_positions(&input.maze);
+
+        let expanded = |heuristic: &dyn Fn(&Orientation) -> usize| {
+            let mut count = 0;
+            pathfinding::directed::astar::astar(
+                &start,
+                |o| {
+                    count += 1;
+                    maze_moves(o, &input.maze, &model)
+                },
+                heuristic,
+                |o| input.maze[o.position.1][o.position.0] == Cell::End,
+            )
+            .unwrap();
+            count
+        };
+        let blind = expanded(&|_| 0);
+        let guided = expanded(&|o| model.heuristic(o, &ends));
+        assert!(guided < blind, "{} >= {}", guided, blind);
+    }
 
-        while let Some(to_visit) = visit_queue.pop() {
-            if visited.contains(&to_visit) {
-                continue;
-            }
-            visited.insert(to_visit.clone());
-
-            let mut child_nodes = BTreeSet::new();
-            let my_cost = markings
-                .get(&to_visit)
-                .expect("Must have already seen")
-                .cost
-                .clone();
-
-            let next_nodes = successors(&to_visit).into_iter();
-            for (n, cost) in next_nodes {
-                let this_cost = my_cost + cost;
-
-                let already_visited = if let Some(marking) = markings.get_mut(&n) {
-                    if success(&n) {
-                        end_node = Some(n.clone());
-                    }
-                    if this_cost < marking.cost {
-                        marking.cost = this_cost;
-                        marking.prev_node = to_visit.clone();
-                    }
-                    true
-                } else {
-                    markings.insert(
-                        n.clone(),
-                        Marking {
-                            cost: this_cost,
-                            prev_node: to_visit.clone(),
-                        },
-                    );
-                    false
-                };
-                if !already_visited {
-                    child_nodes.insert(Visit {
-                        total_cost: this_cost,
-                        node: n.clone(),
-                    });
+    #[test]
+    fn heuristic_is_consistent() {
+        let input = Data::parse(&test_data(super::DAY).unwrap()).unwrap();
+        let model = MovementModel::reindeer();
+        let ends = end_positions(&input.maze);
+        let mut seen = HashSet::from([start_pos(&input.maze).unwrap()]);
+        let mut todo = seen.iter().cloned().collect::<Vec<_>>();
+        while let Some(from) = todo.pop() {
+            for (to, cost) in maze_moves(&from, &input.maze, &model) {
+                assert!(
+                    model.heuristic(&from, &ends) <= cost + model.heuristic(&to, &ends),
+                    "{from:?} -> {to:?}"
+                );
+                if seen.insert(to.clone()) {
+                    todo.push(to);
                 }
             }
-            visit_queue.extend(child_nodes.into_iter().map(|n| n.node));
-        }
-
-        // Go backwards to find the path
-        let mut path = Vec::new();
-        let mut cur_node = end_node.as_ref()?;
-        let mut total_cost = C::zero();
-        while cur_node != start {
-            path.push(cur_node.clone());
-            let cur = markings.get(cur_node)?;
-            total_cost = total_cost + cur.cost;
-            cur_node = &cur.prev_node;
         }
-        Some((path, total_cost))
     }
 
     #[test]
-    fn test_btree_ordering() {
-        let mut btree = BTreeSet::new();
-        btree.insert(Visit {
-            total_cost: 1,
-            node: 1,
-        });
-        btree.insert(Visit {
-            total_cost: 3,
-            node: 3,
-        });
-        btree.insert(Visit {
-            total_cost: 2,
-            node: 2,
-        });
-        let nodes = btree.into_iter().map(|v| v.node).collect::<Vec<_>>();
-        assert_eq!(nodes, vec![1, 2, 3]);
+    fn search_module_agrees() {
+        let input = Data::parse(&test_data(super::DAY).unwrap()).unwrap();
+        let model = MovementModel::reindeer();
+        let start = start_pos(&input.maze).unwrap();
+        let found = crate::search::dijkstra_all(
+            &start,
+            |o| maze_moves(o, &input.maze, &model),
+            |o| input.maze[o.position.1][o.position.0] == Cell::End,
+        )
+        .unwrap();
+        assert_eq!(found.cost(), 11048);
+        let tiles = found
+            .nodes_on_paths()
+            .into_iter()
+            .map(|o| o.position)
+            .collect::<HashSet<_>>();
+        assert_eq!(tiles.len(), 64);
     }
-    #[test]
-    fn test_my_di() -> anyhow::Result<()> {
-        let maze: Vec<Vec<Cell>> = parse_grid(&test_data(super::DAY)?)?;
-
-        let maze = &maze;
 
-        let start_pos = start_pos(maze)?.position;
-
-        let directions = &[(-1, 0), (0, -1), (1, 0), (0, 1)];
-
-        let shortest = pathfinding::directed::dijkstra::dijkstra(
-            &start_pos,
-            |xy| {
-                directions
-                    .iter()
-                    .filter_map(|dir| {
-                        let xy = add_xy(&xy, dir)?;
-                        let cell = maze.get(xy.1)?.get(xy.0)?;
-                        (cell != &Cell::Wall).then_some((xy, 1))
-                    })
-                    .collect::<Vec<_>>()
-                    .into_iter()
-            },
-            |xy| maze[xy.1][xy.0] == Cell::End,
-        )
-        .ok_or_else(|| anyhow::anyhow!("No path found"))?;
-
-        let shortest_length = shortest.0.len();
-        //      print_maze2(maze, shortest.0.as_slice());
-        assert_eq!(shortest_length, 41);
-
-        let _shortest = mydijkstra(
-            &start_pos,
-            |xy| {
-                directions
-                    .iter()
-                    .filter_map(|dir| {
-                        let xy = add_xy(&xy, dir)?;
-                        let cell = maze.get(xy.1)?.get(xy.0)?;
-                        (cell != &Cell::Wall).then_some((xy, 1))
-                    })
+    /// A walled maze with random inner walls and distinct start and end.
+    fn maze() -> impl Strategy<Value = String> {
+        (1..10usize, 1..10usize)
+            .prop_filter("room for a start and an end", |(w, h)| w * h >= 2)
+            .prop_flat_map(|(w, h)| {
+                let cells =
+                    proptest::collection::vec(prop_oneof![3 => Just('.'), 1 => Just('#')], w * h);
+                let ends = (0..w * h, 0..w * h - 1);
+                (Just(w), cells, ends)
+            })
+            .prop_map(|(w, mut cells, (start, end))| {
+                // Skip over the start so the two never share a cell.
+                let end = if end >= start { end + 1 } else { end };
+                cells[start] = 'S';
+                cells[end] = 'E';
+                let wall = "#".repeat(w + 2);
+                let rows = cells
+                    .chunks(w)
+                    .map(|row| format!("#{}#", row.iter().collect::<String>()));
+                std::iter::once(wall.clone())
+                    .chain(rows)
+                    .chain(std::iter::once(wall))
                     .collect::<Vec<_>>()
-                    .into_iter()
-            },
-            |xy| maze[xy.1][xy.0] == Cell::End,
-        )
-        .ok_or_else(|| anyhow::anyhow!("No path found"))?;
-        //       print_maze2(maze, &shortest.0);
-        //        assert_eq!(shortest.0.len(), 41);
-
-        Ok(())
+                    .join("\n")
+            })
     }
 
-    #[allow(dead_code)]
-    fn print_maze2(maze: &Maze, path_points: &[Position]) {
-        for (y, row) in maze.iter().enumerate() {
-            for (x, cell) in row.iter().enumerate() {
-                let c = if path_points.contains(&(x, y)) {
-                    'O'
-                } else {
-                    Into::<char>::into(cell)
-                };
-                print!("{}", c);
-            }
-            println!();
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn search_module_agrees_on_random_mazes(maze in maze()) {
+            let input = Data::parse(&maze).unwrap();
+            let model = MovementModel::reindeer();
+            let start = start_pos(&input.maze).unwrap();
+            let found = crate::search::dijkstra_all(
+                &start,
+                |o| maze_moves(o, &input.maze, &model),
+                |o| input.maze[o.position.1][o.position.0] == Cell::End,
+            );
+            prop_assert_eq!(
+                solve_part1_impl(&input, &model).ok().map(|(_, cost)| cost),
+                found.as_ref().map(|f| f.cost())
+            );
+            let tiles = found.map(|f| {
+                f.nodes_on_paths()
+                    .into_iter()
+                    .map(|o| o.position)
+                    .collect::<HashSet<_>>()
+                    .len()
+            });
+            prop_assert_eq!(solve_part2_impl(&input, &model).ok(), tiles);
         }
     }
 }
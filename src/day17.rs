@@ -2,164 +2,26 @@ use crate::Result;
 use anyhow::Context as _;
 use aoc_runner_derive::aoc;
 use std::fmt::Display;
+use vm::{Machine, Registers, Stop};
 
-pub const DAY: u32 = 17;
-
-fn solve_part1_impl(input: &Data) -> Result<Vec<u64>> {
-    let mut reg_a = input.a;
-    let mut reg_b = input.b;
-    let mut reg_c = input.c;
+pub mod quine;
+pub mod vm;
 
-    let program = &input.program;
+pub const DAY: u32 = 17;
 
-    let mut output = Vec::new();
+/// Instructions part 1 runs before deciding the program never halts.
+const MAX_STEPS: usize = 1_000_000;
 
-    let mut pc = 0;
-    loop {
-        let opcode = match program.get(pc) {
-            Some(Memory::Opcode(opcode)) => opcode,
-            None => break,
-            other => anyhow::bail!("Invalid opcode at {pc}: {:?}", other),
-        };
-        pc += 1;
-        let operand = match program.get(pc) {
-            Some(Memory::Operand(operand)) => *operand,
-            _ => anyhow::bail!("Invalid operand"),
-        };
-        pc += 1;
-        let combo = || {
-            Ok(match operand {
-                0..=3 => operand,
-                4 => reg_a,
-                5 => reg_b,
-                6 => reg_c,
-                _ => anyhow::bail!("Invalid combo: {operand} at pc {pc}"),
-            })
-        };
-        match opcode {
-            Opcode::Adv => reg_a /= 2u64.pow(combo()?.try_into()?),
-            Opcode::Bxl => reg_b ^= operand,
-            Opcode::Bst => reg_b = combo()? % 8,
-            Opcode::Jnz => {
-                if reg_a != 0 {
-                    pc = operand.try_into()?;
-                }
-            }
-            Opcode::Bxc => reg_b ^= reg_c,
-            Opcode::Out => output.push(combo()? % 8),
-            Opcode::Bdv => reg_b = reg_a / (2u64.pow(combo()?.try_into()?)),
-            Opcode::Cdv => reg_c = reg_a / (2u64.pow(combo()?.try_into()?)),
-        }
+fn solve_part1_impl(input: &Data) -> Result<Vec<u64>> {
+    let mut machine = Machine::new(&input.raw_program, input.registers())?;
+    match machine.run_for(MAX_STEPS)? {
+        Stop::StepLimit => anyhow::bail!("program still running after {MAX_STEPS} steps"),
+        _ => Ok(machine.output().to_vec()),
     }
-
-    Ok(output)
 }
 
 fn solve_part2_impl(input: &Data) -> Result<u64> {
-    // let mut last = None;
-    // let mut count = 0;
-    // for reg_a in 0.. {
-    //     let output = solve_part1_impl(&Data {
-    //         a: reg_a,
-    //         ..input.clone()
-    //     })?;
-    //     let last_output = output.iter().rev().take(1).copied().next().unwrap();
-    //     count += 1;
-    //     if let Some(l) = last {
-    //         if last_output != l {
-    //             println!("reg_a {}, output: {:?}, count: {}", reg_a, output, count);
-    //             last = Some(last_output);
-    //             count = 0;
-    //         }
-    //     } else {
-    //         last = Some(last_output);
-    //     }
-    // }
-    // return Ok(0);
-
-    let program_len = input.raw_program.len() as u32;
-
-    let exp = program_len - 1;
-    let reg_a = 8u64.pow(exp);
-
-    assert_eq!(
-        solve_part1_impl(&Data {
-            a: reg_a,
-            ..input.clone()
-        })?
-        .len(),
-        input.raw_program.len()
-    );
-    assert_eq!(
-        solve_part1_impl(&Data {
-            a: reg_a - 1,
-            ..input.clone()
-        })?
-        .len(),
-        input.raw_program.len() - 1
-    );
-
-    //    for index in 0..14 {
-    let mut exps = (0..program_len - 2).map(|_| 0).collect::<Vec<_>>(); // [0; 14];
-    loop {
-        let mut reg_a = 8u64.pow(program_len - 1);
-        for (i, count) in exps.iter().enumerate() {
-            reg_a += 64 * 8u64.pow(program_len - 3 - i as u32) * count;
-        }
-        // println!("exps: {:?}", exps);
-
-        let output = solve_part1_impl(&Data {
-            a: reg_a,
-            ..input.clone()
-        })?;
-        assert_eq!(output.len(), input.raw_program.len());
-
-        // println!(
-        //     "reg_a: {}, exps: {:?}, output: {:?}, program: {:?}",
-        //     reg_a, exps, output, input.raw_program
-        // );
-
-        let mut check = output.iter().rev().zip(input.raw_program.iter().rev());
-
-        let bad_index = check.position(|(a, b)| a != b);
-        if let Some(bad_index) = bad_index {
-            if bad_index >= exps.len() {
-                break;
-            }
-            exps[bad_index] += 1;
-            for exp in exps.iter_mut().skip(bad_index + 1) {
-                *exp = 0;
-            }
-        } else {
-            break;
-        }
-    }
-
-    let mut reg_a = 8u64.pow(program_len - 1);
-    for (i, count) in exps.iter().enumerate() {
-        reg_a += 64 * 8u64.pow(program_len - 3 - i as u32) * count;
-    }
-
-    //   }
-    println!("Brute");
-    // Brute force the last
-    for _ in 0.. {
-        let output = solve_part1_impl(&Data {
-            a: reg_a,
-            ..input.clone()
-        })?;
-        assert_eq!(output.len(), input.raw_program.len());
-        // println!(
-        //     "reg_a: {}, exp: {}, output: {:?}, input: {:?}",
-        //     reg_a, exp, output, input.raw_program
-        // );
-        if output.as_slice() == input.raw_program.as_slice() {
-            break;
-        }
-
-        reg_a += 1;
-    }
-    Ok(reg_a)
+    quine::find_quine(&input.raw_program, input.registers())
 }
 
 /// Solution to part 1
@@ -181,8 +43,8 @@ fn solve_part2(input: &str) -> Result<u64> {
     solve_part2_impl(&input)
 }
 
-#[derive(Debug, Clone)]
-enum Opcode {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
     Adv,
     Bxl,
     Bst,
@@ -210,17 +72,9 @@ impl TryFrom<u64> for Opcode {
     }
 }
 
-#[derive(Debug, Clone)]
-enum Memory {
-    Opcode(Opcode),
-    Operand(u64),
-}
-
 /// Problem input
 #[derive(Debug, Clone)]
 struct Data {
-    // XXX: Change this to the actual data structure
-    program: Vec<Memory>,
     raw_program: Vec<u64>,
     a: u64,
     b: u64,
@@ -244,22 +98,11 @@ impl Data {
             .split_once(": ")
             .ok_or_else(|| anyhow::anyhow!("Invalid program"))?;
         let raw_program: Vec<u64> = program
+            .trim_end()
             .split(",")
             .map(|num| Ok::<_, anyhow::Error>(num.parse::<u64>()?))
             .collect::<Result<_>>()?;
 
-        let program = raw_program
-            .iter()
-            .enumerate()
-            .map(|(i, &num)| {
-                Ok(if i % 2 == 0 {
-                    Memory::Opcode(Opcode::try_from(num)?)
-                } else {
-                    Memory::Operand(num)
-                })
-            })
-            .collect::<Result<_>>()?;
-
         // XXX: Update the returned Data to include the parsed data.
         Ok(Data {
             a: registers
@@ -272,9 +115,31 @@ impl Data {
                 .next()
                 .ok_or_else(|| anyhow::anyhow!("No Reg"))??,
             raw_program,
-            program,
         })
     }
+
+    fn registers(&self) -> Registers {
+        Registers {
+            a: self.a,
+            b: self.b,
+            c: self.c,
+        }
+    }
+}
+
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
 }
 
 /// codspeed compatible function
@@ -313,4 +178,11 @@ Register C: 0
 Program: 0,3,5,4,3,0";
         assert_eq!(solve_part2(data).unwrap(), 117440);
     }
+
+    #[test]
+    fn part1_never_halts() {
+        // Jumps back to 0 forever, as A never changes.
+        let data = "Register A: 1\nRegister B: 0\nRegister C: 0\n\nProgram: 1,1,3,0";
+        assert!(solve_part1(data).is_err());
+    }
 }
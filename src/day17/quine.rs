@@ -0,0 +1,406 @@
+//! Find the smallest value of register A that makes a program output itself.
+//!
+//! Most programs are a single loop that prints one value and shifts A right
+//! by three bits per iteration.  Each output then depends only on the octal
+//! digits of A that haven't been shifted out yet, so A can be built one digit
+//! at a time from the last output backwards ([`search_octal_digits`]).
+//!
+//! Anything else is handed to z3: the program is executed symbolically with A
+//! as a 64 bit vector, forking at every `jnz`, and the smallest A on any path
+//! that prints the program and halts wins ([`search_symbolic`]).
+
+use super::{
+    vm::{Machine, Registers},
+    Opcode,
+};
+use crate::Result;
+use z3::{
+    ast::{Ast as _, Bool, BV},
+    SatResult,
+};
+
+/// Steps a single symbolic path may take before the search gives up on it.
+const MAX_PATH_STEPS: usize = 10_000;
+
+/// Does running `program` with `a` print the program itself?
+pub fn is_quine(program: &[u64], registers: Registers, a: u64) -> Result<bool> {
+    let mut machine = Machine::new(program, Registers { a, ..registers })?;
+    // Bound the run so a program that never halts can't hang the check.
+    machine.run_for(MAX_PATH_STEPS)?;
+    Ok(machine.halted() && machine.output() == program)
+}
+
+/// Smallest A for which the program outputs itself.
+pub fn find_quine(program: &[u64], registers: Registers) -> Result<u64> {
+    if shifts_octal_digits(program) {
+        search_octal_digits(program, registers)
+    } else {
+        search_symbolic(program, registers)
+    }
+}
+
+/// The instructions of a program, failing on a dangling opcode.
+fn instructions(program: &[u64]) -> Result<Vec<(Opcode, u64)>> {
+    anyhow::ensure!(
+        program.len().is_multiple_of(2),
+        "program has a dangling opcode"
+    );
+    program
+        .chunks(2)
+        .map(|pair| Ok((Opcode::try_from(pair[0])?, pair[1])))
+        .collect()
+}
+
+/// Is the program a loop that outputs once and shifts A by three per pass,
+/// with B and C recomputed from A every time round?
+///
+/// For such programs the output of A's leading octal digits is exactly the
+/// tail of A's output, which is what makes the digit search exhaustive.
+pub fn shifts_octal_digits(program: &[u64]) -> bool {
+    let Ok(instructions) = instructions(program) else {
+        return false;
+    };
+    let Some(((last, target), body)) = instructions.split_last() else {
+        return false;
+    };
+    let count = |f: &dyn Fn(&(Opcode, u64)) -> bool| body.iter().filter(|i| f(i)).count();
+    if *last != Opcode::Jnz
+        || *target != 0
+        || count(&|(op, _)| *op == Opcode::Jnz) != 0
+        || count(&|(op, _)| *op == Opcode::Out) != 1
+        || count(&|(op, _)| *op == Opcode::Adv) != 1
+        || count(&|i| *i == (Opcode::Adv, 3)) != 1
+    {
+        return false;
+    }
+
+    // B and C must be written before they are read, so nothing carries over
+    // from the previous pass.
+    let (mut b_set, mut c_set) = (false, false);
+    for (opcode, operand) in body {
+        let combo = matches!(
+            opcode,
+            Opcode::Adv | Opcode::Bst | Opcode::Out | Opcode::Bdv | Opcode::Cdv
+        );
+        let reads_b = matches!(opcode, Opcode::Bxl | Opcode::Bxc) || (combo && *operand == 5);
+        let reads_c = *opcode == Opcode::Bxc || (combo && *operand == 6);
+        if (reads_b && !b_set) || (reads_c && !c_set) {
+            return false;
+        }
+        match opcode {
+            Opcode::Bst | Opcode::Bdv => b_set = true,
+            Opcode::Cdv => c_set = true,
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Build A three bits at a time, from the digit that produces the last output
+/// down to the one producing the first, backtracking when no digit fits.
+///
+/// Digits are tried smallest first, so the first complete match is the
+/// smallest A.  Only exhaustive for programs accepted by [`shifts_octal_digits`].
+pub fn search_octal_digits(program: &[u64], registers: Registers) -> Result<u64> {
+    fn extend(program: &[u64], registers: Registers, a: u64, len: usize) -> Result<Option<u64>> {
+        if len == program.len() {
+            return Ok(Some(a));
+        }
+        if a > u64::MAX >> 3 {
+            return Ok(None);
+        }
+        let tail = &program[program.len() - len - 1..];
+        for digit in 0..8 {
+            let candidate = a << 3 | digit;
+            let mut machine = Machine::new(
+                program,
+                Registers {
+                    a: candidate,
+                    ..registers
+                },
+            )?;
+            machine.run_for(MAX_PATH_STEPS)?;
+            if machine.output() == tail {
+                if let Some(a) = extend(program, registers, candidate, len + 1)? {
+                    return Ok(Some(a));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    let a = extend(program, registers, 0, 0)?
+        .ok_or_else(|| anyhow::anyhow!("no solution: no value of A prints the program"))?;
+    anyhow::ensure!(
+        is_quine(program, registers, a)?,
+        "A = {a} was found by the digit search but does not print the program"
+    );
+    Ok(a)
+}
+
+/// Registers and position of one symbolic execution path.
+#[derive(Clone)]
+struct PathState<'ctx> {
+    pc: usize,
+    a: BV<'ctx>,
+    b: BV<'ctx>,
+    c: BV<'ctx>,
+    outputs: usize,
+    steps: usize,
+}
+
+impl PathState<'_> {
+    fn same_as(&self, other: &Self) -> bool {
+        self.pc == other.pc
+            && self.outputs == other.outputs
+            && self.a == other.a
+            && self.b == other.b
+            && self.c == other.c
+    }
+}
+
+struct Symbolic<'ctx, 'p> {
+    ctx: &'ctx z3::Context,
+    solver: z3::Solver<'ctx>,
+    program: &'p [u64],
+    a: BV<'ctx>,
+    best: Option<u64>,
+    /// Set when a path was abandoned for running too long.
+    gave_up: bool,
+    /// States seen at jumps on the current path, to spot infinite loops.
+    jumps: Vec<PathState<'ctx>>,
+}
+
+impl<'ctx> Symbolic<'ctx, '_> {
+    fn constant(&self, value: u64) -> BV<'ctx> {
+        BV::from_u64(self.ctx, value, 64)
+    }
+
+    /// Satisfiable, and still able to beat the best A found so far?
+    fn feasible(&self) -> bool {
+        let assumptions = self
+            .best
+            .map(|best| vec![self.a.bvult(&self.constant(best))])
+            .unwrap_or_default();
+        self.solver.check_assumptions(&assumptions) == SatResult::Sat
+    }
+
+    fn combo(&self, state: &PathState<'ctx>, operand: u64) -> Option<BV<'ctx>> {
+        Some(match operand {
+            0..=3 => self.constant(operand),
+            4 => state.a.clone(),
+            5 => state.b.clone(),
+            6 => state.c.clone(),
+            _ => return None,
+        })
+    }
+
+    /// Follow a path to its end, forking at every `jnz`.
+    fn explore(&mut self, mut state: PathState<'ctx>) {
+        loop {
+            if state.steps >= MAX_PATH_STEPS {
+                self.gave_up = true;
+                return;
+            }
+            state.steps += 1;
+            if state.pc >= self.program.len() {
+                if state.outputs == self.program.len() {
+                    self.minimize();
+                }
+                return;
+            }
+            // Odd jump targets read an operand as an opcode, like the VM does.
+            let opcode = self.program[state.pc];
+            let (Ok(opcode), Some(&operand)) =
+                (Opcode::try_from(opcode), self.program.get(state.pc + 1))
+            else {
+                return;
+            };
+            let combo = self.combo(&state, operand);
+            state.pc += 2;
+            match opcode {
+                Opcode::Adv => {
+                    let Some(x) = combo else { return };
+                    state.a = state.a.bvlshr(&x);
+                }
+                Opcode::Bxl => state.b = state.b.bvxor(&self.constant(operand)),
+                Opcode::Bst => {
+                    let Some(x) = combo else { return };
+                    state.b = x.bvand(&self.constant(7));
+                }
+                Opcode::Jnz => {
+                    self.jump(state, operand as usize);
+                    return;
+                }
+                Opcode::Bxc => state.b = state.b.bvxor(&state.c),
+                Opcode::Out => {
+                    let Some(x) = combo else { return };
+                    let Some(expected) = self.program.get(state.outputs) else {
+                        return;
+                    };
+                    let printed = x.bvand(&self.constant(7));
+                    self.solver.assert(&printed._eq(&self.constant(*expected)));
+                    state.outputs += 1;
+                    if !self.feasible() {
+                        return;
+                    }
+                }
+                Opcode::Bdv => {
+                    let Some(x) = combo else { return };
+                    state.b = state.a.bvlshr(&x);
+                }
+                Opcode::Cdv => {
+                    let Some(x) = combo else { return };
+                    state.c = state.a.bvlshr(&x);
+                }
+            }
+        }
+    }
+
+    fn jump(&mut self, state: PathState<'ctx>, target: usize) {
+        let nonzero = state.a._eq(&self.constant(0)).not();
+        let state = PathState {
+            a: state.a.simplify(),
+            b: state.b.simplify(),
+            c: state.c.simplify(),
+            ..state
+        };
+
+        // Coming back to a jump in exactly the same state loops forever.
+        if !self.jumps.iter().any(|seen| seen.same_as(&state)) {
+            self.jumps.push(state.clone());
+            self.branch(
+                &nonzero,
+                PathState {
+                    pc: target,
+                    ..state.clone()
+                },
+            );
+            self.jumps.pop();
+        }
+        self.branch(&nonzero.not(), state);
+    }
+
+    fn branch(&mut self, condition: &Bool<'ctx>, state: PathState<'ctx>) {
+        self.solver.push();
+        self.solver.assert(condition);
+        if self.feasible() {
+            self.explore(state);
+        }
+        self.solver.pop(1);
+    }
+
+    /// Fix the bits of A from the top, preferring zero, to find the smallest
+    /// A that satisfies the current path.
+    fn minimize(&mut self) {
+        if !self.feasible() {
+            return;
+        }
+        let mut fixed = vec![];
+        let mut value = 0;
+        for bit in (0..64).rev() {
+            let zero = self.a.extract(bit, bit)._eq(&BV::from_u64(self.ctx, 0, 1));
+            fixed.push(zero);
+            if self.solver.check_assumptions(&fixed) != SatResult::Sat {
+                let one = fixed.pop().expect("just pushed").not();
+                fixed.push(one);
+                value |= 1 << bit;
+            }
+        }
+        if self.best.is_none_or(|best| value < best) {
+            self.best = Some(value);
+        }
+    }
+}
+
+/// Execute the program symbolically and ask z3 for the smallest A.
+pub fn search_symbolic(program: &[u64], registers: Registers) -> Result<u64> {
+    instructions(program)?;
+    let ctx = z3::Context::new(&z3::Config::new());
+    let a = BV::new_const(&ctx, "a", 64);
+    let mut search = Symbolic {
+        ctx: &ctx,
+        solver: z3::Solver::new(&ctx),
+        program,
+        a: a.clone(),
+        best: None,
+        gave_up: false,
+        jumps: vec![],
+    };
+    let start = PathState {
+        pc: 0,
+        a,
+        b: search.constant(registers.b),
+        c: search.constant(registers.c),
+        outputs: 0,
+        steps: 0,
+    };
+    search.explore(start);
+
+    match search.best {
+        Some(a) => {
+            anyhow::ensure!(
+                is_quine(program, registers, a)?,
+                "A = {a} was found by z3 but does not print the program"
+            );
+            if search.gave_up {
+                tracing::warn!("some paths ran too long to search, A = {a} may not be minimal");
+            }
+            Ok(a)
+        }
+        None if search.gave_up => anyhow::bail!(
+            "no solution found, but some paths ran for over {MAX_PATH_STEPS} steps and were not searched"
+        ),
+        None => anyhow::bail!("no solution: no value of A prints the program"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &[u64] = &[0, 3, 5, 4, 3, 0];
+    const PROGRAM: &[u64] = &[2, 4, 1, 7, 7, 5, 0, 3, 1, 7, 4, 1, 5, 5, 3, 0];
+
+    fn registers() -> Registers {
+        Registers::default()
+    }
+
+    #[test]
+    fn recognises_digit_shifting_loops() {
+        assert!(shifts_octal_digits(EXAMPLE));
+        assert!(shifts_octal_digits(PROGRAM));
+        // B is read before it is set, so it carries over between passes.
+        assert!(!shifts_octal_digits(&[1, 1, 5, 5, 0, 3, 3, 0]));
+        // Shifts by one bit.
+        assert!(!shifts_octal_digits(&[0, 1, 5, 4, 3, 0]));
+    }
+
+    #[test]
+    fn digit_search_and_z3_agree() {
+        for program in [EXAMPLE, PROGRAM] {
+            let a = search_octal_digits(program, registers()).unwrap();
+            assert!(is_quine(program, registers(), a).unwrap());
+            assert_eq!(search_symbolic(program, registers()).unwrap(), a);
+        }
+        assert_eq!(find_quine(EXAMPLE, registers()).unwrap(), 117440);
+    }
+
+    #[test]
+    fn two_outputs_per_pass() {
+        // Not a digit shifting loop, so this goes to z3.
+        let program = &[0, 3, 5, 4, 0, 3, 5, 4, 3, 0];
+        assert!(!shifts_octal_digits(program));
+        assert_eq!(find_quine(program, registers()).unwrap(), 481053376);
+    }
+
+    #[test]
+    fn no_solution() {
+        // Only ever prints zeros.
+        let err = find_quine(&[0, 3, 5, 0, 3, 0], registers()).unwrap_err();
+        assert!(err.to_string().contains("no solution"), "{err}");
+        // Prints A forever without changing it.
+        let err = find_quine(&[5, 4, 3, 0], registers()).unwrap_err();
+        assert!(err.to_string().contains("no solution"), "{err}");
+    }
+}
@@ -0,0 +1,555 @@
+//! Tools for the 3-bit computer: a steppable machine with breakpoints,
+//! watches and traces, plus a disassembler and an assembler that round trip.
+//!
+//! The assembly format is one instruction per line, a mnemonic followed by its
+//! operand.  Combo operands are written as `0`-`3` or a register `a`, `b` or
+//! `c`.  An optional `<address>:` prefix and anything after a `;` are ignored,
+//! so the disassembler's output assembles back to the same program.
+//!
+//! ```text
+//!   0: bst a    ; b = a % 8
+//!   2: bxl 7    ; b = b ^ 7
+//! ```
+
+use super::Opcode;
+use crate::Result;
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    A,
+    B,
+    C,
+}
+
+impl FromStr for Register {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "a" | "A" => Register::A,
+            "b" | "B" => Register::B,
+            "c" | "C" => Register::C,
+            _ => anyhow::bail!("invalid register {s}"),
+        })
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let r = match self {
+            Register::A => "a",
+            Register::B => "b",
+            Register::C => "c",
+        };
+        write!(f, "{}", r)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+}
+
+impl Registers {
+    pub fn get(&self, register: Register) -> u64 {
+        match register {
+            Register::A => self.a,
+            Register::B => self.b,
+            Register::C => self.c,
+        }
+    }
+}
+
+impl Opcode {
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::Adv => "adv",
+            Opcode::Bxl => "bxl",
+            Opcode::Bst => "bst",
+            Opcode::Jnz => "jnz",
+            Opcode::Bxc => "bxc",
+            Opcode::Out => "out",
+            Opcode::Bdv => "bdv",
+            Opcode::Cdv => "cdv",
+        }
+    }
+
+    /// Does this opcode read its operand as a combo operand?
+    fn takes_combo(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Adv | Opcode::Bst | Opcode::Out | Opcode::Bdv | Opcode::Cdv
+        )
+    }
+}
+
+/// An opcode and its operand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub operand: u64,
+}
+
+impl Instruction {
+    /// The operand as written in assembly, with combo operands resolved to registers.
+    fn operand_text(&self) -> String {
+        match (self.opcode.takes_combo(), self.operand) {
+            (true, 4) => "a".into(),
+            (true, 5) => "b".into(),
+            (true, 6) => "c".into(),
+            (_, operand) => operand.to_string(),
+        }
+    }
+
+    /// What the instruction does, as a comment for the disassembly.
+    fn effect(&self) -> String {
+        let x = self.operand_text();
+        match self.opcode {
+            Opcode::Adv => format!("a = a >> {x}"),
+            Opcode::Bxl => format!("b = b ^ {x}"),
+            Opcode::Bst => format!("b = {x} % 8"),
+            Opcode::Jnz => format!("if a != 0 goto {x}"),
+            Opcode::Bxc => "b = b ^ c".into(),
+            Opcode::Out => format!("out {x} % 8"),
+            Opcode::Bdv => format!("b = a >> {x}"),
+            Opcode::Cdv => format!("c = a >> {x}"),
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.opcode.mnemonic(), self.operand_text())
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = anyhow::Error;
+
+    /// Parse a single `mnemonic operand` pair.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words = s.split_whitespace();
+        let mnemonic = words
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("missing mnemonic"))?
+            .to_ascii_lowercase();
+        let operand = words
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("missing operand for {mnemonic}"))?;
+        anyhow::ensure!(words.next().is_none(), "too many operands in {s:?}");
+
+        let opcode = (0..8)
+            .map(|n| Opcode::try_from(n).expect("all 3-bit opcodes are valid"))
+            .find(|op| op.mnemonic() == mnemonic)
+            .ok_or_else(|| anyhow::anyhow!("unknown mnemonic {mnemonic}"))?;
+
+        let operand = match (opcode.takes_combo(), operand.parse::<Register>()) {
+            (true, Ok(Register::A)) => 4,
+            (true, Ok(Register::B)) => 5,
+            (true, Ok(Register::C)) => 6,
+            _ => operand
+                .parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("invalid operand {operand}"))?,
+        };
+        anyhow::ensure!(operand < 8, "operand {operand} is not 3 bits");
+        Ok(Instruction { opcode, operand })
+    }
+}
+
+/// Render a program as assembly, one instruction per line.
+pub fn disassemble(program: &[u64]) -> Result<String> {
+    anyhow::ensure!(
+        program.len().is_multiple_of(2),
+        "program has a dangling opcode"
+    );
+    program
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let instruction = Instruction {
+                opcode: Opcode::try_from(pair[0])?,
+                operand: pair[1],
+            };
+            anyhow::ensure!(instruction.operand < 8, "operand is not 3 bits");
+            let text = format!("{:>3}: {}", i * 2, instruction);
+            Ok(format!("{:<12} ; {}\n", text, instruction.effect()))
+        })
+        .collect()
+}
+
+/// Parse assembly back into the raw program.
+pub fn assemble(s: &str) -> Result<Vec<u64>> {
+    let mut program = vec![];
+    for (line_number, line) in s.lines().enumerate() {
+        let line = line.split(';').next().unwrap_or_default();
+        // Drop an address prefix.
+        let line = line.split_once(':').map_or(line, |(_, rest)| rest).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let instruction = line
+            .parse::<Instruction>()
+            .map_err(|e| anyhow::anyhow!("line {}: {}", line_number + 1, e))?;
+        program.push(instruction.opcode as u64);
+        program.push(instruction.operand);
+    }
+    Ok(program)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A condition on a register that stops [`Machine::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Watch {
+    /// Stop whenever the register changes.
+    Changed(Register),
+    /// Stop when the comparison becomes true.
+    Compare(Register, Comparison, u64),
+}
+
+impl Watch {
+    fn triggered(&self, before: &Registers, after: &Registers) -> bool {
+        match self {
+            Watch::Changed(r) => before.get(*r) != after.get(*r),
+            Watch::Compare(r, cmp, value) => {
+                let holds = |regs: &Registers| {
+                    let v = regs.get(*r);
+                    match cmp {
+                        Comparison::Eq => v == *value,
+                        Comparison::Ne => v != *value,
+                        Comparison::Lt => v < *value,
+                        Comparison::Le => v <= *value,
+                        Comparison::Gt => v > *value,
+                        Comparison::Ge => v >= *value,
+                    }
+                };
+                !holds(before) && holds(after)
+            }
+        }
+    }
+}
+
+impl FromStr for Watch {
+    type Err = anyhow::Error;
+
+    /// Parses `a changed` or a comparison such as `b == 3` or `c<10`.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(register) = s.strip_suffix("changed") {
+            return Ok(Watch::Changed(register.trim().parse()?));
+        }
+        // Longest operators first so `<=` isn't read as `<`.
+        let operators = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ];
+        let (register, cmp, value) = operators
+            .iter()
+            .find_map(|(op, cmp)| s.split_once(op).map(|(l, r)| (l, cmp, r)))
+            .ok_or_else(|| anyhow::anyhow!("invalid watch {s:?}"))?;
+        Ok(Watch::Compare(
+            register.trim().parse()?,
+            *cmp,
+            value.trim().parse()?,
+        ))
+    }
+}
+
+/// One executed instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub before: Registers,
+    pub after: Registers,
+    pub output: Option<u64>,
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>3}: {:<8} a={} b={} c={}",
+            self.pc,
+            self.instruction.to_string(),
+            self.after.a,
+            self.after.b,
+            self.after.c
+        )?;
+        if let Some(out) = self.output {
+            write!(f, " out={}", out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why [`Machine::run`] stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    /// The program counter ran off the end of the program.
+    Halted,
+    /// About to execute the instruction at this address.
+    Breakpoint(usize),
+    /// The watch at this index in [`Machine::watches`] triggered.
+    Watch(usize),
+    /// The step limit given to [`Machine::run_for`] ran out.
+    StepLimit,
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine {
+    program: Vec<u64>,
+    pub registers: Registers,
+    pub pc: usize,
+    output: Vec<u64>,
+    pub breakpoints: BTreeSet<usize>,
+    pub watches: Vec<Watch>,
+    trace: Option<Vec<TraceEntry>>,
+}
+
+impl Machine {
+    pub fn new(program: &[u64], registers: Registers) -> Result<Self> {
+        anyhow::ensure!(
+            program.iter().all(|v| *v < 8),
+            "program values must be 3 bits"
+        );
+        Ok(Machine {
+            program: program.to_vec(),
+            registers,
+            pc: 0,
+            output: vec![],
+            breakpoints: BTreeSet::new(),
+            watches: vec![],
+            trace: None,
+        })
+    }
+
+    pub fn program(&self) -> &[u64] {
+        &self.program
+    }
+
+    pub fn output(&self) -> &[u64] {
+        &self.output
+    }
+
+    /// Start recording every executed instruction.
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    /// Instructions executed since tracing was enabled.
+    pub fn trace(&self) -> &[TraceEntry] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    pub fn halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    /// The instruction at the program counter, or None once halted.
+    pub fn current(&self) -> Result<Option<Instruction>> {
+        let Some(opcode) = self.program.get(self.pc) else {
+            return Ok(None);
+        };
+        let operand = *self
+            .program
+            .get(self.pc + 1)
+            .ok_or_else(|| anyhow::anyhow!("Invalid operand at {}", self.pc + 1))?;
+        Ok(Some(Instruction {
+            opcode: Opcode::try_from(*opcode)?,
+            operand,
+        }))
+    }
+
+    fn combo(&self, operand: u64) -> Result<u64> {
+        Ok(match operand {
+            0..=3 => operand,
+            4 => self.registers.a,
+            5 => self.registers.b,
+            6 => self.registers.c,
+            _ => anyhow::bail!("Invalid combo: {operand} at pc {}", self.pc),
+        })
+    }
+
+    /// Execute a single instruction.  Returns false if the machine had halted.
+    pub fn step(&mut self) -> Result<bool> {
+        let Some(instruction) = self.current()? else {
+            return Ok(false);
+        };
+        let before = self.registers;
+        let pc = self.pc;
+        let shifted = |value: u64, by: u64| value.checked_shr(by.try_into().unwrap_or(u32::MAX));
+        let operand = instruction.operand;
+        let mut output = None;
+
+        self.pc += 2;
+        match instruction.opcode {
+            Opcode::Adv => {
+                self.registers.a = shifted(self.registers.a, self.combo(operand)?).unwrap_or(0)
+            }
+            Opcode::Bxl => self.registers.b ^= operand,
+            Opcode::Bst => self.registers.b = self.combo(operand)? % 8,
+            Opcode::Jnz => {
+                if self.registers.a != 0 {
+                    self.pc = operand.try_into()?;
+                }
+            }
+            Opcode::Bxc => self.registers.b ^= self.registers.c,
+            Opcode::Out => output = Some(self.combo(operand)? % 8),
+            Opcode::Bdv => {
+                self.registers.b = shifted(self.registers.a, self.combo(operand)?).unwrap_or(0)
+            }
+            Opcode::Cdv => {
+                self.registers.c = shifted(self.registers.a, self.combo(operand)?).unwrap_or(0)
+            }
+        }
+        self.output.extend(output);
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(TraceEntry {
+                pc,
+                instruction,
+                before,
+                after: self.registers,
+                output,
+            });
+        }
+        Ok(true)
+    }
+
+    /// Run until the machine halts, hits a breakpoint or a watch triggers.
+    ///
+    /// The instruction at the program counter is always executed, so calling
+    /// run again continues past the breakpoint it stopped on.
+    pub fn run(&mut self) -> Result<Stop> {
+        self.run_for(usize::MAX)
+    }
+
+    /// [`Machine::run`], stopping after at most `max_steps` instructions.
+    pub fn run_for(&mut self, max_steps: usize) -> Result<Stop> {
+        for _ in 0..max_steps {
+            let before = self.registers;
+            if !self.step()? {
+                return Ok(Stop::Halted);
+            }
+            if let Some(index) = self
+                .watches
+                .iter()
+                .position(|w| w.triggered(&before, &self.registers))
+            {
+                return Ok(Stop::Watch(index));
+            }
+            if self.halted() {
+                return Ok(Stop::Halted);
+            }
+            if self.breakpoints.contains(&self.pc) {
+                return Ok(Stop::Breakpoint(self.pc));
+            }
+        }
+        Ok(Stop::StepLimit)
+    }
+
+    /// Run to completion ignoring breakpoints and watches, returning the output.
+    pub fn run_to_end(&mut self) -> Result<&[u64]> {
+        while self.step()? {}
+        Ok(&self.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const PROGRAM: &[u64] = &[2, 4, 1, 7, 7, 5, 0, 3, 1, 7, 4, 1, 5, 5, 3, 0];
+
+    #[test]
+    fn disassemble_resolves_combo() {
+        let text = disassemble(PROGRAM).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "  0: bst a   ; b = a % 8");
+        assert_eq!(lines[2], "  4: cdv b   ; c = a >> b");
+        assert_eq!(lines[5], " 10: bxc 1   ; b = b ^ c");
+        assert_eq!(lines[7], " 14: jnz 0   ; if a != 0 goto 0");
+        assert_eq!(assemble(&text).unwrap(), PROGRAM);
+    }
+
+    #[test]
+    fn assemble_errors() {
+        assert!(assemble("mul a").is_err());
+        assert!(assemble("bxl 8").is_err());
+        assert!(assemble("out").is_err());
+        assert!(assemble("out d").is_err());
+        assert_eq!(assemble("  ; nothing\nOUT B\n").unwrap(), vec![5, 5]);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn round_trip_random_programs(
+            instructions in prop::collection::vec((0u64..8, 0u64..8), 0..20)
+        ) {
+            let program = instructions
+                .into_iter()
+                .flat_map(|(opcode, operand)| [opcode, operand])
+                .collect::<Vec<_>>();
+            let text = disassemble(&program).unwrap();
+            prop_assert_eq!(assemble(&text).unwrap(), program, "{}", text);
+            prop_assert_eq!(disassemble(&assemble(&text).unwrap()).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn breakpoints_and_watches() {
+        let registers = Registers {
+            a: 64012472,
+            ..Default::default()
+        };
+        let mut machine = Machine::new(PROGRAM, registers).unwrap();
+        machine.breakpoints.insert(12);
+        assert_eq!(machine.run().unwrap(), Stop::Breakpoint(12));
+        assert!(machine.output().is_empty());
+        // Continuing executes the out and comes back around.
+        assert_eq!(machine.run().unwrap(), Stop::Breakpoint(12));
+        assert_eq!(machine.output().len(), 1);
+
+        machine.breakpoints.clear();
+        machine.watches.push("a == 0".parse().unwrap());
+        machine.enable_trace();
+        assert_eq!(machine.run().unwrap(), Stop::Watch(0));
+        assert_eq!(machine.registers.a, 0);
+        let last = machine.trace().last().unwrap();
+        assert_eq!(last.instruction.to_string(), "adv 3");
+        assert_eq!(machine.run().unwrap(), Stop::Halted);
+        assert_eq!(machine.output(), &[1, 0, 2, 0, 5, 7, 2, 1, 3]);
+    }
+
+    #[test]
+    fn parse_watches() {
+        assert_eq!(
+            "b changed".parse::<Watch>().unwrap(),
+            Watch::Changed(Register::B)
+        );
+        assert_eq!(
+            "c<=10".parse::<Watch>().unwrap(),
+            Watch::Compare(Register::C, Comparison::Le, 10)
+        );
+        assert!("d > 1".parse::<Watch>().is_err());
+    }
+}
@@ -1,35 +1,78 @@
+pub mod blocking;
 mod generics;
-use crate::{add_xy, Direction, Position, Result};
+pub mod matrix;
+use crate::{add_xy, Direction, Header, Position, Result};
 use aoc_runner_derive::aoc;
-use generics::{HashContainer, Map, MutMap};
+use blocking::{BlockingSolver, Strategy};
+use generics::{HashContainer, Map, MutMap, NewMap};
 use std::fmt::Display;
 
 pub const DAY: u32 = 18;
 
-/// The size of our map (depending if we're in test or not).
-#[cfg(test)]
-const MAPSIZE: Position = (7, 7);
-#[cfg(not(test))]
-const MAPSIZE: Position = (71, 71);
+/// The most cells a `size=` header may ask for, 256x256 or a dozen times the
+/// puzzle's 71x71, small enough to search quickly.
+pub const MAX_CELLS: usize = 1 << 16;
 
-/// For part 1, how many rocks should we drop before finding a path.
-#[cfg(test)]
-const FALL_COUNT: usize = 12;
-#[cfg(not(test))]
-const FALL_COUNT: usize = 1024;
+/// The size of the memory space and how many bytes fall before part 1's path
+/// is found.  The puzzle and its example only differ in these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scenario {
+    pub size: Position,
+    pub fall_count: usize,
+}
+
+impl Scenario {
+    pub const PUZZLE: Scenario = Scenario {
+        size: (71, 71),
+        fall_count: 1024,
+    };
+    pub const EXAMPLE: Scenario = Scenario {
+        size: (7, 7),
+        fall_count: 12,
+    };
+
+    /// Split an optional header such as `size=7x7` and `fall_count=12` off
+    /// the input.  Anything the header leaves out comes from `default`.
+    pub fn parse(s: &str, default: Scenario) -> Result<(Scenario, &str)> {
+        let (header, rest) = Header::split(s)?;
+        header.ensure_known(&["size", "fall_count"])?;
+        let size = match header.get::<String>("size")? {
+            Some(size) => {
+                let (w, h) = size
+                    .split_once('x')
+                    .ok_or_else(|| anyhow::anyhow!("size should look like 71x71"))?;
+                let (w, h): Position = (w.parse()?, h.parse()?);
+                if w.checked_mul(h).is_none_or(|cells| cells > MAX_CELLS) {
+                    anyhow::bail!("size {w}x{h} is over {MAX_CELLS} cells");
+                }
+                (w, h)
+            }
+            None => default.size,
+        };
+        let fall_count = header.get("fall_count")?.unwrap_or(default.fall_count);
+        Ok((Scenario { size, fall_count }, rest))
+    }
+}
 
 /// A PathFinder is something that can find a path through a map.
 pub trait PathFinder {
     fn find_path(&self, map: &impl Map) -> Result<Vec<Position>>;
 }
 
+impl<F: PathFinder> PathFinder for &F {
+    fn find_path(&self, map: &impl Map) -> Result<Vec<Position>> {
+        (*self).find_path(map)
+    }
+}
+
 fn solve_part1_impl(
     falling_rocks: impl Iterator<Item = Result<Position>>,
     mut map: impl Map + MutMap,
     path_finder: impl PathFinder,
+    fall_count: usize,
 ) -> Result<usize> {
     // take fall count rocks from the iterator.
-    let rocks_to_add = falling_rocks.take(FALL_COUNT);
+    let rocks_to_add = falling_rocks.take(fall_count);
 
     // Add all of these rocks to the map
     for rock in rocks_to_add {
@@ -143,11 +186,12 @@ fn print_map(map: &impl Map) {
 }
 
 /// Path finding using the Fringe algorithm.
-#[allow(dead_code)]
-struct FringePathFinder {}
+#[derive(Default)]
+pub struct FringePathFinder {}
 impl PathFinder for FringePathFinder {
     fn find_path(&self, map: &impl Map) -> Result<Vec<Position>> {
         let (start, end) = (map.start(), map.end());
+        ensure_start_open(map)?;
         let shortest = pathfinding::directed::fringe::fringe(
             &start,
             |xy| valid_map_steps(map, *xy).map(add_cost),
@@ -160,11 +204,12 @@ impl PathFinder for FringePathFinder {
 }
 
 /// Path finding using the Dijkstra algorithm.
-#[allow(dead_code)]
-struct DijkstraPathFinder {}
+#[derive(Default)]
+pub struct DijkstraPathFinder {}
 impl PathFinder for DijkstraPathFinder {
     fn find_path(&self, map: &impl Map) -> Result<Vec<Position>> {
         let (start, end) = (map.start(), map.end());
+        ensure_start_open(map)?;
         let shortest = pathfinding::directed::dijkstra::dijkstra(
             &start,
             |xy| valid_map_steps(map, *xy).map(add_cost),
@@ -176,11 +221,12 @@ impl PathFinder for DijkstraPathFinder {
 }
 
 /// Path finding using the A* algorithm.
-#[allow(dead_code)]
-struct AStarPathFinder {}
+#[derive(Default)]
+pub struct AStarPathFinder {}
 impl PathFinder for AStarPathFinder {
     fn find_path(&self, map: &impl Map) -> Result<Vec<Position>> {
         let (start, end) = (map.start(), map.end());
+        ensure_start_open(map)?;
         let shortest = pathfinding::directed::astar::astar(
             &start,
             |xy| valid_map_steps(map, *xy).map(add_cost),
@@ -192,6 +238,13 @@ impl PathFinder for AStarPathFinder {
     }
 }
 
+/// A byte on the start cell blocks every path, even though path finders never
+/// check the cell they start from.
+fn ensure_start_open(map: &impl Map) -> Result<()> {
+    anyhow::ensure!(map.can_move_to(&map.start()), "start is blocked");
+    Ok(())
+}
+
 /// Helper function to add a cost of 1 to the value for pathfinding.
 fn add_cost<T>(value: T) -> (T, usize) {
     (value, 1)
@@ -226,36 +279,53 @@ fn valid_map_steps(map: &impl Map, cur_xy: Position) -> impl Iterator<Item = Pos
     .flatten()
 }
 
-/// Create a map (multiple possible options here).
-fn create_map() -> impl Map + MutMap {
-    //use generics::BoundedMap;
-    //use std::collections::BTreeSet;
-    //BoundedMap::<BTreeSet<Position>>::new(MAPSIZE) // Occupied positions backed by a BTreeSet
-    // use std::collections::HashSet;
-    // BoundedMap::<HashSet<Position>>::new(MAPSIZE) // Occupied positions backed by a hash set
-    //BoundedMap::<Vec<Position>>::new(MAPSIZE) // Occupied positions backed by a Vec
-    vec![vec![false; MAPSIZE.0]; MAPSIZE.1] // 2D array represented by vectors
-                                            //[[false; MAPSIZE.0]; MAPSIZE.1] // 2D array
+/// Create the default map.  See [`matrix::combinations`] for the others.
+fn create_map(size: Position) -> impl Map + MutMap {
+    Vec::<Vec<bool>>::new_map(size) // 2D array represented by vectors
 }
 
-/// Create a path finder (multiple possible options here).
+/// Create the default path finder.  See [`matrix::combinations`] for the others.
 fn create_finder() -> impl PathFinder {
     DijkstraPathFinder {}
-    //AStarPathFinder{}
-    //FringePathFinder{}
 }
 
 /// Solution to part 1
 #[aoc(day18, part1)]
 fn solve_part1(input: &str) -> Result<usize> {
-    solve_part1_impl(parse(input), create_map(), create_finder())
+    solve_part1_with(input, Scenario::PUZZLE)
 }
 
 /// Solution to part 2
 #[aoc(day18, part2)]
 fn solve_part2(input: &str) -> Result<String> {
-    let falling = parse(input);
-    let solution = solve_part2_impl(falling, create_map(), create_finder())?;
+    solve_part2_with(input, Scenario::PUZZLE)
+}
+
+/// Part 1 for any scenario.  A header in the input overrides `scenario`.
+pub fn solve_part1_with(input: &str, scenario: Scenario) -> Result<usize> {
+    let (scenario, input) = Scenario::parse(input, scenario)?;
+    solve_part1_impl(
+        parse(input),
+        create_map(scenario.size),
+        create_finder(),
+        scenario.fall_count,
+    )
+}
+
+/// Part 2 for any scenario.  A header in the input overrides `scenario`.
+pub fn solve_part2_with(input: &str, scenario: Scenario) -> Result<String> {
+    solve_part2_using(input, scenario, Strategy::RockUnionFind.solver().as_ref())
+}
+
+/// Part 2 for any scenario, finding the blocking byte with `solver`.
+pub fn solve_part2_using(
+    input: &str,
+    scenario: Scenario,
+    solver: &dyn BlockingSolver,
+) -> Result<String> {
+    let (scenario, input) = Scenario::parse(input, scenario)?;
+    let falling = parse(input).collect::<Result<Vec<_>>>()?;
+    let solution = solver.first_blocking(scenario.size, &falling)?;
     Ok(format!("{},{}", solution.0, solution.1))
 }
 
@@ -279,6 +349,43 @@ fn parse(s: &str) -> impl Iterator<Item = Result<Position>> + '_ {
     coords
 }
 
+/// A small memory space and distinct bytes falling into it, for property
+/// tests comparing the solvers.
+#[cfg(test)]
+pub(crate) fn falling_bytes() -> impl proptest::strategy::Strategy<Value = (Scenario, Vec<Position>)>
+{
+    use proptest::prelude::*;
+    (1..12usize, 1..12usize)
+        .prop_flat_map(|(w, h)| {
+            let bytes = proptest::collection::vec((0..w, 0..h), 0..w * h);
+            (Just((w, h)), bytes)
+        })
+        .prop_flat_map(|(size, mut bytes)| {
+            let mut seen = std::collections::HashSet::new();
+            bytes.retain(|b| seen.insert(*b));
+            let fall_count = 0..=bytes.len();
+            (fall_count, Just(bytes))
+                .prop_map(move |(fall_count, bytes)| (Scenario { size, fall_count }, bytes))
+        })
+}
+
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    let (scenario, input) = Scenario::parse(input, Scenario::PUZZLE)?;
+    let bytes = parse(input).collect::<Result<Vec<_>>>()?;
+    Ok((scenario, bytes))
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -300,35 +407,62 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(solve_part1(&test_data(super::DAY).unwrap()).unwrap(), 22);
+        let input = test_data(super::DAY).unwrap();
+        assert_eq!(solve_part1_with(&input, Scenario::EXAMPLE).unwrap(), 22);
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(solve_part2(&test_data(super::DAY).unwrap()).unwrap(), "6,1");
+        let input = test_data(super::DAY).unwrap();
+        assert_eq!(solve_part2_with(&input, Scenario::EXAMPLE).unwrap(), "6,1");
+    }
+
+    #[test]
+    fn scenario_header() {
+        let input = format!(
+            "size=7x7\nfall_count=12\n\n{}",
+            test_data(super::DAY).unwrap()
+        );
+        assert_eq!(solve_part1(&input).unwrap(), 22);
+        assert_eq!(solve_part2(&input).unwrap(), "6,1");
+
+        // Only the fall count given, so the size comes from the default.
+        let (scenario, rest) = Scenario::parse("fall_count=3\n\n1,2\n", Scenario::PUZZLE).unwrap();
+        assert_eq!(scenario.size, (71, 71));
+        assert_eq!(scenario.fall_count, 3);
+        assert_eq!(rest, "1,2\n");
+
+        assert!(Scenario::parse("size=7\n\n", Scenario::PUZZLE).is_err());
+        assert!(Scenario::parse("falls=7\n\n", Scenario::PUZZLE).is_err());
+        assert!(Scenario::parse("size=7x7\n1,2", Scenario::PUZZLE).is_err());
+        let huge = "size=18446744073709551615x18446744073709551615\n\n";
+        assert!(Scenario::parse(huge, Scenario::PUZZLE).is_err());
+        assert!(Scenario::parse("size=100000x100000\n\n", Scenario::PUZZLE).is_err());
+        assert!(Scenario::parse("size=256x256\n\n", Scenario::PUZZLE).is_ok());
+        assert!(Scenario::parse("size=257x256\n\n", Scenario::PUZZLE).is_err());
     }
 
     fn test_affected_by_generic<T: PreviousPath + Default>() {
         let mut prev_path = T::default();
         // always starts with returning true
-        assert_eq!(prev_path.will_be_affected_by(&(0, 0)), true);
-        assert_eq!(prev_path.will_be_affected_by(&(1, 1)), true);
+        assert!(prev_path.will_be_affected_by(&(0, 0)));
+        assert!(prev_path.will_be_affected_by(&(1, 1)));
         // remember a path
         prev_path.remember_path(vec![(0, 0), (1, 1)]);
         // now it should return true for the path remembered
-        assert_eq!(prev_path.will_be_affected_by(&(0, 0)), true);
-        assert_eq!(prev_path.will_be_affected_by(&(1, 1)), true);
+        assert!(prev_path.will_be_affected_by(&(0, 0)));
+        assert!(prev_path.will_be_affected_by(&(1, 1)));
         // but not for others
-        assert_eq!(prev_path.will_be_affected_by(&(0, 1)), false);
-        assert_eq!(prev_path.will_be_affected_by(&(1, 0)), false);
+        assert!(!prev_path.will_be_affected_by(&(0, 1)));
+        assert!(!prev_path.will_be_affected_by(&(1, 0)));
         // try another
         prev_path.remember_path(vec![(0, 1), (1, 0)]);
         // now it should return true for the path remembered
-        assert_eq!(prev_path.will_be_affected_by(&(0, 1)), true);
-        assert_eq!(prev_path.will_be_affected_by(&(1, 0)), true);
+        assert!(prev_path.will_be_affected_by(&(0, 1)));
+        assert!(prev_path.will_be_affected_by(&(1, 0)));
         // and not the one before
-        assert_eq!(prev_path.will_be_affected_by(&(0, 0)), false);
-        assert_eq!(prev_path.will_be_affected_by(&(1, 1)), false);
+        assert!(!prev_path.will_be_affected_by(&(0, 0)));
+        assert!(!prev_path.will_be_affected_by(&(1, 1)));
     }
 
     #[test]
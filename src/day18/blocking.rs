@@ -0,0 +1,418 @@
+//! Ways of finding the first falling byte that cuts the exit off from the start.
+//!
+//! Part 2 only needs one answer for a known list of bytes, so the search doesn't
+//! have to be done as the bytes fall.  Each [`BlockingSolver`] takes the whole
+//! list at once:
+//!
+//! - [`Incremental`] drops bytes one by one and reruns a [`PathFinder`] only
+//!   when a byte lands on the last path found (the original part 2).
+//! - [`BinarySearch`] binary searches for the shortest blocked prefix, so any
+//!   [`PathFinder`] runs O(log n) times.
+//! - [`ReverseUnionFind`] drops every byte, then takes them away again in
+//!   reverse, joining free cells in a union-find until start and end meet.
+//! - [`RockUnionFind`] joins the bytes themselves instead.  The path is cut as
+//!   soon as touching bytes form a wall from the top or right edge to the bottom
+//!   or left edge, so only the bytes are visited and never the free cells.
+
+use super::{create_map, generics::MutMap, solve_part2_impl, DijkstraPathFinder, PathFinder};
+use crate::{Position, Result};
+use std::{fmt::Display, str::FromStr};
+
+pub trait BlockingSolver {
+    /// The first byte after which there is no path from the top left to the
+    /// bottom right of a map of the given size.
+    fn first_blocking(&self, size: Position, bytes: &[Position]) -> Result<Position>;
+}
+
+/// Drop bytes in order, rerunning the path finder when the last path is hit.
+pub struct Incremental<F>(pub F);
+
+impl<F: PathFinder> BlockingSolver for Incremental<F> {
+    fn first_blocking(&self, size: Position, bytes: &[Position]) -> Result<Position> {
+        solve_part2_impl(bytes.iter().copied().map(Ok), create_map(size), &self.0)
+    }
+}
+
+/// Binary search on how many bytes have fallen, using any path finder.
+pub struct BinarySearch<F>(pub F);
+
+impl<F: PathFinder> BinarySearch<F> {
+    fn blocked(&self, size: Position, bytes: &[Position]) -> Result<bool> {
+        let mut map = create_map(size);
+        for byte in bytes {
+            map.add_rock(*byte)?;
+        }
+        Ok(self.0.find_path(&map).is_err())
+    }
+}
+
+impl<F: PathFinder> BlockingSolver for BinarySearch<F> {
+    fn first_blocking(&self, size: Position, bytes: &[Position]) -> Result<Position> {
+        if !self.blocked(size, bytes)? {
+            anyhow::bail!("no solution found");
+        }
+        // The path is open after `low` bytes and blocked after `high`.
+        let (mut low, mut high) = (0, bytes.len());
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if self.blocked(size, &bytes[..mid])? {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Ok(bytes[high - 1])
+    }
+}
+
+/// A disjoint set forest with path halving and union by size.
+struct UnionFind {
+    parent: Vec<u32>,
+    size: Vec<u32>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        UnionFind {
+            parent: (0..len as u32).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    fn find(&mut self, mut x: u32) -> u32 {
+        while self.parent[x as usize] != x {
+            let grandparent = self.parent[self.parent[x as usize] as usize];
+            self.parent[x as usize] = grandparent;
+            x = grandparent;
+        }
+        x
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (big, small) = if self.size[a as usize] >= self.size[b as usize] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[small as usize] = big;
+        self.size[big as usize] += self.size[small as usize];
+    }
+
+    fn connected(&mut self, a: u32, b: u32) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+/// Index of every byte in a flat row major grid, failing if one is off the map.
+fn cell_indices(size: Position, bytes: &[Position]) -> Result<Vec<usize>> {
+    anyhow::ensure!(size.0 > 0 && size.1 > 0, "map is empty");
+    anyhow::ensure!(
+        size.0 * size.1 <= u32::MAX as usize,
+        "map is too large for a union-find"
+    );
+    bytes
+        .iter()
+        .map(|&(x, y)| {
+            anyhow::ensure!(x < size.0 && y < size.1, "byte {x},{y} is off the map");
+            Ok(y * size.0 + x)
+        })
+        .collect()
+}
+
+/// Remove the bytes in reverse, joining free cells until start meets end.
+pub struct ReverseUnionFind;
+
+impl BlockingSolver for ReverseUnionFind {
+    fn first_blocking(&self, size: Position, bytes: &[Position]) -> Result<Position> {
+        let (width, height) = size;
+        let cells = cell_indices(size, bytes)?;
+        let (start, end) = (0, (width * height).saturating_sub(1) as u32);
+
+        // A cell frees up when the first byte to land on it is removed.
+        let mut first_byte = vec![usize::MAX; width * height];
+        for (i, &cell) in cells.iter().enumerate().rev() {
+            first_byte[cell] = i;
+        }
+        let mut free = first_byte
+            .iter()
+            .map(|i| *i == usize::MAX)
+            .collect::<Vec<_>>();
+
+        let mut sets = UnionFind::new(width * height);
+        let join_neighbours = |sets: &mut UnionFind, free: &[bool], cell: usize| {
+            let (x, y) = (cell % width, cell / width);
+            let neighbours = [
+                (x > 0).then(|| cell - 1),
+                (x + 1 < width).then(|| cell + 1),
+                (y > 0).then(|| cell - width),
+                (y + 1 < height).then(|| cell + width),
+            ];
+            for neighbour in neighbours.into_iter().flatten() {
+                if free[neighbour] {
+                    sets.union(cell as u32, neighbour as u32);
+                }
+            }
+        };
+
+        for cell in 0..width * height {
+            // Right and down neighbours are enough to join the whole grid.
+            if free[cell] {
+                if cell % width + 1 < width && free[cell + 1] {
+                    sets.union(cell as u32, cell as u32 + 1);
+                }
+                if cell + width < width * height && free[cell + width] {
+                    sets.union(cell as u32, (cell + width) as u32);
+                }
+            }
+        }
+        if free[start as usize] && sets.connected(start, end) {
+            anyhow::bail!("no solution found");
+        }
+
+        for (i, &cell) in cells.iter().enumerate().rev() {
+            if first_byte[cell] != i {
+                continue;
+            }
+            free[cell] = true;
+            join_neighbours(&mut sets, &free, cell);
+            if free[start as usize] && free[end as usize] && sets.connected(start, end) {
+                return Ok(bytes[i]);
+            }
+        }
+        anyhow::bail!("start and end are never connected")
+    }
+}
+
+/// Join bytes that touch, including diagonally, until a wall of them splits
+/// the map.
+pub struct RockUnionFind;
+
+impl BlockingSolver for RockUnionFind {
+    fn first_blocking(&self, size: Position, bytes: &[Position]) -> Result<Position> {
+        let (width, height) = size;
+        let cells = cell_indices(size, bytes)?;
+
+        // Two extra sets stand for the edges either side of the path.
+        let (top_right, bottom_left) = (0, 1);
+        let mut sets = UnionFind::new(bytes.len() + 2);
+        // Which cells hold a byte, as a bitset small enough to stay in cache,
+        // and the set of the byte on each of them.
+        let mut occupied = vec![0u64; (width * height).div_ceil(64)];
+        let is_occupied =
+            |occupied: &[u64], cell: usize| occupied[cell / 64] & (1 << (cell % 64)) != 0;
+        let mut byte_at = vec![0u32; width * height];
+
+        for (i, (&(x, y), &cell)) in bytes.iter().zip(cells.iter()).enumerate() {
+            if is_occupied(&occupied, cell) {
+                continue;
+            }
+            let set = i as u32 + 2;
+            occupied[cell / 64] |= 1 << (cell % 64);
+            byte_at[cell] = set;
+            if y == 0 || x + 1 == width {
+                sets.union(set, top_right);
+            }
+            if y + 1 == height || x == 0 {
+                sets.union(set, bottom_left);
+            }
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    let neighbour = ny * width + nx;
+                    if neighbour != cell && is_occupied(&occupied, neighbour) {
+                        sets.union(set, byte_at[neighbour]);
+                    }
+                }
+            }
+            if sets.connected(top_right, bottom_left) {
+                return Ok(bytes[i]);
+            }
+        }
+        anyhow::bail!("no solution found")
+    }
+}
+
+/// The available solvers, for choosing one by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Incremental,
+    BinarySearch,
+    ReverseUnionFind,
+    RockUnionFind,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 4] = [
+        Strategy::Incremental,
+        Strategy::BinarySearch,
+        Strategy::ReverseUnionFind,
+        Strategy::RockUnionFind,
+    ];
+
+    /// The solver, using Dijkstra where a path finder is needed.
+    pub fn solver(self) -> Box<dyn BlockingSolver> {
+        match self {
+            Strategy::Incremental => Box::new(Incremental(DijkstraPathFinder {})),
+            Strategy::BinarySearch => Box::new(BinarySearch(DijkstraPathFinder {})),
+            Strategy::ReverseUnionFind => Box::new(ReverseUnionFind),
+            Strategy::RockUnionFind => Box::new(RockUnionFind),
+        }
+    }
+}
+
+impl Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Strategy::Incremental => "incremental",
+            Strategy::BinarySearch => "binary-search",
+            Strategy::ReverseUnionFind => "reverse-union-find",
+            Strategy::RockUnionFind => "rock-union-find",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Strategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.to_string() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown strategy {s}"))
+    }
+}
+
+/// Bytes for a `size` map that build a wall down the middle, shuffled in with
+/// `extra` bytes scattered at random.  The answer is the last wall byte.
+///
+/// Scattered bytes stay off the top and bottom rows and the columns either
+/// side of the wall, so there is always a way round them to the gap left in
+/// the wall.  The map must be at least four wide and three high, or no cell
+/// is left to scatter over.
+pub fn walled_bytes(size: Position, extra: usize, seed: u64) -> Result<Vec<Position>> {
+    let (width, height) = size;
+    anyhow::ensure!(
+        width >= 4 && height >= 3,
+        "walled maps must be at least 4x3, not {width}x{height}"
+    );
+    let mut state = seed | 1;
+    let mut random = move |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+    let wall_x = width / 2;
+    let mut bytes = (0..height).map(|y| (wall_x, y)).collect::<Vec<_>>();
+    let scattered = std::iter::repeat_with(|| (random(width), random(height)))
+        .filter(|&(x, y)| y > 0 && y + 1 < height && x.abs_diff(wall_x) > 1)
+        .take(extra)
+        .collect::<Vec<_>>();
+    bytes.extend(scattered);
+    for i in (1..bytes.len()).rev() {
+        bytes.swap(i, random(i + 1));
+    }
+    // Move the last wall byte to the end.
+    let last_wall = bytes
+        .iter()
+        .rposition(|&(x, _)| x == wall_x)
+        .expect("the wall has at least one byte");
+    let byte = bytes.remove(last_wall);
+    bytes.push(byte);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        day18::{parse, AStarPathFinder, FringePathFinder},
+        test_data, TestRng,
+    };
+
+    fn solvers() -> Vec<(String, Box<dyn BlockingSolver>)> {
+        let mut solvers = Strategy::ALL
+            .into_iter()
+            .map(|s| (s.to_string(), s.solver()))
+            .collect::<Vec<_>>();
+        solvers.push(("astar".into(), Box::new(BinarySearch(AStarPathFinder {}))));
+        solvers.push(("fringe".into(), Box::new(Incremental(FringePathFinder {}))));
+        solvers
+    }
+
+    #[test]
+    fn example() {
+        let bytes = parse(&test_data(super::super::DAY).unwrap())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        for (name, solver) in solvers() {
+            assert_eq!(
+                solver.first_blocking((7, 7), &bytes).unwrap(),
+                (6, 1),
+                "{name}"
+            );
+            assert!(
+                solver.first_blocking((7, 7), &bytes[..20]).is_err(),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn strategies_agree_on_random_maps() {
+        let mut rng = TestRng(18);
+        for _ in 0..100 {
+            let size = (1 + rng.below(12) as usize, 1 + rng.below(12) as usize);
+            let bytes = (0..rng.below(150))
+                .map(|_| {
+                    (
+                        rng.below(size.0 as u64) as usize,
+                        rng.below(size.1 as u64) as usize,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let answers = solvers()
+                .into_iter()
+                .map(|(name, solver)| (name, solver.first_blocking(size, &bytes).ok()))
+                .collect::<Vec<_>>();
+            for (name, answer) in answers.iter() {
+                assert_eq!(*answer, answers[0].1, "{name} on {size:?} {bytes:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn walled_bytes_block_last() {
+        let size = (40, 30);
+        let bytes = walled_bytes(size, 200, 7).unwrap();
+        assert_eq!(bytes.len(), 230);
+        assert_eq!(
+            RockUnionFind.first_blocking(size, &bytes).unwrap(),
+            *bytes.last().unwrap()
+        );
+    }
+
+    #[test]
+    fn walled_bytes_small_maps() {
+        // Too short or too narrow to leave anywhere for scattered bytes.
+        for size in [(10, 2), (3, 10), (0, 0)] {
+            assert!(walled_bytes(size, 5, 7).is_err(), "{size:?}");
+        }
+        let size = (4, 3);
+        let bytes = walled_bytes(size, 5, 7).unwrap();
+        assert_eq!(bytes.len(), 8);
+        assert!(bytes.iter().all(|&(x, y)| x < 4 && y < 3));
+    }
+
+    #[test]
+    fn strategy_names() {
+        for strategy in Strategy::ALL {
+            assert_eq!(strategy.to_string().parse::<Strategy>().unwrap(), strategy);
+        }
+        assert!("bfs".parse::<Strategy>().is_err());
+    }
+}
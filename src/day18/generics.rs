@@ -112,7 +112,6 @@ where
 
 /// A bounded map will implement Map and MutMap, and is
 /// backed by something that implements HashContainer.
-#[allow(dead_code)]
 pub struct BoundedMap<T>
 where
     T: HashContainer<Position>,
@@ -128,7 +127,6 @@ impl<T> BoundedMap<T>
 where
     T: HashContainer<Position> + Default,
 {
-    #[allow(dead_code)]
     /// Create a new BoundedMap with the given bounds.
     pub fn new(bounds: Position) -> Self {
         Self {
@@ -242,3 +240,106 @@ impl<const XBOUND: usize, const YBOUND: usize> Map for [[bool; XBOUND]; YBOUND]
         self.as_slice().can_move_to(pos)
     }
 }
+
+/// Maps that can be created empty for a given size, so a map type can be
+/// picked by the caller rather than by `create_map`.
+pub trait NewMap {
+    fn new_map(bounds: Position) -> Self;
+}
+
+impl<T> NewMap for BoundedMap<T>
+where
+    T: HashContainer<Position> + Default,
+{
+    fn new_map(bounds: Position) -> Self {
+        BoundedMap::new(bounds)
+    }
+}
+
+impl NewMap for Vec<Vec<bool>> {
+    fn new_map(bounds: Position) -> Self {
+        vec![vec![false; bounds.0]; bounds.1]
+    }
+}
+
+/// Occupied cells as one bit each, row by row.
+pub struct BitMap {
+    bits: Vec<u64>,
+    bounds: Position,
+}
+
+impl BitMap {
+    /// The bit index of a position, if it's on the map.
+    fn index(&self, pos: &Position) -> Option<usize> {
+        (pos.0 < self.bounds.0 && pos.1 < self.bounds.1).then(|| pos.1 * self.bounds.0 + pos.0)
+    }
+}
+
+impl NewMap for BitMap {
+    fn new_map(bounds: Position) -> Self {
+        BitMap {
+            bits: vec![0; (bounds.0 * bounds.1).div_ceil(64)],
+            bounds,
+        }
+    }
+}
+
+impl MutMap for BitMap {
+    fn add_rock(&mut self, rock: Position) -> Result<()> {
+        let i = self
+            .index(&rock)
+            .ok_or_else(|| anyhow::anyhow!("no cell at {:?}", rock))?;
+        self.bits[i / 64] |= 1 << (i % 64);
+        Ok(())
+    }
+}
+
+impl Map for BitMap {
+    fn can_move_to(&self, pos: &Position) -> bool {
+        self.index(pos)
+            .is_some_and(|i| self.bits[i / 64] & (1 << (i % 64)) == 0)
+    }
+    fn bound(&self) -> Position {
+        self.bounds
+    }
+}
+
+/// Occupied cells in a single Vec, indexed by `y * width + x`.
+pub struct FlatMap {
+    cells: Vec<bool>,
+    bounds: Position,
+}
+
+impl FlatMap {
+    fn index(&self, pos: &Position) -> Option<usize> {
+        (pos.0 < self.bounds.0 && pos.1 < self.bounds.1).then(|| pos.1 * self.bounds.0 + pos.0)
+    }
+}
+
+impl NewMap for FlatMap {
+    fn new_map(bounds: Position) -> Self {
+        FlatMap {
+            cells: vec![false; bounds.0 * bounds.1],
+            bounds,
+        }
+    }
+}
+
+impl MutMap for FlatMap {
+    fn add_rock(&mut self, rock: Position) -> Result<()> {
+        let i = self
+            .index(&rock)
+            .ok_or_else(|| anyhow::anyhow!("no cell at {:?}", rock))?;
+        self.cells[i] = true;
+        Ok(())
+    }
+}
+
+impl Map for FlatMap {
+    fn can_move_to(&self, pos: &Position) -> bool {
+        self.index(pos).is_some_and(|i| !self.cells[i])
+    }
+    fn bound(&self) -> Position {
+        self.bounds
+    }
+}
@@ -0,0 +1,153 @@
+//! Every map backend paired with every path finder, so they can be compared
+//! without editing `create_map` and `create_finder`.
+
+use super::{
+    generics::{BitMap, BoundedMap, FlatMap, Map, MutMap, NewMap},
+    solve_part1_impl, solve_part2_impl, AStarPathFinder, DijkstraPathFinder, FringePathFinder,
+    PathFinder, Scenario,
+};
+use crate::{Position, Result};
+use std::collections::{BTreeSet, HashSet};
+
+/// One map backend and path finder pair.
+#[derive(Clone, Copy)]
+pub struct Combination {
+    pub map: &'static str,
+    pub finder: &'static str,
+    part1: fn(&[Position], Scenario) -> Result<usize>,
+    part2: fn(&[Position], Scenario) -> Result<Position>,
+}
+
+impl Combination {
+    fn new<M, F>(map: &'static str, finder: &'static str) -> Self
+    where
+        M: Map + MutMap + NewMap,
+        F: PathFinder + Default,
+    {
+        Combination {
+            map,
+            finder,
+            part1: |bytes, scenario| {
+                solve_part1_impl(
+                    bytes.iter().copied().map(Ok),
+                    M::new_map(scenario.size),
+                    F::default(),
+                    scenario.fall_count,
+                )
+            },
+            part2: |bytes, scenario| {
+                solve_part2_impl(
+                    bytes.iter().copied().map(Ok),
+                    M::new_map(scenario.size),
+                    F::default(),
+                )
+            },
+        }
+    }
+
+    /// Length of the shortest path after `scenario.fall_count` bytes.
+    pub fn part1(&self, bytes: &[Position], scenario: Scenario) -> Result<usize> {
+        (self.part1)(bytes, scenario)
+    }
+
+    /// The first byte to cut off the exit, found incrementally.
+    pub fn part2(&self, bytes: &[Position], scenario: Scenario) -> Result<Position> {
+        (self.part2)(bytes, scenario)
+    }
+
+    /// `map+finder`, for naming benchmarks.
+    pub fn name(&self) -> String {
+        format!("{}+{}", self.map, self.finder)
+    }
+}
+
+fn with_every_finder<M: Map + MutMap + NewMap>(map: &'static str) -> [Combination; 3] {
+    [
+        Combination::new::<M, FringePathFinder>(map, "fringe"),
+        Combination::new::<M, DijkstraPathFinder>(map, "dijkstra"),
+        Combination::new::<M, AStarPathFinder>(map, "astar"),
+    ]
+}
+
+/// Every map backend with every path finder.
+pub fn combinations() -> Vec<Combination> {
+    [
+        with_every_finder::<Vec<Vec<bool>>>("vec-of-vecs"),
+        with_every_finder::<FlatMap>("flat"),
+        with_every_finder::<BitMap>("bitset"),
+        with_every_finder::<BoundedMap<HashSet<Position>>>("hash-set"),
+        with_every_finder::<BoundedMap<BTreeSet<Position>>>("btree-set"),
+        with_every_finder::<BoundedMap<Vec<Position>>>("vec"),
+    ]
+    .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day18::parse, test_data};
+    use proptest::prelude::*;
+
+    #[test]
+    fn every_combination_solves_the_example() {
+        let bytes = parse(&test_data(super::super::DAY).unwrap())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let combinations = combinations();
+        assert_eq!(combinations.len(), 18);
+        for c in combinations {
+            assert_eq!(
+                c.part1(&bytes, Scenario::EXAMPLE).unwrap(),
+                22,
+                "{}",
+                c.name()
+            );
+            assert_eq!(
+                c.part2(&bytes, Scenario::EXAMPLE).unwrap(),
+                (6, 1),
+                "{}",
+                c.name()
+            );
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn finders_agree((scenario, bytes) in crate::day18::falling_bytes()) {
+            let answers = combinations()
+                .into_iter()
+                .map(|c| {
+                    (
+                        c.name(),
+                        c.part1(&bytes, scenario).ok(),
+                        c.part2(&bytes, scenario).ok(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let (_, part1, part2) = &answers[0];
+            for (name, p1, p2) in &answers[1..] {
+                prop_assert_eq!(p1, part1, "{} part 1", name);
+                prop_assert_eq!(p2, part2, "{} part 2", name);
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_are_enforced() {
+        let mut map = BitMap::new_map((3, 2));
+        assert!(map.add_rock((3, 0)).is_err());
+        map.add_rock((2, 1)).unwrap();
+        assert!(!map.can_move_to(&(2, 1)));
+        assert!(map.can_move_to(&(1, 1)));
+        assert!(!map.can_move_to(&(0, 2)));
+
+        let mut map = FlatMap::new_map((3, 2));
+        assert!(map.add_rock((0, 2)).is_err());
+        map.add_rock((2, 1)).unwrap();
+        assert!(!map.can_move_to(&(2, 1)));
+        assert!(!map.can_move_to(&(3, 0)));
+        assert_eq!(Map::bound(&map), (3, 2));
+    }
+}
@@ -1,19 +1,9 @@
-use crate::{add_xy, Direction, GetCell, GetCellMut, Position, Result};
+use crate::{add_xy, day18::Scenario, Direction, GetCell, GetCellMut, Position, Result};
 use anyhow::Context as _;
 use std::{collections::HashSet, fmt::Display, hash::Hash};
 
 pub const DAY: u32 = 18;
 
-#[cfg(test)]
-const MAPSIZE: Position = (7, 7);
-#[cfg(not(test))]
-const MAPSIZE: Position = (71, 71);
-
-#[cfg(test)]
-const FALL_COUNT: usize = 12;
-#[cfg(not(test))]
-const FALL_COUNT: usize = 1024;
-
 trait Occupied<T> {
     fn contains(&self, value: &T) -> bool;
 }
@@ -59,25 +49,30 @@ where
 }
 
 fn solve_part1_impl(input: &Data) -> Result<usize> {
+    let Scenario { size, fall_count } = input.scenario;
     // Simulate falling (use hash set this time for funzies)
     let map = input
         .coords
         .iter()
-        .take(FALL_COUNT)
+        .take(fall_count)
         .copied()
         .collect::<HashSet<_>>();
 
+    // A byte on the start cell blocks every path, but pathfinding never
+    // checks the cell it starts from.
+    anyhow::ensure!(!map.contains(&(0, 0)), "start is blocked");
+
     // Do pathfinding
     let shortest = pathfinding::directed::dijkstra::dijkstra(
         &(0, 0),
-        |xy| valid_map_steps(&(&map, MAPSIZE), *xy),
-        |coord| *coord == (MAPSIZE.0 - 1, MAPSIZE.1 - 1),
+        |xy| valid_map_steps(&(&map, size), *xy),
+        |coord| *coord == (size.0 - 1, size.1 - 1),
     );
 
     // print_map(
     //     &map,
     //     &shortest.as_ref().unwrap().0.iter().copied().collect(),
-    //     MAPSIZE,
+    //     size,
     // );
 
     shortest
@@ -103,11 +98,12 @@ fn print_map(map: &HashSet<Position>, path: &HashSet<Position>, map_size: Positi
 }
 
 fn solve_part2_impl(input: &Data) -> Result<Position> {
-    let next_cell = input.coords.iter().copied().enumerate();
+    let size = input.scenario.size;
+    let next_cell = input.coords.iter().copied();
 
     // Simulate falling (use vector this time for funzies)
     // For part 2 the map is incrementally built
-    let mut map = vec![vec![false; MAPSIZE.0]; MAPSIZE.1];
+    let mut map = vec![vec![false; size.0]; size.1];
     let mut map = map.as_mut_slice();
 
     let mut prev_path: Option<Vec<Position>> = None;
@@ -115,7 +111,7 @@ fn solve_part2_impl(input: &Data) -> Result<Position> {
     // The critical path now doesn't allocate and is fast.
     // Well... except for the generation of the vec that the pathfinding
     // library does.
-    for (_count, cell) in next_cell {
+    for cell in next_cell {
         // Add the cell to the map
         {
             let c = map
@@ -136,14 +132,16 @@ fn solve_part2_impl(input: &Data) -> Result<Position> {
         let shortest_path = pathfinding::directed::dijkstra::dijkstra(
             &(0, 0),
             |xy| valid_map_steps(&map, *xy),
-            |coord| *coord == (MAPSIZE.0 - 1, MAPSIZE.1 - 1),
-        );
+            |coord| *coord == (size.0 - 1, size.1 - 1),
+        )
+        // As in part 1, a byte on the start cell blocks every path.
+        .filter(|_| cell != (0, 0));
         // astar_bag is quite slower than dijkstra for this map
         // let shortest = pathfinding::directed::astar::astar_bag(
         //     &(0, 0),
         //     |xy| valid_map_steps(map.clone(), *xy),
         //     |_| 0,
-        //     |coord| *coord == (MAPSIZE.0 - 1, MAPSIZE.1 - 1),
+        //     |coord| *coord == (size.0 - 1, size.1 - 1),
         // );
 
         // if let Some(mut shortest) = shortest {
@@ -201,13 +199,23 @@ fn valid_map_steps(
 
 /// Solution to part 1
 fn solve_part1(input: &str) -> Result<usize> {
-    let input = Data::parse(input).context("input parsing")?;
-    solve_part1_impl(&input)
+    solve_part1_with(input, Scenario::PUZZLE)
 }
 
 /// Solution to part 2
 fn solve_part2(input: &str) -> Result<String> {
-    let input = Data::parse(input).context("input parsing")?;
+    solve_part2_with(input, Scenario::PUZZLE)
+}
+
+/// Part 1 for any scenario.  A header in the input overrides `scenario`.
+pub fn solve_part1_with(input: &str, scenario: Scenario) -> Result<usize> {
+    let input = Data::parse(input, scenario).context("input parsing")?;
+    solve_part1_impl(&input)
+}
+
+/// Part 2 for any scenario.  A header in the input overrides `scenario`.
+pub fn solve_part2_with(input: &str, scenario: Scenario) -> Result<String> {
+    let input = Data::parse(input, scenario).context("input parsing")?;
     let ans = solve_part2_impl(&input)?;
     Ok(format!("{},{}", ans.0, ans.1))
 }
@@ -216,9 +224,11 @@ fn solve_part2(input: &str) -> Result<String> {
 #[derive(Debug)]
 struct Data {
     coords: Vec<Position>,
+    scenario: Scenario,
 }
 impl Data {
-    fn parse(s: &str) -> Result<Self> {
+    fn parse(s: &str, scenario: Scenario) -> Result<Self> {
+        let (scenario, s) = Scenario::parse(s, scenario)?;
         let s = s.lines();
         let coords = s
             .map(|line| {
@@ -231,7 +241,7 @@ impl Data {
             })
             .collect::<Result<_>>()?;
 
-        Ok(Data { coords })
+        Ok(Data { coords, scenario })
     }
 }
 
@@ -248,17 +258,40 @@ pub fn part2(input: &str) -> impl Display {
 #[cfg(test)]
 mod tests {
     use crate::test_data;
+    use proptest::prelude::*;
     use test_log::test;
 
     use super::*;
 
     #[test]
     fn part1_example() {
-        assert_eq!(solve_part1(&test_data(super::DAY).unwrap()).unwrap(), 22);
+        let input = test_data(super::DAY).unwrap();
+        assert_eq!(solve_part1_with(&input, Scenario::EXAMPLE).unwrap(), 22);
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(solve_part2(&test_data(super::DAY).unwrap()).unwrap(), "6,1");
+        let input = test_data(super::DAY).unwrap();
+        assert_eq!(solve_part2_with(&input, Scenario::EXAMPLE).unwrap(), "6,1");
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn agrees_with_day18((scenario, bytes) in crate::day18::falling_bytes()) {
+            let input = bytes
+                .iter()
+                .map(|(x, y)| format!("{x},{y}\n"))
+                .collect::<String>();
+            prop_assert_eq!(
+                solve_part1_with(&input, scenario).ok(),
+                crate::day18::solve_part1_with(&input, scenario).ok()
+            );
+            prop_assert_eq!(
+                solve_part2_with(&input, scenario).ok(),
+                crate::day18::solve_part2_with(&input, scenario).ok()
+            );
+        }
     }
 }
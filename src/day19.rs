@@ -2,47 +2,28 @@ use crate::Result;
 use anyhow::Context as _;
 use aoc_runner_derive::aoc;
 use std::fmt::Display;
+use towels::TowelIndex;
 
-pub const DAY: u32 = 19;
+pub mod towels;
 
-fn possible_counts<'a>(
-    original_pattern: &str,
-    towels: impl Iterator<Item = &'a str> + Clone,
-) -> usize {
-    pathfinding::directed::count_paths::count_paths(
-        original_pattern,
-        |sub_pattern| {
-            towels
-                .clone()
-                // Create a tuple with the head of the pattern the same
-                // length as the towel and the towel itself.
-                .filter_map(|towel| Some((sub_pattern.get(..towel.len())?, towel)))
-                // Filter to include tuples where the head and towel match.
-                // i.e. the pattern starts with the towel and can be used.
-                .filter(|(pattern_head, towel)| pattern_head == towel)
-                // Create a tuple with the tail of the pattern and the towel.
-                // Safety: Unwrap is safe here because we got the head of the
-                // same length earlier.
-                .map(|(_, towel)| sub_pattern.get(towel.len()..).unwrap())
-        },
-        |p| p.is_empty(),
-    )
-}
+pub const DAY: u32 = 19;
 
 fn solve_part1_impl(input: &Data) -> Result<usize> {
+    let index = TowelIndex::new(input.towels.iter().copied())?;
     Ok(input
         .patterns
         .iter()
-        .filter(|p| possible_counts(p, input.towels.iter().map(|t| *t)) > 0)
+        .filter(|p| index.is_possible(p))
         .count())
 }
 
-fn solve_part2_impl(input: &Data) -> Result<usize> {
-    Ok(input
-        .patterns
-        .iter()
-        .map(|p| possible_counts(p, input.towels.iter().map(|t| *t)))
-        .sum())
+fn solve_part2_impl(input: &Data) -> Result<u128> {
+    let index = TowelIndex::new(input.towels.iter().copied())?;
+    input.patterns.iter().try_fold(0u128, |total, p| {
+        total
+            .checked_add(index.count(p)?)
+            .ok_or_else(|| anyhow::anyhow!("total arrangements overflowed"))
+    })
 }
 
 /// Solution to part 1
@@ -54,7 +35,7 @@ fn solve_part1(input: &str) -> Result<usize> {
 
 /// Solution to part 2
 #[aoc(day19, part2)]
-fn solve_part2(input: &str) -> Result<usize> {
+fn solve_part2(input: &str) -> Result<u128> {
     let input = Data::parse(input).context("input parsing")?;
     solve_part2_impl(&input)
 }
@@ -72,12 +53,31 @@ impl<'a> Data<'a> {
             .ok_or_else(|| anyhow::anyhow!("Invalid input split"))?;
 
         let towels = towels.split(',').map(|s| s.trim()).collect();
-        let patterns = patterns.split('\n').map(|s| s.trim()).collect();
+        let patterns = patterns
+            .lines()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect();
 
         Ok(Data { towels, patterns })
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -0,0 +1,394 @@
+//! Count, list and sample the ways a design can be made from towels.
+//!
+//! A [`TowelIndex`] is a trie over stripe colours built once from the towel
+//! list.  Walking it from a position in a design finds every towel that fits
+//! there in one pass, however many towels there are, so counting is a forward
+//! DP over design positions costing O(design length × longest towel).
+
+use crate::Result;
+
+/// Where a trie node has no child for a colour.  The root is never a child.
+const NONE: u32 = 0;
+
+#[derive(Debug, Clone)]
+pub struct TowelIndex {
+    towels: Vec<String>,
+    /// Dense number of each stripe colour plus one, or zero if no towel has it.
+    colour: [u8; 256],
+    colours: usize,
+    /// Child of each node for each colour, `colours` entries per node.
+    children: Vec<u32>,
+    /// The towel ending at each node, if any.
+    towel_at: Vec<Option<u32>>,
+}
+
+impl TowelIndex {
+    /// Build the index.  Repeated towels are only counted once.
+    pub fn new<'a>(towels: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let mut towels = towels.into_iter().map(str::to_string).collect::<Vec<_>>();
+        anyhow::ensure!(
+            towels.iter().all(|t| !t.is_empty()),
+            "towels need at least one stripe"
+        );
+        towels.sort();
+        towels.dedup();
+
+        let mut colour = [0u8; 256];
+        let mut colours = 0;
+        for b in towels.iter().flat_map(|t| t.bytes()) {
+            if colour[b as usize] == 0 {
+                colours += 1;
+                colour[b as usize] = colours as u8;
+            }
+        }
+
+        let mut index = TowelIndex {
+            towels: vec![],
+            colour,
+            colours,
+            children: vec![NONE; colours],
+            towel_at: vec![None],
+        };
+        for (id, towel) in towels.iter().enumerate() {
+            let mut node = 0;
+            for b in towel.bytes() {
+                let slot = node * colours + index.colour[b as usize] as usize - 1;
+                if index.children[slot] == NONE {
+                    index.children[slot] = index.towel_at.len() as u32;
+                    index.towel_at.push(None);
+                    index.children.extend(std::iter::repeat_n(NONE, colours));
+                }
+                node = index.children[slot] as usize;
+            }
+            index.towel_at[node] = Some(id as u32);
+        }
+        index.towels = towels;
+        Ok(index)
+    }
+
+    pub fn towels(&self) -> &[String] {
+        &self.towels
+    }
+
+    /// Every towel that fits the design at `start`, shortest first, as the
+    /// towel's index and length.
+    fn fits_at<'a>(
+        &'a self,
+        design: &'a [u8],
+        start: usize,
+    ) -> impl Iterator<Item = (u32, usize)> + 'a {
+        let mut node = 0;
+        design[start..]
+            .iter()
+            .enumerate()
+            .map_while(move |(i, b)| {
+                let colour = self.colour[*b as usize] as usize;
+                if colour == 0 {
+                    return None;
+                }
+                node = self.children[node * self.colours + colour - 1] as usize;
+                (node as u32 != NONE).then_some((self.towel_at[node], i + 1))
+            })
+            .filter_map(|(id, len)| Some((id?, len)))
+    }
+
+    /// Can the design be made at all?
+    pub fn is_possible(&self, design: &str) -> bool {
+        let design = design.as_bytes();
+        let mut reachable = vec![false; design.len() + 1];
+        reachable[0] = true;
+        for start in 0..design.len() {
+            if reachable[start] {
+                for (_, len) in self.fits_at(design, start) {
+                    reachable[start + len] = true;
+                }
+            }
+        }
+        reachable[design.len()]
+    }
+
+    /// Ways of making the design, failing if there are more than a u128 holds.
+    pub fn count(&self, design: &str) -> Result<u128> {
+        let design = design.as_bytes();
+        let mut ways = vec![0u128; design.len() + 1];
+        ways[0] = 1;
+        for start in 0..design.len() {
+            if ways[start] == 0 {
+                continue;
+            }
+            for (_, len) in self.fits_at(design, start) {
+                ways[start + len] = ways[start + len]
+                    .checked_add(ways[start])
+                    .ok_or_else(|| anyhow::anyhow!("too many arrangements to count"))?;
+            }
+        }
+        Ok(ways[design.len()])
+    }
+
+    /// Ways of finishing the design from each position, back to front.
+    fn ways_to_finish(&self, design: &[u8]) -> Result<Vec<u128>> {
+        let mut ways = vec![0u128; design.len() + 1];
+        ways[design.len()] = 1;
+        for start in (0..design.len()).rev() {
+            for (_, len) in self.fits_at(design, start) {
+                ways[start] = ways[start]
+                    .checked_add(ways[start + len])
+                    .ok_or_else(|| anyhow::anyhow!("too many arrangements to count"))?;
+            }
+        }
+        Ok(ways)
+    }
+
+    /// Every arrangement of the design, in order of towel lengths with the
+    /// shortest first.  Take the first N with [`Iterator::take`].
+    pub fn arrangements<'a>(&'a self, design: &'a str) -> Arrangements<'a> {
+        let bytes = design.as_bytes();
+        // Never step into a position the rest of the design can't be made from.
+        let mut finishable = vec![false; bytes.len() + 1];
+        finishable[bytes.len()] = true;
+        for start in (0..bytes.len()).rev() {
+            finishable[start] = self
+                .fits_at(bytes, start)
+                .any(|(_, len)| finishable[start + len]);
+        }
+        let stack = if finishable[0] && !bytes.is_empty() {
+            vec![(0, self.choices(bytes, 0, &finishable))]
+        } else {
+            vec![]
+        };
+        Arrangements {
+            index: self,
+            design: bytes,
+            finishable,
+            stack,
+            current: vec![],
+            empty: bytes.is_empty(),
+        }
+    }
+
+    /// The towels to try at `start`, in reverse so they pop shortest first.
+    fn choices(&self, design: &[u8], start: usize, finishable: &[bool]) -> Vec<(u32, usize)> {
+        let mut choices = self
+            .fits_at(design, start)
+            .filter(|(_, len)| finishable[start + len])
+            .collect::<Vec<_>>();
+        choices.reverse();
+        choices
+    }
+
+    /// An arrangement picked uniformly from all of them, or None if the design
+    /// can't be made.  `random` should return uniformly distributed u64s.
+    pub fn random_arrangement(
+        &self,
+        design: &str,
+        mut random: impl FnMut() -> u64,
+    ) -> Result<Option<Vec<&str>>> {
+        let bytes = design.as_bytes();
+        let ways = self.ways_to_finish(bytes)?;
+        if ways[0] == 0 {
+            return Ok(None);
+        }
+
+        // Pick arrangement number `n` and walk to it: each towel at a position
+        // accounts for as many arrangements as there are ways to finish after it.
+        let mut n = below(ways[0], &mut random);
+        let mut arrangement = vec![];
+        let mut start = 0;
+        while start < bytes.len() {
+            let (id, len) = self
+                .fits_at(bytes, start)
+                .find(|(_, len)| {
+                    let after = ways[start + len];
+                    if n < after {
+                        true
+                    } else {
+                        n -= after;
+                        false
+                    }
+                })
+                .expect("counts add up to the ways from here");
+            arrangement.push(self.towels[id as usize].as_str());
+            start += len;
+        }
+        Ok(Some(arrangement))
+    }
+}
+
+/// A uniform value in `0..n` from a source of random u64s, by rejection.
+fn below(n: u128, random: &mut impl FnMut() -> u64) -> u128 {
+    let zone = u128::MAX - u128::MAX % n;
+    loop {
+        let value = (random() as u128) << 64 | random() as u128;
+        if value < zone {
+            return value % n;
+        }
+    }
+}
+
+/// Iterator over arrangements of a design, from [`TowelIndex::arrangements`].
+pub struct Arrangements<'a> {
+    index: &'a TowelIndex,
+    design: &'a [u8],
+    finishable: Vec<bool>,
+    /// Towels left to try at each position of the arrangement being built.
+    stack: Vec<(usize, Vec<(u32, usize)>)>,
+    current: Vec<u32>,
+    /// An empty design has one arrangement, using no towels.
+    empty: bool,
+}
+
+impl<'a> Iterator for Arrangements<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if std::mem::take(&mut self.empty) {
+            return Some(vec![]);
+        }
+        loop {
+            let (start, choices) = self.stack.last_mut()?;
+            let start = *start;
+            let Some((id, len)) = choices.pop() else {
+                self.stack.pop();
+                continue;
+            };
+            // Replace the towel tried last time at this depth.
+            self.current.truncate(self.stack.len() - 1);
+            self.current.push(id);
+            let end = start + len;
+            if end == self.design.len() {
+                return Some(
+                    self.current
+                        .iter()
+                        .map(|id| self.index.towels[*id as usize].as_str())
+                        .collect(),
+                );
+            }
+            let choices = self.index.choices(self.design, end, &self.finishable);
+            self.stack.push((end, choices));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestRng;
+    use std::collections::HashMap;
+
+    const TOWELS: [&str; 8] = ["r", "wr", "b", "g", "bwu", "rb", "gb", "br"];
+
+    fn index() -> TowelIndex {
+        TowelIndex::new(TOWELS).unwrap()
+    }
+
+    /// Count by trying every towel at every position.
+    fn naive_count(towels: &[&str], design: &str) -> u128 {
+        if design.is_empty() {
+            return 1;
+        }
+        towels
+            .iter()
+            .filter_map(|t| design.strip_prefix(t))
+            .map(|rest| naive_count(towels, rest))
+            .sum()
+    }
+
+    #[test]
+    fn counts_example_designs() {
+        let index = index();
+        let expected = [
+            ("brwrr", 2),
+            ("bggr", 1),
+            ("gbbr", 4),
+            ("rrbgbr", 6),
+            ("ubwu", 0),
+            ("bwurrg", 1),
+            ("brgr", 2),
+            ("bbrgwb", 0),
+        ];
+        for (design, ways) in expected {
+            assert_eq!(index.count(design).unwrap(), ways, "{design}");
+            assert_eq!(index.is_possible(design), ways > 0, "{design}");
+        }
+        // A colour no towel has.
+        assert_eq!(index.count("rxr").unwrap(), 0);
+        assert_eq!(index.count("").unwrap(), 1);
+    }
+
+    #[test]
+    fn matches_naive_count() {
+        let mut rng = TestRng(19);
+        for _ in 0..200 {
+            let stripe = |rng: &mut TestRng| (b'a' + rng.below(3) as u8) as char;
+            let towels = (0..1 + rng.below(8))
+                .map(|_| {
+                    (0..1 + rng.below(4))
+                        .map(|_| stripe(&mut rng))
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>();
+            let towels = towels.iter().map(String::as_str).collect::<Vec<_>>();
+            let mut unique = towels.clone();
+            unique.sort();
+            unique.dedup();
+            let design = (0..rng.below(12))
+                .map(|_| stripe(&mut rng))
+                .collect::<String>();
+
+            let index = TowelIndex::new(towels.iter().copied()).unwrap();
+            let count = index.count(&design).unwrap();
+            assert_eq!(count, naive_count(&unique, &design), "{towels:?} {design}");
+            let all = index.arrangements(&design).collect::<Vec<_>>();
+            assert_eq!(all.len() as u128, count);
+            for arrangement in all {
+                assert_eq!(arrangement.concat(), design);
+            }
+        }
+    }
+
+    #[test]
+    fn lists_arrangements_in_order() {
+        let index = index();
+        let all = index.arrangements("gbbr").collect::<Vec<_>>();
+        assert_eq!(
+            all,
+            vec![
+                vec!["g", "b", "b", "r"],
+                vec!["g", "b", "br"],
+                vec!["gb", "b", "r"],
+                vec!["gb", "br"],
+            ]
+        );
+        assert_eq!(index.arrangements("gbbr").take(1).count(), 1);
+        assert_eq!(index.arrangements("ubwu").count(), 0);
+    }
+
+    #[test]
+    fn random_arrangements_are_uniform() {
+        let index = index();
+        let mut rng = TestRng(1919);
+        let mut seen = HashMap::new();
+        for _ in 0..6000 {
+            let arrangement = index
+                .random_arrangement("rrbgbr", || rng.next())
+                .unwrap()
+                .unwrap();
+            assert_eq!(arrangement.concat(), "rrbgbr");
+            *seen.entry(arrangement).or_insert(0) += 1;
+        }
+        // Six arrangements, each expected a thousand times.
+        assert_eq!(seen.len(), 6);
+        assert!(seen.values().all(|n| (850..1150).contains(n)), "{seen:?}");
+        assert_eq!(index.random_arrangement("ubwu", || 0).unwrap(), None);
+    }
+
+    #[test]
+    fn long_designs() {
+        let index = TowelIndex::new(["a", "aa"]).unwrap();
+        // Fibonacci numbers, up to the largest that fits.
+        assert_eq!(index.count(&"a".repeat(10)).unwrap(), 89);
+        assert!(index.count(&"a".repeat(184)).is_ok());
+        assert!(index.count(&"a".repeat(300)).is_err());
+        assert!(index.is_possible(&"a".repeat(300)));
+    }
+}
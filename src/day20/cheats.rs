@@ -0,0 +1,280 @@
+//! Count cheats from distance fields instead of searching the maze per cheat.
+//!
+//! A cheat from track cell `a` to track cell `b` takes
+//! `from_start[a] + cost(a, b) + to_end[b]` picoseconds, so two breadth first
+//! searches are enough to price every cheat.  Any cheat radius and metric then
+//! costs O(track cells × radius²).
+
+use super::{Cell, MapRef};
+use crate::{add_xy, find_xy, Position, Result};
+use std::collections::{BTreeMap, VecDeque};
+
+/// How far a cheat moving by `(dx, dy)` travels.
+#[derive(Debug, Clone, Copy)]
+pub enum Metric {
+    /// Moving only up, down, left and right.
+    Manhattan,
+    /// Diagonal steps allowed.
+    Chebyshev,
+    Custom(fn(isize, isize) -> usize),
+}
+
+impl Metric {
+    pub fn cost(&self, dx: isize, dy: isize) -> usize {
+        match self {
+            Metric::Manhattan => dx.unsigned_abs() + dy.unsigned_abs(),
+            Metric::Chebyshev => dx.unsigned_abs().max(dy.unsigned_abs()),
+            Metric::Custom(f) => f(dx, dy),
+        }
+    }
+}
+
+/// A cheat and how much time it saves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cheat {
+    pub start: Position,
+    pub end: Position,
+    pub saving: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheatAnalyzer {
+    width: usize,
+    height: usize,
+    /// Steps from the start to each cell, None for walls and unreachable cells.
+    from_start: Vec<Option<usize>>,
+    /// Steps from each cell to the end.
+    to_end: Vec<Option<usize>>,
+    /// Length of the race without cheating.
+    best: usize,
+}
+
+impl CheatAnalyzer {
+    pub fn new(map: MapRef) -> Result<Self> {
+        let width = map.first().map_or(0, |row| row.len());
+        if let Some(y) = map.iter().position(|row| row.len() != width) {
+            anyhow::bail!("row {y} is {} wide, expected {width}", map[y].len());
+        }
+        let (start, _) =
+            find_xy(map, &Cell::Start).ok_or_else(|| anyhow::anyhow!("no start cell found"))?;
+        let (end, _) =
+            find_xy(map, &Cell::End).ok_or_else(|| anyhow::anyhow!("no end cell found"))?;
+        let from_start = distance_field(map, start);
+        let to_end = distance_field(map, end);
+        let best =
+            from_start[end.1 * width + end.0].ok_or_else(|| anyhow::anyhow!("no path found"))?;
+        Ok(CheatAnalyzer {
+            width,
+            height: map.len(),
+            from_start,
+            to_end,
+            best,
+        })
+    }
+
+    /// Length of the race without cheating.
+    pub fn best(&self) -> usize {
+        self.best
+    }
+
+    /// Call `f` with every cheat of cost at most `radius` that saves at least
+    /// `min_saving`.  Only cheats that move at most `radius` along each axis
+    /// are considered, whatever the metric.  The offsets never reach past the
+    /// map, since a cheat that far away can't land, so a huge `radius` is fine.
+    pub fn for_each_cheat(
+        &self,
+        radius: usize,
+        metric: Metric,
+        min_saving: usize,
+        mut f: impl FnMut(Cheat),
+    ) {
+        let r = radius.min(self.width.max(self.height)) as isize;
+        let offsets = (-r..=r)
+            .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+            .map(|(dx, dy)| (dx, dy, metric.cost(dx, dy)))
+            .filter(|(_, _, cost)| *cost <= radius)
+            .collect::<Vec<_>>();
+
+        for (i, from_start) in self.from_start.iter().enumerate() {
+            let Some(from_start) = from_start else {
+                continue;
+            };
+            let start = (i % self.width, i / self.width);
+            for (dx, dy, cost) in offsets.iter() {
+                let Some(end) = add_xy(&start, &(*dx, *dy)) else {
+                    continue;
+                };
+                if end.0 >= self.width || end.1 >= self.height {
+                    continue;
+                }
+                let Some(to_end) = self.to_end[end.1 * self.width + end.0] else {
+                    continue;
+                };
+                let time = from_start + cost + to_end;
+                if let Some(saving) = self.best.checked_sub(time) {
+                    if saving >= min_saving && saving > 0 {
+                        f(Cheat { start, end, saving });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number of cheats saving at least `min_saving`.
+    pub fn count(&self, radius: usize, metric: Metric, min_saving: usize) -> usize {
+        let mut count = 0;
+        self.for_each_cheat(radius, metric, min_saving, |_| count += 1);
+        count
+    }
+
+    /// How many cheats save each amount of time.
+    pub fn histogram(
+        &self,
+        radius: usize,
+        metric: Metric,
+        min_saving: usize,
+    ) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        self.for_each_cheat(radius, metric, min_saving, |cheat| {
+            *histogram.entry(cheat.saving).or_insert(0) += 1
+        });
+        histogram
+    }
+
+    /// Every cheat saving at least `min_saving`, sorted by start then end.
+    pub fn cheats(&self, radius: usize, metric: Metric, min_saving: usize) -> Vec<Cheat> {
+        let mut cheats = vec![];
+        self.for_each_cheat(radius, metric, min_saving, |cheat| cheats.push(cheat));
+        cheats.sort();
+        cheats
+    }
+}
+
+/// Breadth first search over the track from `from`, as a row major field.
+/// Every row must be as wide as the first.
+fn distance_field(map: MapRef, from: Position) -> Vec<Option<usize>> {
+    const DIRECTIONS: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    let width = map.first().map_or(0, |row| row.len());
+    let mut field = vec![None; width * map.len()];
+    field[from.1 * width + from.0] = Some(0);
+    let mut queue = VecDeque::from([(from, 0)]);
+    while let Some((xy, distance)) = queue.pop_front() {
+        for next in DIRECTIONS.iter().filter_map(|dir| add_xy(&xy, dir)) {
+            let open = map
+                .get(next.1)
+                .and_then(|row| row.get(next.0))
+                .is_some_and(|cell| *cell != Cell::Wall);
+            if open && next.0 < width && field[next.1 * width + next.0].is_none() {
+                field[next.1 * width + next.0] = Some(distance + 1);
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_grid, test_data};
+
+    fn analyzer() -> CheatAnalyzer {
+        let map: Vec<Vec<Cell>> = parse_grid(&test_data(super::super::DAY).unwrap()).unwrap();
+        CheatAnalyzer::new(&map).unwrap()
+    }
+
+    #[test]
+    fn example_histograms() {
+        let analyzer = analyzer();
+        assert_eq!(analyzer.best(), 84);
+        assert_eq!(
+            analyzer.histogram(2, Metric::Manhattan, 1),
+            BTreeMap::from([
+                (2, 14),
+                (4, 14),
+                (6, 2),
+                (8, 4),
+                (10, 2),
+                (12, 3),
+                (20, 1),
+                (36, 1),
+                (38, 1),
+                (40, 1),
+                (64, 1)
+            ])
+        );
+        assert_eq!(
+            analyzer.histogram(20, Metric::Manhattan, 50),
+            BTreeMap::from([
+                (50, 32),
+                (52, 31),
+                (54, 29),
+                (56, 39),
+                (58, 25),
+                (60, 23),
+                (62, 20),
+                (64, 19),
+                (66, 12),
+                (68, 14),
+                (70, 12),
+                (72, 22),
+                (74, 4),
+                (76, 3)
+            ])
+        );
+    }
+
+    #[test]
+    fn cheat_pairs() {
+        let analyzer = analyzer();
+        let cheats = analyzer.cheats(2, Metric::Manhattan, 64);
+        assert_eq!(cheats.len(), 1);
+        let cheat = cheats[0];
+        assert_eq!(cheat.saving, 64);
+        assert_eq!(
+            cheat.start.0.abs_diff(cheat.end.0) + cheat.start.1.abs_diff(cheat.end.1),
+            2
+        );
+
+        let all = analyzer.cheats(20, Metric::Manhattan, 50);
+        assert_eq!(all.len(), 285);
+        assert!(all.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn metrics() {
+        let analyzer = analyzer();
+        let manhattan = analyzer.count(6, Metric::Manhattan, 10);
+        let custom = Metric::Custom(|dx, dy| dx.unsigned_abs() + dy.unsigned_abs());
+        assert_eq!(analyzer.count(6, custom, 10), manhattan);
+        // Diagonal steps reach further for the same radius.
+        assert!(analyzer.count(6, Metric::Chebyshev, 10) > manhattan);
+        // A radius of zero can't go anywhere new.
+        assert_eq!(analyzer.count(0, Metric::Chebyshev, 1), 0);
+    }
+
+    #[test]
+    fn huge_radius() {
+        let analyzer = analyzer();
+        let reach = analyzer.width + analyzer.height;
+        for metric in [Metric::Manhattan, Metric::Chebyshev] {
+            assert_eq!(
+                analyzer.count(usize::MAX, metric, 1),
+                analyzer.count(reach, metric, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn ragged_maps() {
+        for input in [
+            "###\n#.#\n#.#S.....E\n###",
+            "#\n#S......E\n#",
+            "####\n#S.#\n#..#\n#.........E",
+        ] {
+            let map: Vec<Vec<Cell>> = parse_grid(input).unwrap();
+            let e = CheatAnalyzer::new(&map).unwrap_err().to_string();
+            assert!(e.contains("wide"), "{input:?}: {e}");
+        }
+    }
+}
@@ -0,0 +1,404 @@
+//! Keypads of any shape and chains of robots pressing them.
+//!
+//! A keypad is parsed from a text picture, one row per line, with spaces (or
+//! `.`) marking gaps.  Every keypad starts with its arm on `A`, and a keypad
+//! that controls another robot must have the `^`, `v`, `<`, `>` and `A` keys.
+//!
+//! Every press on a keypad leaves the keypad controlling it back on `A`, so
+//! typing a code costs the sum of the cost of each consecutive pair of keys.
+//! The chain fills a table of those pair costs one keypad at a time, from the
+//! human's keypad down, which is O(keys² × depth).
+
+use crate::Result;
+use std::{cmp::Reverse, collections::BinaryHeap, fmt::Display};
+
+/// The keys that move a robot arm, and the direction each moves it in.
+pub const MOVES: [(char, (isize, isize)); 4] =
+    [('^', (0, -1)), ('v', (0, 1)), ('<', (-1, 0)), ('>', (1, 0))];
+
+/// A rectangle of keys, possibly with gaps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keypad {
+    /// The key in each cell, row major, None for gaps.
+    cells: Vec<Option<char>>,
+    width: usize,
+    /// Every key and its position, in reading order.
+    keys: Vec<(char, (usize, usize))>,
+}
+
+impl Keypad {
+    /// The door's numeric keypad.
+    pub const NUMERIC: &'static str = "789\n456\n123\n 0A";
+    /// The robots' directional keypad.
+    pub const DIRECTIONAL: &'static str = " ^A\n<v>";
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let rows = s.lines().collect::<Vec<_>>();
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut cells = vec![None; width * rows.len()];
+        let mut keys = vec![];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == ' ' || c == '.' {
+                    continue;
+                }
+                if c.is_whitespace() {
+                    anyhow::bail!("invalid key {c:?} at {:?}", (x, y));
+                }
+                if keys.iter().any(|(k, _)| *k == c) {
+                    anyhow::bail!("key {c:?} appears more than once");
+                }
+                cells[y * width + x] = Some(c);
+                keys.push((c, (x, y)));
+            }
+        }
+        if !keys.iter().any(|(k, _)| *k == 'A') {
+            anyhow::bail!("keypad has no A key to start on");
+        }
+        Ok(Keypad { cells, width, keys })
+    }
+
+    pub fn numeric() -> Self {
+        Self::parse(Self::NUMERIC).expect("numeric keypad is valid")
+    }
+
+    pub fn directional() -> Self {
+        Self::parse(Self::DIRECTIONAL).expect("directional keypad is valid")
+    }
+
+    /// Width and height of the keypad.
+    pub fn size(&self) -> (usize, usize) {
+        (
+            self.width,
+            self.cells.len().checked_div(self.width).unwrap_or(0),
+        )
+    }
+
+    /// Every key and its position, in reading order.
+    pub fn keys(&self) -> impl Iterator<Item = (char, (usize, usize))> + '_ {
+        self.keys.iter().copied()
+    }
+
+    /// Where a key is, if the keypad has it.
+    pub fn position(&self, key: char) -> Option<(usize, usize)> {
+        self.index(key).map(|i| self.keys[i].1)
+    }
+
+    /// The key at a position, None for gaps and positions off the keypad.
+    pub fn key_at(&self, (x, y): (usize, usize)) -> Option<char> {
+        if x >= self.width {
+            return None;
+        }
+        *self.cells.get(y * self.width + x)?
+    }
+
+    /// Whether this keypad can steer a robot arm.
+    pub fn is_directional(&self) -> bool {
+        MOVES
+            .iter()
+            .map(|(key, _)| *key)
+            .chain(['A'])
+            .all(|key| self.index(key).is_some())
+    }
+
+    fn index(&self, key: char) -> Option<usize> {
+        self.keys.iter().position(|(k, _)| *k == key)
+    }
+}
+
+impl Display for Keypad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, row) in self.cells.chunks(self.width.max(1)).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let row = row.iter().map(|c| c.unwrap_or(' ')).collect::<String>();
+            write!(f, "{}", row.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// A keypad typed on by a robot, steered from a chain of keypads that ends
+/// with the one a human presses.
+#[derive(Debug, Clone)]
+pub struct KeypadChain {
+    /// `pads[0]` is typed on, each later pad steers the one before it, and the
+    /// human presses the last.
+    pads: Vec<Keypad>,
+    /// `costs[i][a][b]`: human presses to move the arm over `pads[i]` from key
+    /// `a` to key `b` and press it.  None if `b` can't be reached.
+    costs: Vec<Vec<Vec<Option<u64>>>>,
+    /// `moves[i][a][b]`: what `pads[i + 1]` types for that move and press.
+    moves: Vec<Vec<Vec<String>>>,
+}
+
+impl KeypadChain {
+    /// Build a chain from the keypad typed on to the keypad the human presses.
+    pub fn new(pads: Vec<Keypad>) -> Result<Self> {
+        if pads.is_empty() {
+            anyhow::bail!("a keypad chain needs at least one keypad");
+        }
+        if let Some(i) = pads.iter().skip(1).position(|pad| !pad.is_directional()) {
+            anyhow::bail!("keypad {} steers a robot but lacks ^v<>A", i + 1);
+        }
+
+        let last = pads.len() - 1;
+        let n = pads[last].keys.len();
+        let mut costs = vec![vec![vec![Some(1); n]; n]];
+        let mut moves = vec![];
+        for i in (0..last).rev() {
+            let (cost, path) = layer(&pads[i], &pads[i + 1], &costs[0])
+                .map_err(|e| e.context(format!("keypad {i}")))?;
+            costs.insert(0, cost);
+            moves.insert(0, path);
+        }
+        Ok(KeypadChain { pads, costs, moves })
+    }
+
+    /// `target` steered through `robots` robot operated copies of
+    /// `controller`, then one more copy that the human presses.
+    pub fn uniform(target: Keypad, controller: Keypad, robots: usize) -> Result<Self> {
+        let pads = std::iter::once(target)
+            .chain(std::iter::repeat_n(controller, robots + 1))
+            .collect();
+        Self::new(pads)
+    }
+
+    /// The puzzle's numeric keypad behind `robots` directional keypads.
+    pub fn puzzle(robots: usize) -> Self {
+        Self::uniform(Keypad::numeric(), Keypad::directional(), robots)
+            .expect("puzzle keypads are valid")
+    }
+
+    pub fn pads(&self) -> &[Keypad] {
+        &self.pads
+    }
+
+    /// The fewest presses the human needs to type `code` on the first keypad.
+    pub fn press_count(&self, code: &str) -> Result<u64> {
+        self.pairs(0, code)?
+            .into_iter()
+            .try_fold(0u64, |sum, (a, b)| {
+                let cost = self.costs[0][a][b].ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "can't reach {:?} from {:?}",
+                        self.pads[0].keys[b].0,
+                        self.pads[0].keys[a].0
+                    )
+                })?;
+                sum.checked_add(cost)
+                    .ok_or_else(|| anyhow::anyhow!("press count overflowed"))
+            })
+    }
+
+    /// An optimal sequence of presses on every keypad for typing `code`.
+    /// Entry 0 is `code` itself and the last entry is what the human presses.
+    ///
+    /// The sequences grow exponentially with depth, so this refuses codes
+    /// needing more than `limit` presses.
+    pub fn sequences(&self, code: &str, limit: u64) -> Result<Vec<String>> {
+        let count = self.press_count(code)?;
+        if count > limit {
+            anyhow::bail!("{count} presses is over the limit of {limit}");
+        }
+        let mut levels = vec![code.to_string()];
+        for (i, moves) in self.moves.iter().enumerate() {
+            let below = &levels[i];
+            let above = self
+                .pairs(i, below)?
+                .into_iter()
+                .map(|(a, b)| moves[a][b].as_str())
+                .collect::<String>();
+            levels.push(above);
+        }
+        Ok(levels)
+    }
+
+    /// What the human typing `presses` types on the first keypad.
+    pub fn simulate(&self, presses: &str) -> Result<String> {
+        super::simulator::simulate(&self.pads, presses)
+    }
+
+    /// Index pairs of consecutive keys in `code` on keypad `level`, starting
+    /// from `A`.
+    fn pairs(&self, level: usize, code: &str) -> Result<Vec<(usize, usize)>> {
+        let pad = &self.pads[level];
+        let start = pad.index('A').expect("keypads have an A key");
+        let indices = code
+            .chars()
+            .map(|c| {
+                pad.index(c)
+                    .ok_or_else(|| anyhow::anyhow!("keypad has no {c:?} key"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(std::iter::once(start)
+            .chain(indices.iter().copied())
+            .zip(indices.iter().copied())
+            .collect())
+    }
+
+    /// Human presses to move the arm over keypad `level` from `from` to `to`
+    /// and press it.
+    pub fn pair_cost(&self, level: usize, from: char, to: char) -> Option<u64> {
+        let pad = self.pads.get(level)?;
+        self.costs[level][pad.index(from)?][pad.index(to)?]
+    }
+}
+
+type Layer = (Vec<Vec<Option<u64>>>, Vec<Vec<String>>);
+
+/// Pair costs and moves for `pad`, steered by `controller` whose pair costs
+/// are `above`.
+///
+/// From each key, Dijkstra over (key on `pad`, key last pressed on
+/// `controller`) finds the cheapest way to every other key, including detours
+/// around gaps.
+fn layer(pad: &Keypad, controller: &Keypad, above: &[Vec<Option<u64>>]) -> Result<Layer> {
+    let n = pad.keys.len();
+    let m = controller.keys.len();
+    let ctrl_a = controller.index('A').expect("keypads have an A key");
+    let ctrl_moves = MOVES.map(|(key, dir)| (controller.index(key).expect("directional"), dir));
+
+    let mut costs = vec![vec![None; n]; n];
+    let mut moves = vec![vec![String::new(); n]; n];
+    for from in 0..n {
+        // Best cost and the state it came from, indexed by key * m + ctrl.
+        let mut best: Vec<Option<(u64, usize)>> = vec![None; n * m];
+        let start = from * m + ctrl_a;
+        best[start] = Some((0, start));
+        let mut queue = BinaryHeap::from([Reverse((0u64, start))]);
+        while let Some(Reverse((cost, state))) = queue.pop() {
+            if best[state].is_some_and(|(c, _)| c < cost) {
+                continue;
+            }
+            let (key, ctrl) = (state / m, state % m);
+            for &(press, dir) in &ctrl_moves {
+                let Some(step) = above[ctrl][press] else {
+                    continue;
+                };
+                let Some(next) = crate::add_xy(&pad.keys[key].1, &dir)
+                    .and_then(|xy| pad.key_at(xy))
+                    .and_then(|k| pad.index(k))
+                else {
+                    continue;
+                };
+                let cost = cost
+                    .checked_add(step)
+                    .ok_or_else(|| anyhow::anyhow!("press count overflowed"))?;
+                let next = next * m + press;
+                if best[next].is_none_or(|(c, _)| cost < c) {
+                    best[next] = Some((cost, state));
+                    queue.push(Reverse((cost, next)));
+                }
+            }
+        }
+
+        for to in 0..n {
+            // Finish by pressing A on the controller.
+            let end = (0..m)
+                .filter_map(|ctrl| {
+                    let (cost, _) = best[to * m + ctrl]?;
+                    Some((cost.checked_add(above[ctrl][ctrl_a]?)?, ctrl))
+                })
+                .min();
+            let Some((cost, ctrl)) = end else {
+                continue;
+            };
+            costs[from][to] = Some(cost);
+
+            let mut presses = vec!['A'];
+            let mut state = to * m + ctrl;
+            while state != start {
+                presses.push(controller.keys[state % m].0);
+                state = best[state].expect("visited").1;
+            }
+            moves[from][to] = presses.into_iter().rev().collect();
+        }
+    }
+    Ok((costs, moves))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_render() {
+        let pad = Keypad::numeric();
+        assert_eq!(pad.size(), (3, 4));
+        assert_eq!(pad.position('0'), Some((1, 3)));
+        assert_eq!(pad.key_at((0, 3)), None);
+        assert_eq!(pad.key_at((3, 0)), None);
+        assert_eq!(pad.to_string(), Keypad::NUMERIC);
+        assert!(!pad.is_directional());
+        assert!(Keypad::directional().is_directional());
+
+        assert!(Keypad::parse("12\n3").is_err());
+        assert!(Keypad::parse("A1\n1.").is_err());
+        // Short rows are padded with gaps.
+        let pad = Keypad::parse("A12\n3").unwrap();
+        assert_eq!(pad.key_at((1, 1)), None);
+    }
+
+    #[test]
+    fn example_sequences() {
+        let chain = KeypadChain::puzzle(2);
+        let levels = chain.sequences("029A", 1000).unwrap();
+        assert_eq!(levels.len(), 4);
+        assert_eq!(levels[0], "029A");
+        assert_eq!(levels[1].len(), "<A^A>^^AvvvA".len());
+        assert_eq!(levels[2].len(), "v<<A>>^A<A>AvA<^AA>A<vAAA>^A".len());
+        assert_eq!(levels[3].len(), 68);
+        assert_eq!(chain.press_count("029A").unwrap(), 68);
+        assert_eq!(chain.press_count("379A").unwrap(), 64);
+        assert!(chain.press_count("12B").is_err());
+    }
+
+    #[test]
+    fn sequences_match_press_counts() {
+        for robots in 0..4 {
+            let chain = KeypadChain::puzzle(robots);
+            for code in ["029A", "980A", "179A", "456A", "379A"] {
+                let levels = chain.sequences(code, u64::MAX).unwrap();
+                assert_eq!(
+                    levels.last().unwrap().len() as u64,
+                    chain.press_count(code).unwrap()
+                );
+                for (level, presses) in levels.iter().enumerate().skip(1) {
+                    // Each level moves its arm one step or presses A.
+                    assert!(presses.chars().all(|c| "^v<>A".contains(c)));
+                    assert_eq!(presses.matches('A').count(), levels[level - 1].len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn detours_around_gaps() {
+        // The only way from A to 1 goes around the gap in the middle.
+        let pad = Keypad::parse("A2\n.3\n14").unwrap();
+        let chain = KeypadChain::uniform(pad, Keypad::directional(), 0).unwrap();
+        let levels = chain.sequences("1", 100).unwrap();
+        assert_eq!(levels[1], ">vv<A");
+        assert_eq!(chain.pair_cost(0, 'A', '1'), Some(5));
+
+        // Unreachable keys are an error, not a panic.
+        let pad = Keypad::parse("A.1").unwrap();
+        let chain = KeypadChain::uniform(pad, Keypad::directional(), 1).unwrap();
+        assert!(chain.press_count("1").is_err());
+        assert!(KeypadChain::new(vec![Keypad::directional(), Keypad::numeric()]).is_err());
+    }
+
+    #[test]
+    fn deep_chains() {
+        let chain = KeypadChain::puzzle(25);
+        assert_eq!(chain.pads().len(), 27);
+        assert!(chain.sequences("029A", 1_000_000).is_err());
+        assert_eq!(chain.press_count("029A").unwrap(), 82050061710);
+    }
+}
@@ -0,0 +1,184 @@
+//! Run a human's presses through a chain of keypads to see what gets typed.
+//!
+//! Uses the same layout as [`KeypadChain`](super::keypad::KeypadChain):
+//! `pads[0]` is typed on, each later pad steers the robot arm over the one
+//! before it, and the human presses keys on the last pad directly.
+
+use super::keypad::{Keypad, MOVES};
+use crate::{add_xy, Result};
+
+/// The robot arms over every keypad but the human's, all starting on `A`.
+#[derive(Debug, Clone)]
+pub struct Simulator<'a> {
+    pads: &'a [Keypad],
+    /// `arms[i]` is the position of the arm over `pads[i]`.
+    arms: Vec<(usize, usize)>,
+    typed: String,
+    step: usize,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(pads: &'a [Keypad]) -> Result<Self> {
+        if pads.is_empty() {
+            anyhow::bail!("a keypad chain needs at least one keypad");
+        }
+        if let Some(i) = pads.iter().skip(1).position(|pad| !pad.is_directional()) {
+            anyhow::bail!("keypad {} steers a robot but lacks ^v<>A", i + 1);
+        }
+        let arms = pads[..pads.len() - 1]
+            .iter()
+            .map(|pad| pad.position('A').expect("keypads have an A key"))
+            .collect();
+        Ok(Simulator {
+            pads,
+            arms,
+            typed: String::new(),
+            step: 0,
+        })
+    }
+
+    /// The human presses `key` on the last keypad.  Returns the key typed on
+    /// the first keypad, if this press typed one.
+    ///
+    /// Fails, naming the step, if an arm would move onto a gap or off its
+    /// keypad.  The simulator is left as it was before the failing press.
+    pub fn press(&mut self, key: char) -> Result<Option<char>> {
+        let step = self.step;
+        let top = self.pads.len() - 1;
+        if self.pads[top].position(key).is_none() {
+            anyhow::bail!("step {step}: keypad {top} has no {key:?} key");
+        }
+
+        let mut key = key;
+        for level in (0..top).rev() {
+            if key == 'A' {
+                key = self.pads[level]
+                    .key_at(self.arms[level])
+                    .expect("arms stay on keys");
+                continue;
+            }
+            let (_, dir) = MOVES
+                .iter()
+                .find(|(k, _)| *k == key)
+                .ok_or_else(|| anyhow::anyhow!("step {step}: {key:?} doesn't steer a robot"))?;
+            let arm = self.arms[level];
+            let next = add_xy(&arm, dir).filter(|xy| {
+                let (width, height) = self.pads[level].size();
+                xy.0 < width && xy.1 < height
+            });
+            let Some(next) = next else {
+                anyhow::bail!("step {step}: arm over keypad {level} left the keypad at {arm:?}");
+            };
+            if self.pads[level].key_at(next).is_none() {
+                anyhow::bail!(
+                    "step {step}: arm over keypad {level} pointed at the gap at {next:?}"
+                );
+            }
+            self.arms[level] = next;
+            self.step += 1;
+            return Ok(None);
+        }
+
+        self.typed.push(key);
+        self.step += 1;
+        Ok(Some(key))
+    }
+
+    /// The key under each robot arm, from the first keypad up.
+    pub fn arms(&self) -> impl Iterator<Item = char> + '_ {
+        self.arms
+            .iter()
+            .zip(self.pads)
+            .map(|(&arm, pad)| pad.key_at(arm).expect("arms stay on keys"))
+    }
+
+    /// Everything typed on the first keypad so far.
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+
+    /// How many presses the human has made.
+    pub fn steps(&self) -> usize {
+        self.step
+    }
+}
+
+/// What the human typing `presses` on the last of `pads` types on the first.
+pub fn simulate(pads: &[Keypad], presses: &str) -> Result<String> {
+    let mut simulator = Simulator::new(pads)?;
+    for key in presses.chars() {
+        simulator.press(key)?;
+    }
+    Ok(simulator.typed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day21::keypad::KeypadChain, TestRng};
+
+    #[test]
+    fn example_sequence() {
+        let chain = KeypadChain::puzzle(2);
+        let presses = "<vA<AA>>^AvAA<^A>A<v<A>>^AvA^A<vA>^A<v<A>^A>AAvA^A<v<A>A>^AAAvA<^A>A";
+        assert_eq!(chain.simulate(presses).unwrap(), "029A");
+
+        let mut simulator = Simulator::new(chain.pads()).unwrap();
+        for key in "<vA<AA>>^A".chars() {
+            simulator.press(key).unwrap();
+        }
+        assert_eq!(simulator.arms().collect::<String>(), "0<A");
+        assert_eq!(simulator.steps(), 10);
+        assert_eq!(simulator.typed(), "");
+    }
+
+    #[test]
+    fn invalid_sequences() {
+        let chain = KeypadChain::puzzle(1);
+        let err = chain.simulate("<<A").unwrap_err().to_string();
+        assert!(err.starts_with("step 1:"), "{err}");
+        assert!(err.contains("gap"), "{err}");
+
+        let err = chain.simulate("v^^").unwrap_err().to_string();
+        assert!(err.starts_with("step 2:"), "{err}");
+        assert!(err.contains("left the keypad"), "{err}");
+
+        assert!(chain
+            .simulate("AAx")
+            .unwrap_err()
+            .to_string()
+            .starts_with("step 2:"));
+
+        // A failed press leaves the simulator untouched.
+        let mut simulator = Simulator::new(chain.pads()).unwrap();
+        simulator.press('<').unwrap();
+        assert!(simulator.press('<').is_err());
+        assert_eq!(simulator.steps(), 1);
+        assert_eq!(simulator.arms().collect::<String>(), "A^");
+    }
+
+    #[test]
+    fn optimal_sequences_type_their_code() {
+        let mut rng = TestRng(39);
+        let layouts = [Keypad::NUMERIC, "A2\n.3\n14", "1.2\nA34\n5.6"];
+        for layout in layouts {
+            let pad = Keypad::parse(layout).unwrap();
+            let keys = pad.keys().map(|(k, _)| k).collect::<Vec<_>>();
+            for robots in 0..3 {
+                let chain =
+                    KeypadChain::uniform(pad.clone(), Keypad::directional(), robots).unwrap();
+                for _ in 0..10 {
+                    let code = (0..1 + rng.below(5))
+                        .map(|_| keys[rng.below(keys.len() as u64) as usize])
+                        .collect::<String>();
+                    let levels = chain.sequences(&code, u64::MAX).unwrap();
+                    for (level, presses) in levels.iter().enumerate().skip(1) {
+                        let typed = simulate(&chain.pads()[level - 1..=level], presses).unwrap();
+                        assert_eq!(typed, levels[level - 1], "{layout:?} {code}");
+                    }
+                    assert_eq!(chain.simulate(levels.last().unwrap()).unwrap(), code);
+                }
+            }
+        }
+    }
+}
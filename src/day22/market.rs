@@ -0,0 +1,236 @@
+//! Find the best run of price changes to sell on in one pass over each buyer.
+//!
+//! Each window of `window` price changes is encoded in base 19 as an index
+//! into a table with a slot for every possible window.  Walking a buyer's
+//! prices once records the price at the first time each window appears, and
+//! the buyers' tables are summed with rayon.
+
+use super::part2_sequence;
+use crate::{Header, Result};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+/// Price changes run from -9 to 9.
+const CHANGES: usize = 19;
+
+/// The largest window with a dense table, 19⁵ slots.  Each rayon split holds
+/// a `u64` total and a `u32` buyer per slot, about 30 MB at this window, and
+/// [`MIN_BUYERS_PER_SPLIT`] keeps small inputs from splitting into many.
+pub const MAX_WINDOW: usize = 5;
+
+/// The fewest buyers rayon hands one fold accumulator.
+const MIN_BUYERS_PER_SPLIT: usize = 64;
+
+/// The longest market, fifty times the puzzle's, so a buyer's walk ends in
+/// reasonable time.
+pub const MAX_SEQUENCE_LENGTH: usize = 100_000;
+
+/// How long the market runs and how many changes the monkey watches for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Market {
+    /// New secret numbers, and so price changes, per buyer.
+    pub sequence_length: usize,
+    /// Price changes in the run the monkey sells on.
+    pub window: usize,
+}
+
+/// The run of changes that earns the most bananas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Best {
+    pub changes: Vec<i8>,
+    pub bananas: u64,
+}
+
+impl Market {
+    pub const PUZZLE: Market = Market {
+        sequence_length: 2000,
+        window: 4,
+    };
+
+    /// Split an optional `sequence_length=`/`window=` header off the input.
+    pub fn parse(s: &str, default: Market) -> Result<(Market, &str)> {
+        let (header, rest) = Header::split(s)?;
+        header.ensure_known(&["sequence_length", "window"])?;
+        let market = Market {
+            sequence_length: header
+                .get("sequence_length")?
+                .unwrap_or(default.sequence_length),
+            window: header.get("window")?.unwrap_or(default.window),
+        };
+        market.check_sequence_length()?;
+        Ok((market, rest))
+    }
+
+    /// The run of changes that earns the most bananas from `seeds`, or None
+    /// if no run earns any.  Ties go to the lowest run of changes.
+    pub fn best(&self, seeds: &[usize]) -> Result<Option<Best>> {
+        let totals = self.totals(seeds)?;
+        let best = totals
+            .iter()
+            .enumerate()
+            .filter(|(_, total)| **total > 0)
+            .max_by_key(|(key, total)| (**total, std::cmp::Reverse(*key)));
+        Ok(best.map(|(key, total)| Best {
+            changes: self.decode(key),
+            bananas: *total,
+        }))
+    }
+
+    /// Bananas earned from `seeds` by every run of changes, indexed by the
+    /// encoded run.
+    pub fn totals(&self, seeds: &[usize]) -> Result<Vec<u64>> {
+        self.check_sequence_length()?;
+        if !(1..=MAX_WINDOW).contains(&self.window) {
+            anyhow::bail!("window must be 1 to {MAX_WINDOW}, not {}", self.window);
+        }
+        if seeds.len() >= u32::MAX as usize {
+            anyhow::bail!("at most {} buyers, not {}", u32::MAX - 1, seeds.len());
+        }
+        let slots = CHANGES.pow(self.window as u32);
+        let totals = seeds
+            .par_iter()
+            .enumerate()
+            .with_min_len(MIN_BUYERS_PER_SPLIT)
+            .fold(
+                || (vec![0u64; slots], vec![u32::MAX; slots]),
+                |(mut totals, mut seen), (buyer, &seed)| {
+                    // `seen` holds the last buyer to record each slot, so it
+                    // never needs clearing.
+                    let buyer = buyer as u32;
+                    self.record(seed, |key| {
+                        let first = seen[key] != buyer;
+                        seen[key] = buyer;
+                        first
+                    })
+                    .for_each(|(key, price)| totals[key] += price as u64);
+                    (totals, seen)
+                },
+            )
+            .map(|(totals, _)| totals)
+            .reduce(
+                || vec![0; slots],
+                |mut a, b| {
+                    a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+                    a
+                },
+            );
+        Ok(totals)
+    }
+
+    fn check_sequence_length(&self) -> Result<()> {
+        if self.sequence_length > MAX_SEQUENCE_LENGTH {
+            anyhow::bail!(
+                "sequence_length must be at most {MAX_SEQUENCE_LENGTH}, not {}",
+                self.sequence_length
+            );
+        }
+        Ok(())
+    }
+
+    /// The encoded window ending at each price of a buyer, with the price,
+    /// for the windows `first` accepts.
+    fn record(
+        &self,
+        seed: usize,
+        mut first: impl FnMut(usize) -> bool,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let slots = CHANGES.pow(self.window as u32);
+        let window = self.window;
+        let mut key = 0;
+        part2_sequence(seed)
+            .take(self.sequence_length)
+            .enumerate()
+            .filter_map(move |(i, (price, change))| {
+                key = (key * CHANGES + (change + 9) as usize) % slots;
+                (i + 1 >= window && first(key)).then_some((key, price))
+            })
+    }
+
+    fn decode(&self, mut key: usize) -> Vec<i8> {
+        let mut changes = vec![0; self.window];
+        for change in changes.iter_mut().rev() {
+            *change = (key % CHANGES) as i8 - 9;
+            key /= CHANGES;
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let best = Market::PUZZLE.best(&[1, 2, 3, 2024]).unwrap().unwrap();
+        assert_eq!(
+            best,
+            Best {
+                changes: vec![-2, 1, -1, 3],
+                bananas: 23
+            }
+        );
+    }
+
+    #[test]
+    fn first_ten_prices() {
+        let market = Market {
+            sequence_length: 9,
+            window: 4,
+        };
+        let best = market.best(&[123]).unwrap().unwrap();
+        assert_eq!(best.changes, vec![-1, -1, 0, 2]);
+        assert_eq!(best.bananas, 6);
+
+        let market = Market {
+            sequence_length: 3,
+            window: 4,
+        };
+        assert_eq!(market.best(&[123]).unwrap(), None);
+    }
+
+    #[test]
+    fn only_first_sale_counts() {
+        // Brute force the first sale of each window for a few buyers.
+        let seeds = [5, 77, 1234];
+        for window in 1..=3 {
+            let market = Market {
+                sequence_length: 300,
+                window,
+            };
+            let totals = market.totals(&seeds).unwrap();
+            let mut expected = vec![0u64; totals.len()];
+            for seed in seeds {
+                let prices = part2_sequence(seed).take(300).collect::<Vec<_>>();
+                let mut seen = std::collections::HashSet::new();
+                for run in prices.windows(window) {
+                    let key = run
+                        .iter()
+                        .fold(0, |key, (_, change)| key * 19 + (change + 9) as usize);
+                    if seen.insert(key) {
+                        expected[key] += run[window - 1].0 as u64;
+                    }
+                }
+            }
+            assert_eq!(totals, expected, "window {window}");
+        }
+        assert!(Market {
+            sequence_length: 10,
+            window: 6
+        }
+        .best(&seeds)
+        .is_err());
+    }
+
+    #[test]
+    fn sequence_length_limit() {
+        let huge = "sequence_length=18446744073709551615\n\n1\n";
+        assert!(Market::parse(huge, Market::PUZZLE).is_err());
+        let longest = format!("sequence_length={MAX_SEQUENCE_LENGTH}\n\n1\n");
+        assert!(Market::parse(&longest, Market::PUZZLE).is_ok());
+        let market = Market {
+            sequence_length: MAX_SEQUENCE_LENGTH + 1,
+            window: 4,
+        };
+        assert!(market.best(&[1]).is_err());
+    }
+}
@@ -1,102 +1,26 @@
 use crate::Result;
 use anyhow::Context as _;
 use aoc_runner_derive::aoc;
-use itertools::Itertools as _;
-use std::{
-    collections::{BTreeSet, HashMap, HashSet},
-    fmt::Display,
-};
+use network::Network;
+use std::fmt::Display;
+
+pub mod export;
+pub mod network;
 
 pub const DAY: u32 = 23;
 
 fn solve_part1_impl(input: &Data) -> Result<usize> {
-    let mut mappings = HashMap::<&str, HashSet<&str>>::new();
-    for (a, b) in &input.connections {
-        mappings.entry(a).or_default().insert(b);
-        mappings.entry(b).or_default().insert(a);
-    }
-
-    let nodes = mappings.keys().copied().collect::<Vec<_>>();
-
-    let permutations = nodes.iter().combinations(3);
-    let all_connected = permutations.filter(|p| {
-        mappings.get(p[0]).unwrap().contains(p[1])
-            && mappings.get(p[1]).unwrap().contains(p[2])
-            && mappings.get(p[2]).unwrap().contains(p[0])
-    });
-    let with_t = all_connected.filter(|p| p.iter().any(|x| x.starts_with('t')));
-
-    Ok(with_t.count())
+    let network = Network::new(input.connections.iter().copied());
+    Ok(network.triangles_where(|name| name.starts_with('t')).len())
 }
 
 fn solve_part2_impl(input: &Data) -> Result<String> {
-    let mut mappings = HashMap::<&str, HashSet<&str>>::new();
-    for (a, b) in &input.connections {
-        mappings.entry(a).or_default().insert(b);
-        mappings.entry(b).or_default().insert(a);
+    let network = Network::new(input.connections.iter().copied());
+    let clique = network.max_clique();
+    if clique.is_empty() {
+        anyhow::bail!("No groups found");
     }
-
-    let nodes = mappings.keys().copied().collect::<Vec<_>>();
-
-    let longest_groups = nodes.into_iter().map(|n| {
-        let mut longest = HashSet::new();
-        longest_group(
-            n,
-            &mappings,
-            &mut BTreeSet::new(),
-            &mut longest,
-            &mut HashSet::new(),
-        );
-        longest
-    });
-    let longest_group = longest_groups
-        .max_by_key(|g| g.len())
-        .ok_or_else(|| anyhow::anyhow!("No groups found"))?;
-
-    let password = longest_group.iter().sorted().join(",");
-
-    Ok(password)
-}
-
-fn longest_group<'a>(
-    node: &'a str,
-    mappings: &'a HashMap<&'a str, HashSet<&'a str>>,
-    current_group: &mut BTreeSet<&'a str>,
-    longest: &mut HashSet<&'a str>,
-    seen: &mut HashSet<Vec<&'a str>>,
-) -> bool {
-    let key = current_group.iter().copied().collect::<Vec<_>>();
-    if seen.contains(&key) {
-        return false;
-    }
-
-    if current_group.contains(node) {
-        return false;
-    }
-    // If all nodes are not connected to this node
-    let my_connections = mappings.get(node).unwrap();
-    if !current_group.iter().all(|n| my_connections.contains(n)) {
-        return false;
-    }
-
-    current_group.insert(node);
-    let children = mappings.get(node).unwrap();
-    let mut added = false;
-    for child in children {
-        if longest_group(child, mappings, current_group, longest, seen) {
-            added = true;
-        }
-    }
-    if added == false {
-        // See if we're now the longest
-        if current_group.len() > longest.len() {
-            longest.clear();
-            longest.extend(current_group.iter().copied());
-        }
-    }
-    current_group.remove(node);
-    seen.insert(key);
-    added
+    Ok(network.names_of(&clique).join(","))
 }
 
 /// Solution to part 1
@@ -113,6 +37,12 @@ fn solve_part2(input: &str) -> Result<String> {
     solve_part2_impl(&input)
 }
 
+/// Parse a connection list into a network.
+pub fn parse_network(input: &str) -> Result<Network<'_>> {
+    let input = Data::parse(input).context("input parsing")?;
+    Ok(Network::new(input.connections))
+}
+
 /// Problem input
 #[derive(Debug)]
 struct Data<'a> {
@@ -134,6 +64,21 @@ impl<'a> Data<'a> {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -163,4 +108,19 @@ mod tests {
             "co,de,ka,ta"
         );
     }
+
+    #[test]
+    fn export_example() {
+        let input = test_data(super::DAY).unwrap();
+        let network = parse_network(&input).unwrap();
+        let mut dot = vec![];
+        export::GraphExporter::new(&network)
+            .highlight(&[network.max_clique()])
+            .write_dot(&mut dot)
+            .unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert_eq!(dot.matches(" -- ").count(), network.edge_count());
+        assert_eq!(dot.matches("penwidth").count(), 6);
+        assert_eq!(network.summary().components, 1);
+    }
 }
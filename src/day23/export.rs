@@ -0,0 +1,234 @@
+//! Write a network out for graph tools, as Graphviz DOT or GraphML.
+//!
+//! Chosen cliques are highlighted: their hosts and the connections inside
+//! them are coloured, one colour per clique.  A host in several highlighted
+//! cliques takes the colour of the first.
+
+use super::network::{Network, NodeId};
+use crate::Result;
+use std::io::Write;
+
+/// Colours handed out to highlighted cliques in turn.
+const PALETTE: [&str; 8] = [
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#a65628", "#f781bf", "#999999",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT.
+    Dot,
+    /// GraphML, with a `clique` attribute on highlighted hosts and connections.
+    GraphMl,
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            _ => anyhow::bail!("unknown graph format {s:?}, expected dot or graphml"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphExporter<'n, 'a> {
+    network: &'n Network<'a>,
+    /// The first highlighted clique each host is in.
+    clique_of: Vec<Option<usize>>,
+}
+
+impl<'n, 'a> GraphExporter<'n, 'a> {
+    pub fn new(network: &'n Network<'a>) -> Self {
+        GraphExporter {
+            network,
+            clique_of: vec![None; network.len()],
+        }
+    }
+
+    /// Highlight these cliques, each in its own colour.  Ids not in the
+    /// network are ignored.
+    pub fn highlight(mut self, cliques: &[Vec<NodeId>]) -> Self {
+        for (i, clique) in cliques.iter().enumerate() {
+            for &id in clique {
+                if let Some(clique) = self.clique_of.get_mut(id) {
+                    clique.get_or_insert(i);
+                }
+            }
+        }
+        self
+    }
+
+    /// The highlighted clique a connection lies in, if both ends share one.
+    fn edge_clique(&self, a: NodeId, b: NodeId) -> Option<usize> {
+        self.clique_of[a].filter(|c| self.clique_of[b] == Some(*c))
+    }
+
+    /// Every connection once, lower host first, in sorted order.
+    fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        (0..self.network.len()).flat_map(move |a| {
+            self.network
+                .neighbours(a)
+                .iter()
+                .filter(move |&&b| a < b)
+                .map(move |&b| (a, b))
+        })
+    }
+
+    pub fn write_dot(&self, mut w: impl Write) -> Result<()> {
+        writeln!(w, "graph network {{")?;
+        for (id, name) in self.network.names().iter().enumerate() {
+            write!(w, "  \"{}\"", dot_escape(name))?;
+            if let Some(c) = self.clique_of[id] {
+                let colour = PALETTE[c % PALETTE.len()];
+                write!(w, " [style=filled, fillcolor=\"{colour}\", clique={c}]")?;
+            }
+            writeln!(w, ";")?;
+        }
+        for (a, b) in self.edges() {
+            let (a_name, b_name) = (self.network.name(a), self.network.name(b));
+            write!(
+                w,
+                "  \"{}\" -- \"{}\"",
+                dot_escape(a_name),
+                dot_escape(b_name)
+            )?;
+            if let Some(c) = self.edge_clique(a, b) {
+                let colour = PALETTE[c % PALETTE.len()];
+                write!(w, " [color=\"{colour}\", penwidth=3]")?;
+            }
+            writeln!(w, ";")?;
+        }
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    pub fn write_graphml(&self, mut w: impl Write) -> Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="clique" for="all" attr.name="clique" attr.type="int"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="colour" for="all" attr.name="colour" attr.type="string"/>"#
+        )?;
+        writeln!(w, r#"  <graph id="network" edgedefault="undirected">"#)?;
+        for (id, name) in self.network.names().iter().enumerate() {
+            let name = xml_escape(name);
+            match self.clique_of[id] {
+                Some(c) => {
+                    writeln!(w, r#"    <node id="{name}">"#)?;
+                    write_clique_data(&mut w, c)?;
+                    writeln!(w, "    </node>")?;
+                }
+                None => writeln!(w, r#"    <node id="{name}"/>"#)?,
+            }
+        }
+        for (a, b) in self.edges() {
+            let a_name = xml_escape(self.network.name(a));
+            let b_name = xml_escape(self.network.name(b));
+            match self.edge_clique(a, b) {
+                Some(c) => {
+                    writeln!(w, r#"    <edge source="{a_name}" target="{b_name}">"#)?;
+                    write_clique_data(&mut w, c)?;
+                    writeln!(w, "    </edge>")?;
+                }
+                None => writeln!(w, r#"    <edge source="{a_name}" target="{b_name}"/>"#)?,
+            }
+        }
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")?;
+        Ok(())
+    }
+
+    pub fn write(&self, format: GraphFormat, w: impl Write) -> Result<()> {
+        match format {
+            GraphFormat::Dot => self.write_dot(w),
+            GraphFormat::GraphMl => self.write_graphml(w),
+        }
+    }
+}
+
+fn write_clique_data(w: &mut impl Write, clique: usize) -> Result<()> {
+    let colour = PALETTE[clique % PALETTE.len()];
+    writeln!(w, r#"      <data key="clique">{clique}</data>"#)?;
+    writeln!(w, r#"      <data key="colour">{colour}</data>"#)?;
+    Ok(())
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(network: &Network, cliques: &[Vec<NodeId>], format: GraphFormat) -> String {
+        let mut out = vec![];
+        GraphExporter::new(network)
+            .highlight(cliques)
+            .write(format, &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn dot() {
+        let network = Network::new([("a", "b"), ("b", "c"), ("c", "a"), ("c", "d\"q")]);
+        let dot = export(&network, &[network.max_clique()], GraphFormat::Dot);
+        assert_eq!(
+            dot,
+            r##"graph network {
+  "a" [style=filled, fillcolor="#e41a1c", clique=0];
+  "b" [style=filled, fillcolor="#e41a1c", clique=0];
+  "c" [style=filled, fillcolor="#e41a1c", clique=0];
+  "d\"q";
+  "a" -- "b" [color="#e41a1c", penwidth=3];
+  "a" -- "c" [color="#e41a1c", penwidth=3];
+  "b" -- "c" [color="#e41a1c", penwidth=3];
+  "c" -- "d\"q";
+}
+"##
+        );
+    }
+
+    #[test]
+    fn graphml() {
+        let network = Network::new([("a", "b"), ("b", "c"), ("c", "<d>")]);
+        let cliques = vec![vec![1, 2], vec![2, 3]];
+        let xml = export(&network, &cliques, GraphFormat::GraphMl);
+        assert!(xml.contains(r#"<node id="&lt;d&gt;"/>"#), "{xml}");
+        // b is in both cliques and keeps the first.
+        assert_eq!(xml.matches(r#"<data key="clique">0</data>"#).count(), 3);
+        assert_eq!(xml.matches(r#"<data key="clique">1</data>"#).count(), 1);
+        assert!(xml.contains(r#"<edge source="b" target="c"/>"#), "{xml}");
+        assert_eq!(xml.matches("<edge ").count(), network.edge_count());
+        assert!(xml.trim_end().ends_with("</graphml>"));
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+
+    #[test]
+    fn unknown_ids_are_ignored() {
+        let network = Network::new([("a", "b"), ("b", "c")]);
+        assert_eq!(
+            export(&network, &[vec![0, 1, 99]], GraphFormat::Dot),
+            export(&network, &[vec![0, 1]], GraphFormat::Dot)
+        );
+    }
+}
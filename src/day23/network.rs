@@ -0,0 +1,623 @@
+//! An undirected graph of named hosts, with triangle and clique searches that
+//! scale to dumps with tens of thousands of hosts.
+//!
+//! Hosts are interned in name order, so sorting by id sorts by name and every
+//! result comes out sorted.  Adjacency is kept as sorted lists for the whole
+//! graph; clique searches build a bitset adjacency matrix over one host's
+//! neighbourhood at a time, so they never need an n² matrix.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+/// A host's index in the network.
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, Default)]
+pub struct Network<'a> {
+    names: Vec<&'a str>,
+    ids: HashMap<&'a str, NodeId>,
+    /// Sorted neighbours of each host.
+    neighbours: Vec<Vec<NodeId>>,
+}
+
+impl<'a> Network<'a> {
+    /// Build a network from its connections.  Repeated connections and hosts
+    /// connected to themselves are ignored.
+    pub fn new(connections: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let connections = connections.into_iter().collect::<Vec<_>>();
+        let mut names = connections
+            .iter()
+            .flat_map(|(a, b)| [*a, *b])
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (*name, id))
+            .collect::<HashMap<_, _>>();
+
+        let mut neighbours = vec![vec![]; names.len()];
+        for (a, b) in connections {
+            let (a, b) = (ids[a], ids[b]);
+            if a != b {
+                neighbours[a].push(b);
+                neighbours[b].push(a);
+            }
+        }
+        for list in neighbours.iter_mut() {
+            list.sort_unstable();
+            list.dedup();
+        }
+        Network {
+            names,
+            ids,
+            neighbours,
+        }
+    }
+
+    /// Number of hosts.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Number of connections.
+    pub fn edge_count(&self) -> usize {
+        self.neighbours.iter().map(Vec::len).sum::<usize>() / 2
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &'a str {
+        self.names[id]
+    }
+
+    /// Every host name, sorted.
+    pub fn names(&self) -> &[&'a str] {
+        &self.names
+    }
+
+    /// Sorted neighbours of a host.
+    pub fn neighbours(&self, id: NodeId) -> &[NodeId] {
+        &self.neighbours[id]
+    }
+
+    pub fn degree(&self, id: NodeId) -> usize {
+        self.neighbours[id].len()
+    }
+
+    pub fn is_connected(&self, a: NodeId, b: NodeId) -> bool {
+        self.neighbours[a].binary_search(&b).is_ok()
+    }
+
+    /// Every triangle, each sorted, in sorted order.
+    ///
+    /// Each edge points from the lower to the higher degree host, so every
+    /// host has O(√m) outgoing edges and each triangle is found exactly once
+    /// by intersecting the outgoing lists of its two lowest hosts.
+    pub fn triangles(&self) -> Vec<[NodeId; 3]> {
+        let rank = |id: NodeId| (self.degree(id), id);
+        let out = self
+            .neighbours
+            .iter()
+            .enumerate()
+            .map(|(a, list)| {
+                list.iter()
+                    .copied()
+                    .filter(|&b| rank(a) < rank(b))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut triangles = vec![];
+        for (a, a_out) in out.iter().enumerate() {
+            for &b in a_out {
+                for c in intersect(a_out, &out[b]) {
+                    let mut triangle = [a, b, c];
+                    triangle.sort_unstable();
+                    triangles.push(triangle);
+                }
+            }
+        }
+        triangles.sort_unstable();
+        triangles
+    }
+
+    /// The triangles as host names.
+    pub fn triangle_names(&self) -> Vec<[&'a str; 3]> {
+        self.triangles()
+            .into_iter()
+            .map(|t| t.map(|id| self.name(id)))
+            .collect()
+    }
+
+    /// A largest clique, sorted.  When several are the largest, the first in
+    /// sorted order.
+    pub fn max_clique(&self) -> Vec<NodeId> {
+        let mut best = vec![];
+        self.for_each_maximal_clique(0, |clique| {
+            if clique.len() > best.len() || (clique.len() == best.len() && clique < &best[..]) {
+                best = clique.to_vec();
+            }
+            // Smaller cliques can't win, equal ones might on the tie break.
+            best.len()
+        });
+        best
+    }
+
+    /// Every maximal clique with at least `min_size` hosts, each sorted, in
+    /// sorted order.
+    pub fn maximal_cliques(&self, min_size: usize) -> Vec<Vec<NodeId>> {
+        let mut cliques = vec![];
+        self.for_each_maximal_clique(min_size, |clique| {
+            cliques.push(clique.to_vec());
+            min_size
+        });
+        cliques.sort_unstable();
+        cliques
+    }
+
+    /// Triangles with at least one host whose name matches `predicate`.
+    pub fn triangles_where(&self, predicate: impl Fn(&str) -> bool) -> Vec<[NodeId; 3]> {
+        let matches = self
+            .names
+            .iter()
+            .map(|name| predicate(name))
+            .collect::<Vec<_>>();
+        self.triangles()
+            .into_iter()
+            .filter(|t| t.iter().any(|&id| matches[id]))
+            .collect()
+    }
+
+    /// How many hosts have each degree.
+    pub fn degree_distribution(&self) -> BTreeMap<usize, usize> {
+        let mut distribution = BTreeMap::new();
+        for list in &self.neighbours {
+            *distribution.entry(list.len()).or_insert(0) += 1;
+        }
+        distribution
+    }
+
+    /// Connected components, each sorted, ordered by their lowest host.
+    pub fn components(&self) -> Vec<Vec<NodeId>> {
+        let mut seen = vec![false; self.len()];
+        let mut components = vec![];
+        for start in 0..self.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut component = vec![start];
+            let mut i = 0;
+            while let Some(&id) = component.get(i) {
+                for &next in &self.neighbours[id] {
+                    if !seen[next] {
+                        seen[next] = true;
+                        component.push(next);
+                    }
+                }
+                i += 1;
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// Fraction of pairs of each host's neighbours that are connected, 0 for
+    /// hosts with fewer than two neighbours.
+    pub fn clustering_coefficients(&self) -> Vec<f64> {
+        let mut triangles = vec![0usize; self.len()];
+        for t in self.triangles() {
+            for id in t {
+                triangles[id] += 1;
+            }
+        }
+        triangles
+            .iter()
+            .zip(&self.neighbours)
+            .map(|(&t, list)| {
+                let pairs = list.len() * list.len().saturating_sub(1) / 2;
+                if pairs == 0 {
+                    0.0
+                } else {
+                    t as f64 / pairs as f64
+                }
+            })
+            .collect()
+    }
+
+    /// Mean of the hosts' clustering coefficients.
+    pub fn average_clustering(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        self.clustering_coefficients().iter().sum::<f64>() / self.len() as f64
+    }
+
+    pub fn summary(&self) -> Summary {
+        let components = self.components();
+        Summary {
+            hosts: self.len(),
+            connections: self.edge_count(),
+            components: components.len(),
+            largest_component: components.iter().map(Vec::len).max().unwrap_or(0),
+            average_clustering: self.average_clustering(),
+            degree_distribution: self.degree_distribution(),
+        }
+    }
+
+    /// Hosts as names.
+    pub fn names_of(&self, ids: &[NodeId]) -> Vec<&'a str> {
+        ids.iter().map(|&id| self.name(id)).collect()
+    }
+
+    /// Bron–Kerbosch with pivoting, started from each host in degeneracy
+    /// order so each maximal clique is found once.  `f` gets each sorted
+    /// maximal clique of at least the minimum size and returns the new
+    /// minimum, so searches can prune as they go.
+    fn for_each_maximal_clique(&self, min_size: usize, mut f: impl FnMut(&[NodeId]) -> usize) {
+        let order = self.degeneracy_order();
+        let mut position = vec![0; self.len()];
+        for (i, &id) in order.iter().enumerate() {
+            position[id] = i;
+        }
+
+        let mut search = Search {
+            min_size,
+            report: &mut f,
+            clique: vec![],
+        };
+        // Local index of each host in the current neighbourhood.
+        let mut local = vec![usize::MAX; self.len()];
+        for &v in &order {
+            let around = &self.neighbours[v];
+            for (i, &w) in around.iter().enumerate() {
+                local[w] = i;
+            }
+            let rows = around
+                .iter()
+                .map(|&w| {
+                    let mut row = Bits::new(around.len());
+                    for &u in &self.neighbours[w] {
+                        if local[u] != usize::MAX {
+                            row.set(local[u]);
+                        }
+                    }
+                    row
+                })
+                .collect::<Vec<_>>();
+            let mut later = Bits::new(around.len());
+            let mut earlier = Bits::new(around.len());
+            for (i, &w) in around.iter().enumerate() {
+                if position[w] > position[v] {
+                    later.set(i);
+                } else {
+                    earlier.set(i);
+                }
+            }
+
+            search.clique.push(v);
+            search.expand(around, &rows, later, earlier);
+            search.clique.pop();
+            for &w in around {
+                local[w] = usize::MAX;
+            }
+        }
+    }
+
+    /// Hosts in the order of repeatedly removing one of least degree.
+    fn degeneracy_order(&self) -> Vec<NodeId> {
+        let mut degree = self.neighbours.iter().map(Vec::len).collect::<Vec<_>>();
+        let max = degree.iter().copied().max().unwrap_or(0);
+        let mut buckets = vec![vec![]; max + 1];
+        for (id, &d) in degree.iter().enumerate() {
+            buckets[d].push(id);
+        }
+        let mut removed = vec![false; self.len()];
+        let mut order = Vec::with_capacity(self.len());
+        let mut d = 0;
+        while order.len() < self.len() {
+            // Buckets hold stale entries for hosts whose degree has dropped.
+            let Some(id) = buckets[d].pop() else {
+                d += 1;
+                continue;
+            };
+            if removed[id] || degree[id] != d {
+                continue;
+            }
+            removed[id] = true;
+            order.push(id);
+            for &w in &self.neighbours[id] {
+                if !removed[w] {
+                    degree[w] -= 1;
+                    buckets[degree[w]].push(w);
+                }
+            }
+            d = d.saturating_sub(1);
+        }
+        order
+    }
+}
+
+/// Statistics for a whole network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub hosts: usize,
+    pub connections: usize,
+    pub components: usize,
+    pub largest_component: usize,
+    pub average_clustering: f64,
+    /// How many hosts have each degree.
+    pub degree_distribution: BTreeMap<usize, usize>,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "hosts: {}", self.hosts)?;
+        writeln!(f, "connections: {}", self.connections)?;
+        writeln!(
+            f,
+            "components: {} (largest {})",
+            self.components, self.largest_component
+        )?;
+        writeln!(f, "average clustering: {:.4}", self.average_clustering)?;
+        write!(f, "degrees:")?;
+        for (degree, count) in &self.degree_distribution {
+            write!(f, " {degree}x{count}")?;
+        }
+        Ok(())
+    }
+}
+
+struct Search<'f, F> {
+    min_size: usize,
+    report: &'f mut F,
+    clique: Vec<NodeId>,
+}
+
+impl<F: FnMut(&[NodeId]) -> usize> Search<'_, F> {
+    /// Grow `clique` with hosts from `candidates` (local indices into
+    /// `around`), skipping cliques already grown from `excluded`.
+    fn expand(&mut self, around: &[NodeId], rows: &[Bits], candidates: Bits, excluded: Bits) {
+        if candidates.is_empty() {
+            if excluded.is_empty() && self.clique.len() >= self.min_size {
+                let mut clique = self.clique.clone();
+                clique.sort_unstable();
+                self.min_size = (self.report)(&clique);
+            }
+            return;
+        }
+        if self.clique.len() + candidates.count() < self.min_size {
+            return;
+        }
+
+        // Pivot on the host adjacent to the most candidates; only hosts it
+        // isn't adjacent to need trying.
+        let pivot = candidates
+            .ones()
+            .chain(excluded.ones())
+            .max_by_key(|&u| candidates.and(&rows[u]).count())
+            .expect("candidates isn't empty");
+        let mut candidates = candidates;
+        let mut excluded = excluded;
+        for v in candidates.and_not(&rows[pivot]).ones().collect::<Vec<_>>() {
+            self.clique.push(around[v]);
+            self.expand(
+                around,
+                rows,
+                candidates.and(&rows[v]),
+                excluded.and(&rows[v]),
+            );
+            self.clique.pop();
+            candidates.clear(v);
+            excluded.set(v);
+        }
+    }
+}
+
+/// A fixed size set of small integers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn new(len: usize) -> Self {
+        Bits(vec![0; len.div_ceil(64)])
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn clear(&mut self, i: usize) {
+        self.0[i / 64] &= !(1 << (i % 64));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|w| *w == 0)
+    }
+
+    fn count(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn and(&self, other: &Bits) -> Bits {
+        Bits(self.0.iter().zip(&other.0).map(|(a, b)| a & b).collect())
+    }
+
+    fn and_not(&self, other: &Bits) -> Bits {
+        Bits(self.0.iter().zip(&other.0).map(|(a, b)| a & !b).collect())
+    }
+
+    fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+/// Common elements of two sorted lists.
+fn intersect<'l>(a: &'l [NodeId], b: &'l [NodeId]) -> impl Iterator<Item = NodeId> + 'l {
+    let (mut i, mut j) = (0, 0);
+    std::iter::from_fn(move || {
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    i += 1;
+                    j += 1;
+                    return Some(a[i - 1]);
+                }
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestRng;
+
+    fn random_network(rng: &mut TestRng, names: &[String]) -> Network<'static> {
+        let names = names
+            .iter()
+            .map(|n| &*n.clone().leak())
+            .collect::<Vec<&'static str>>();
+        let mut edges = vec![];
+        for (i, a) in names.iter().enumerate() {
+            for b in &names[i + 1..] {
+                if rng.below(100) < 45 {
+                    edges.push((*a, *b));
+                }
+            }
+        }
+        Network::new(edges)
+    }
+
+    fn is_clique(network: &Network, ids: &[NodeId]) -> bool {
+        ids.iter()
+            .enumerate()
+            .all(|(i, &a)| ids[i + 1..].iter().all(|&b| network.is_connected(a, b)))
+    }
+
+    #[test]
+    fn small_network() {
+        let network = Network::new([
+            ("b", "a"),
+            ("a", "c"),
+            ("c", "b"),
+            ("c", "d"),
+            ("a", "a"),
+            ("b", "a"),
+        ]);
+        assert_eq!(network.names(), ["a", "b", "c", "d"]);
+        assert_eq!(network.edge_count(), 4);
+        assert_eq!(network.neighbours(network.id("c").unwrap()), [0, 1, 3]);
+        assert_eq!(network.triangle_names(), [["a", "b", "c"]]);
+        assert_eq!(network.names_of(&network.max_clique()), ["a", "b", "c"]);
+        assert_eq!(network.maximal_cliques(0), vec![vec![0, 1, 2], vec![2, 3]]);
+        assert_eq!(network.maximal_cliques(3), vec![vec![0, 1, 2]]);
+        assert!(Network::default().max_clique().is_empty());
+    }
+
+    #[test]
+    fn statistics() {
+        let network = Network::new([("a", "b"), ("b", "c"), ("c", "a"), ("c", "d"), ("e", "f")]);
+        assert_eq!(
+            network.degree_distribution(),
+            BTreeMap::from([(1, 3), (2, 2), (3, 1)])
+        );
+        assert_eq!(network.components(), vec![vec![0, 1, 2, 3], vec![4, 5]]);
+        assert_eq!(
+            network.clustering_coefficients(),
+            vec![1.0, 1.0, 1.0 / 3.0, 0.0, 0.0, 0.0]
+        );
+        assert!(network.triangles_where(|name| name == "d").is_empty());
+        assert_eq!(network.triangles_where(|name| name == "b").len(), 1);
+
+        let summary = network.summary();
+        assert_eq!(summary.components, 2);
+        assert_eq!(summary.largest_component, 4);
+        assert_eq!(
+            summary.to_string(),
+            "hosts: 6\nconnections: 5\ncomponents: 2 (largest 4)\n\
+             average clustering: 0.3889\ndegrees: 1x3 2x2 3x1"
+        );
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = TestRng(41);
+        let names = (0..11).map(|i| format!("h{i:02}")).collect::<Vec<_>>();
+        for _ in 0..20 {
+            let network = random_network(&mut rng, &names);
+            let n = network.len();
+
+            let mut triangles = vec![];
+            for a in 0..n {
+                for b in a + 1..n {
+                    for c in b + 1..n {
+                        if is_clique(&network, &[a, b, c]) {
+                            triangles.push([a, b, c]);
+                        }
+                    }
+                }
+            }
+            assert_eq!(network.triangles(), triangles);
+
+            // Every subset, in an order that makes the first largest clique
+            // the lowest sorted one.
+            let cliques = (1u32..1 << n)
+                .map(|mask| (0..n).filter(|i| mask & (1 << i) != 0).collect::<Vec<_>>())
+                .filter(|ids| is_clique(&network, ids))
+                .collect::<Vec<_>>();
+            let maximal = cliques
+                .iter()
+                .filter(|ids| {
+                    (0..n).all(|v| {
+                        ids.contains(&v) || ids.iter().any(|&u| !network.is_connected(u, v))
+                    })
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut sorted = maximal.clone();
+            sorted.sort();
+            assert_eq!(network.maximal_cliques(0), sorted);
+            assert_eq!(
+                network.maximal_cliques(4),
+                sorted
+                    .iter()
+                    .filter(|c| c.len() >= 4)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            );
+
+            let size = maximal.iter().map(Vec::len).max().unwrap_or(0);
+            let first = sorted
+                .iter()
+                .find(|c| c.len() == size)
+                .cloned()
+                .unwrap_or_default();
+            assert_eq!(network.max_clique(), first);
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! Check that a netlist is a ripple carry adder, `z = x + y`, and find the
+//! fewest gate output swaps that repair it.
+//!
+//! Each bit of a correct adder is the canonical full adder:
+//!
+//! ```text
+//! s = x XOR y    z = s XOR carry_in    g = x AND y
+//! p = s AND carry_in                   carry_out = g OR p
+//! ```
+//!
+//! with bit 0 a half adder (`z00 = x00 XOR y00`, carry `x00 AND y00`) and
+//! the top `z` bit the last carry.  [`suspects`] flags gates whose outputs
+//! don't fit those roles.  [`find_swaps`] finds the lowest bit that adds
+//! wrongly, tries swapping outputs of gates near that bit, and keeps swaps
+//! that push the first wrong bit higher, deepening until every bit passes.
+//! Swaps that only help together are found by also trying every set of swaps
+//! among the suspects.
+
+use super::{
+    netlist::{Bus, Netlist, WireId, LANES},
+    Op,
+};
+use crate::Result;
+
+/// Random full width additions run on top of the per-bit tests.
+const RANDOM_TESTS: usize = 4 * LANES;
+
+/// A gate output that doesn't fit its role in a full adder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Suspect {
+    pub wire: WireId,
+    pub reason: &'static str,
+}
+
+/// Width of the adder's inputs, checking the buses line up.
+pub fn width(netlist: &Netlist) -> Result<usize> {
+    let n = netlist.bus(Bus::X).len();
+    if n == 0 || netlist.bus(Bus::Y).len() != n || netlist.bus(Bus::Z).len() != n + 1 {
+        anyhow::bail!(
+            "an adder needs x and y buses of the same width and one more z bit, not {}, {} and {}",
+            n,
+            netlist.bus(Bus::Y).len(),
+            netlist.bus(Bus::Z).len()
+        );
+    }
+    if n >= 64 {
+        anyhow::bail!("adders are checked with 64 bit sums, {n} bits is too wide");
+    }
+    Ok(n)
+}
+
+/// Gate outputs that break the full adder roles, sorted by wire name.
+pub fn suspects(netlist: &Netlist) -> Result<Vec<Suspect>> {
+    let n = width(netlist)?;
+    let bit = |wire: WireId, bus: Bus| netlist.bus(bus).iter().position(|&w| w == wire);
+    let mut readers = vec![vec![]; netlist.wire_count()];
+    for gate in netlist.gates() {
+        readers[gate.a].push(gate.op);
+        readers[gate.b].push(gate.op);
+    }
+
+    let mut suspects = vec![];
+    let mut flag = |wire, reason| suspects.push(Suspect { wire, reason });
+    for gate in netlist.gates() {
+        let w = gate.out;
+        let read_by = |op| readers[w].contains(&op);
+        let inputs = [gate.a, gate.b].map(|i| bit(i, Bus::X).or(bit(i, Bus::Y)));
+        let from_inputs = inputs.iter().all(Option::is_some);
+        let first_bit = from_inputs && inputs == [Some(0), Some(0)];
+        let feeds_carry = !readers[w].is_empty() && readers[w].iter().all(|op| *op == Op::OR);
+
+        match bit(w, Bus::Z) {
+            Some(k) if k == n => {
+                let op = if n == 1 { Op::AND } else { Op::OR };
+                if gate.op != op {
+                    flag(w, "the top z bit should be the last carry");
+                }
+            }
+            Some(0) if !(gate.op == Op::XOR && first_bit) => {
+                flag(w, "z00 should be x00 XOR y00");
+            }
+            Some(_) if gate.op != Op::XOR => flag(w, "z bits should come from an XOR"),
+            _ => {}
+        }
+        match gate.op {
+            Op::XOR if from_inputs && !first_bit && !read_by(Op::XOR) => {
+                flag(w, "x XOR y should feed the sum XOR");
+            }
+            Op::XOR if !from_inputs && bit(w, Bus::Z).is_none() => {
+                flag(w, "the sum XOR should drive a z bit");
+            }
+            Op::AND if !first_bit && !feeds_carry && bit(w, Bus::Z) != Some(n) => {
+                flag(w, "an AND should only feed a carry OR");
+            }
+            Op::OR if bit(w, Bus::Z).is_none() && !read_by(Op::XOR) => {
+                flag(w, "a carry should feed the next sum XOR");
+            }
+            _ => {}
+        }
+    }
+    suspects.sort_by_key(|s| netlist.name(s.wire));
+    suspects.dedup_by_key(|s| s.wire);
+    Ok(suspects)
+}
+
+/// The lowest `z` bit that comes out wrong, or None if the netlist adds.
+///
+/// Every bit is tested exhaustively with all values of its own and the
+/// previous bit's inputs, then the full width with random additions and
+/// long carry chains.
+pub fn first_wrong_bit(netlist: &Netlist) -> Result<Option<usize>> {
+    let n = width(netlist)?;
+    let mut x = vec![];
+    let mut y = vec![];
+    for i in 0..n {
+        for combo in 0..16u64 {
+            let at = |b: u64, shift: usize| b << shift;
+            let low = i.saturating_sub(1);
+            x.push(at(combo & 1, i) | at((combo >> 2) & 1, low));
+            y.push(at((combo >> 1) & 1, i) | at((combo >> 3) & 1, low));
+        }
+    }
+    let mask = (1u64 << n) - 1;
+    let mut rng = SplitMix(0x0add_0add);
+    for _ in 0..RANDOM_TESTS {
+        x.push(rng.next() & mask);
+        y.push(rng.next() & mask);
+    }
+    for i in 0..n {
+        // A carry rippling from bit i to the top.
+        x.push(mask);
+        y.push(1 << i);
+    }
+
+    let z = netlist.run(&x, &y)?;
+    Ok(x.iter()
+        .zip(&y)
+        .zip(z)
+        .map(|((x, y), z)| z ^ (x + y))
+        .filter(|wrong| *wrong != 0)
+        .map(|wrong| wrong.trailing_zeros() as usize)
+        .min())
+}
+
+/// The fewest output swaps, at most `max_swaps`, that make the netlist add.
+///
+/// Each number of swaps is searched two ways.  First greedily, where each
+/// swap must fix the lowest wrong bit without breaking any bit below it,
+/// which keeps the search local to one adder stage at a time.  Then over
+/// every set of swaps among the suspects, which finds swaps that only fix a
+/// bit together.  A repair needing a swap that is neither near a wrong bit
+/// nor between two suspects is not found.
+pub fn find_swaps(netlist: &Netlist, max_swaps: usize) -> Result<Vec<(WireId, WireId)>> {
+    let suspects = suspects(netlist)?
+        .into_iter()
+        .map(|s| s.wire)
+        .collect::<Vec<_>>();
+    for budget in 0..=max_swaps {
+        let mut swaps = vec![];
+        if search(netlist, &suspects, budget, &mut swaps)? {
+            return Ok(swaps);
+        }
+        if let Some(swaps) = among_suspects(netlist, &suspects, budget)? {
+            return Ok(swaps);
+        }
+    }
+    anyhow::bail!(
+        "no {max_swaps} or fewer swaps near a wrong bit or among the suspects make this netlist add"
+    )
+}
+
+/// The first set of `budget` disjoint swaps between suspects that makes the
+/// netlist add, trying every one.
+fn among_suspects(
+    netlist: &Netlist,
+    suspects: &[WireId],
+    budget: usize,
+) -> Result<Option<Vec<(WireId, WireId)>>> {
+    if budget == 0 || suspects.len() < 2 * budget {
+        return Ok(None);
+    }
+    let pairs = suspects
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &a)| suspects[i + 1..].iter().map(move |&b| (a, b)))
+        .collect::<Vec<_>>();
+    let mut swaps = vec![];
+    if choose(netlist, &pairs, budget, &mut swaps)? {
+        return Ok(Some(swaps));
+    }
+    Ok(None)
+}
+
+/// Extend `swaps` with disjoint `pairs`, in order, until `budget` are chosen
+/// and the netlist adds.
+fn choose(
+    netlist: &Netlist,
+    pairs: &[(WireId, WireId)],
+    budget: usize,
+    swaps: &mut Vec<(WireId, WireId)>,
+) -> Result<bool> {
+    if swaps.len() == budget {
+        // Swaps that make a cycle can't be the answer.
+        return Ok(match netlist.swapped(swaps) {
+            Ok(next) => first_wrong_bit(&next)?.is_none(),
+            Err(_) => false,
+        });
+    }
+    for (i, &(a, b)) in pairs.iter().enumerate() {
+        let used = |w| swaps.iter().any(|&(c, d)| c == w || d == w);
+        if used(a) || used(b) {
+            continue;
+        }
+        swaps.push((a, b));
+        if choose(netlist, &pairs[i + 1..], budget, swaps)? {
+            return Ok(true);
+        }
+        swaps.pop();
+    }
+    Ok(false)
+}
+
+fn search(
+    netlist: &Netlist,
+    suspects: &[WireId],
+    budget: usize,
+    swaps: &mut Vec<(WireId, WireId)>,
+) -> Result<bool> {
+    let Some(wrong) = first_wrong_bit(netlist)? else {
+        return Ok(true);
+    };
+    if budget == 0 {
+        return Ok(false);
+    }
+    for pair in candidates(netlist, suspects, wrong) {
+        // Swaps that make a cycle can't be the answer.
+        let Ok(next) = netlist.swapped(&[pair]) else {
+            continue;
+        };
+        if first_wrong_bit(&next)?.is_some_and(|bit| bit <= wrong) {
+            continue;
+        }
+        swaps.push(pair);
+        if search(&next, suspects, budget - 1, swaps)? {
+            return Ok(true);
+        }
+        swaps.pop();
+    }
+    Ok(false)
+}
+
+/// Pairs of gate outputs worth swapping to fix bit `wrong`, suspects first.
+///
+/// One end of each
+/// pair is a gate within a stage of `wrong`; the other is any gate from
+/// those stages or a suspect.
+fn candidates(netlist: &Netlist, suspects: &[WireId], wrong: usize) -> Vec<(WireId, WireId)> {
+    let stage = netlist.stages();
+    let near = netlist
+        .gates()
+        .iter()
+        .map(|gate| gate.out)
+        .filter(|&w| stage[w].is_some_and(|s| s + 1 >= wrong && s <= wrong + 1))
+        .collect::<Vec<_>>();
+    let mut others = near.clone();
+    others.extend(suspects.iter().filter(|s| !near.contains(s)));
+
+    let mut pairs = near
+        .iter()
+        .flat_map(|&a| others.iter().map(move |&b| (a.min(b), a.max(b))))
+        .filter(|(a, b)| a != b)
+        .collect::<Vec<_>>();
+    pairs.sort_unstable();
+    pairs.dedup();
+    let suspected = |w: &WireId| suspects.contains(w);
+    pairs.sort_by_key(|(a, b)| !suspected(a) as u8 + !suspected(b) as u8);
+    pairs
+}
+
+/// Swaps as names, each pair and the list sorted.
+pub fn swap_names<'a>(
+    netlist: &Netlist<'a>,
+    swaps: &[(WireId, WireId)],
+) -> Vec<(&'a str, &'a str)> {
+    let mut names = swaps
+        .iter()
+        .map(|&(a, b)| {
+            let (a, b) = (netlist.name(a), netlist.name(b));
+            (a.min(b), a.max(b))
+        })
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    names
+}
+
+/// Deterministic test values without a rand dependency.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// A ripple carry adder in the puzzle's input format, with the named gate
+/// outputs swapped, for testing graders.
+///
+/// Internal wires are `sNN` (x XOR y), `gNN` (x AND y), `pNN` (s AND carry)
+/// and `cNN` (carry out).  Inputs are all zero.
+pub fn ripple_carry_input(bits: usize, swaps: &[(&str, &str)]) -> String {
+    let swap = |wire: String| {
+        swaps
+            .iter()
+            .find_map(|&(a, b)| (wire == a).then_some(b).or((wire == b).then_some(a)))
+            .map_or(wire.clone(), str::to_string)
+    };
+    let mut lines = vec![];
+    for i in 0..bits {
+        lines.push(format!("x{i:02}: 0"));
+    }
+    for i in 0..bits {
+        lines.push(format!("y{i:02}: 0"));
+    }
+    lines.push(String::new());
+    let mut gate = |a: String, op: &str, b: String, out: String| {
+        lines.push(format!("{a} {op} {b} -> {}", swap(out)));
+    };
+    for i in 0..bits {
+        let (x, y) = (format!("x{i:02}"), format!("y{i:02}"));
+        let carry_out = if i + 1 == bits {
+            format!("z{bits:02}")
+        } else {
+            format!("c{i:02}")
+        };
+        if i == 0 {
+            gate(x.clone(), "XOR", y.clone(), "z00".into());
+            gate(x, "AND", y, carry_out);
+            continue;
+        }
+        let carry_in = format!("c{:02}", i - 1);
+        gate(x.clone(), "XOR", y.clone(), format!("s{i:02}"));
+        gate(x, "AND", y, format!("g{i:02}"));
+        gate(
+            format!("s{i:02}"),
+            "XOR",
+            carry_in.clone(),
+            format!("z{i:02}"),
+        );
+        gate(format!("s{i:02}"), "AND", carry_in, format!("p{i:02}"));
+        gate(format!("g{i:02}"), "OR", format!("p{i:02}"), carry_out);
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day24::Data;
+
+    fn analyse(input: &str) -> (Vec<String>, Vec<(String, String)>) {
+        let data = Data::parse(input).unwrap();
+        let netlist =
+            Netlist::new(data.start.keys().copied(), data.operations.iter().cloned()).unwrap();
+        let suspects = suspects(&netlist)
+            .unwrap()
+            .iter()
+            .map(|s| netlist.name(s.wire).to_string())
+            .collect();
+        let swaps = find_swaps(&netlist, 4).unwrap();
+        let swaps = swap_names(&netlist, &swaps)
+            .into_iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect();
+        (suspects, swaps)
+    }
+
+    #[test]
+    fn correct_adders() {
+        for bits in 1..=12 {
+            let input = ripple_carry_input(bits, &[]);
+            let (suspects, swaps) = analyse(&input);
+            assert!(suspects.is_empty(), "{bits} bits: {suspects:?}");
+            assert!(swaps.is_empty());
+        }
+    }
+
+    #[test]
+    fn finds_swaps() {
+        let cases: &[&[(&str, &str)]] = &[
+            &[("s03", "g03")],
+            &[("z05", "p05")],
+            &[("z02", "c04")],
+            &[("z07", "c07"), ("s02", "g02")],
+            &[
+                ("z01", "p01"),
+                ("s04", "g04"),
+                ("z09", "c09"),
+                ("p06", "z06"),
+            ],
+        ];
+        for swaps in cases {
+            let input = ripple_carry_input(12, swaps);
+            let (suspects, found) = analyse(&input);
+            let mut expected = swaps
+                .iter()
+                .map(|&(a, b)| (a.min(b).to_string(), a.max(b).to_string()))
+                .collect::<Vec<_>>();
+            expected.sort();
+            assert_eq!(found, expected, "{suspects:?}");
+            assert!(!suspects.is_empty());
+        }
+    }
+
+    #[test]
+    fn swaps_that_only_work_together() {
+        // Both swaps break bit 1, so neither alone moves the first wrong bit.
+        let swaps = [("z01", "c05"), ("s01", "g07")];
+        let (_, found) = analyse(&ripple_carry_input(8, &swaps));
+        assert_eq!(
+            found,
+            [("c05", "z01"), ("g07", "s01")].map(|(a, b)| (a.to_string(), b.to_string()))
+        );
+    }
+
+    #[test]
+    fn wrong_bits() {
+        let input = ripple_carry_input(8, &[("z05", "p05")]);
+        let data = Data::parse(&input).unwrap();
+        let netlist =
+            Netlist::new(data.start.keys().copied(), data.operations.iter().cloned()).unwrap();
+        assert_eq!(first_wrong_bit(&netlist).unwrap(), Some(5));
+        let z5 = netlist.id("z05").unwrap();
+        let p5 = netlist.id("p05").unwrap();
+        let fixed = netlist.swapped(&[(z5, p5)]).unwrap();
+        assert_eq!(first_wrong_bit(&fixed).unwrap(), None);
+        assert!(find_swaps(&netlist, 0).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! Draw a netlist as a Graphviz DOT or Mermaid flowchart.
+//!
+//! Every gate is a node named after the wire it drives, and every input wire
+//! is a node of its own.  Gates can be coloured by type, suspect gates
+//! outlined in red, gates grouped into one cluster per adder bit, and wires
+//! labelled with their values for one simulated addition.
+
+use super::{
+    netlist::{Bus, Netlist, WireId},
+    Op,
+};
+use crate::Result;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitFormat {
+    Dot,
+    Mermaid,
+}
+
+impl std::str::FromStr for CircuitFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dot" => Ok(CircuitFormat::Dot),
+            "mermaid" => Ok(CircuitFormat::Mermaid),
+            _ => anyhow::bail!("unknown circuit format {s:?}, expected dot or mermaid"),
+        }
+    }
+}
+
+/// Fill colour for a gate type or an input bus.
+fn fill(op: Option<Op>, wire: &str) -> &'static str {
+    match op {
+        Some(Op::AND) => "#b3de69",
+        Some(Op::OR) => "#80b1d3",
+        Some(Op::XOR) => "#fb8072",
+        None if wire.starts_with('x') => "#deb887",
+        None if wire.starts_with('y') => "#ffa500",
+        None => "#d9d9d9",
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitExporter<'n, 'a> {
+    netlist: &'n Netlist<'a>,
+    colour_gates: bool,
+    suspects: Vec<bool>,
+    cluster_by_bit: bool,
+    /// Each wire's value in the overlaid simulation.
+    values: Option<Vec<bool>>,
+}
+
+impl<'n, 'a> CircuitExporter<'n, 'a> {
+    pub fn new(netlist: &'n Netlist<'a>) -> Self {
+        CircuitExporter {
+            netlist,
+            colour_gates: false,
+            suspects: vec![false; netlist.wire_count()],
+            cluster_by_bit: false,
+            values: None,
+        }
+    }
+
+    /// Fill gates with a colour for AND, OR and XOR.
+    pub fn colour_gates(mut self, colour: bool) -> Self {
+        self.colour_gates = colour;
+        self
+    }
+
+    /// Outline the gates driving these wires.  Ids not in the netlist are
+    /// ignored.
+    pub fn highlight(mut self, wires: &[WireId]) -> Self {
+        for &wire in wires {
+            if let Some(suspect) = self.suspects.get_mut(wire) {
+                *suspect = true;
+            }
+        }
+        self
+    }
+
+    /// Group gates by the highest input bit they depend on.
+    pub fn cluster_by_bit(mut self, cluster: bool) -> Self {
+        self.cluster_by_bit = cluster;
+        self
+    }
+
+    /// Label every wire with its value when adding `x` and `y`.
+    pub fn overlay(mut self, x: u64, y: u64) -> Result<Self> {
+        let mut lanes = self.netlist.lanes();
+        self.netlist.set_bus(&mut lanes, Bus::X, &[x])?;
+        self.netlist.set_bus(&mut lanes, Bus::Y, &[y])?;
+        self.netlist.eval(&mut lanes);
+        self.values = Some(lanes.iter().map(|lane| lane & 1 == 1).collect());
+        Ok(self)
+    }
+
+    /// Nodes grouped by cluster, unclustered nodes under None.
+    fn clusters(&self) -> Vec<(Option<usize>, Vec<WireId>)> {
+        let wires = (0..self.netlist.wire_count()).collect::<Vec<_>>();
+        if !self.cluster_by_bit {
+            return vec![(None, wires)];
+        }
+        let stages = self.netlist.stages();
+        let mut clusters = Vec::<(Option<usize>, Vec<WireId>)>::new();
+        for wire in wires {
+            // Inputs stay outside the clusters so buses line up.
+            let stage = stages[wire].filter(|_| self.netlist.driver(wire).is_some());
+            match clusters.iter_mut().find(|(s, _)| *s == stage) {
+                Some((_, members)) => members.push(wire),
+                None => clusters.push((stage, vec![wire])),
+            }
+        }
+        clusters.sort_by_key(|(stage, _)| *stage);
+        clusters
+    }
+
+    fn label(&self, wire: WireId) -> String {
+        let name = self.netlist.name(wire);
+        let mut label = match self.netlist.driver(wire) {
+            Some(gate) => format!("{:?} {name}", gate.op),
+            None => name.to_string(),
+        };
+        if let Some(values) = &self.values {
+            label += &format!(" = {}", values[wire] as u8);
+        }
+        label
+    }
+
+    /// Every edge, from the wire read to the gate reading it.
+    fn edges(&self) -> impl Iterator<Item = (WireId, WireId)> + '_ {
+        self.netlist
+            .gates()
+            .iter()
+            .flat_map(|gate| [(gate.a, gate.out), (gate.b, gate.out)])
+    }
+
+    pub fn write_dot(&self, mut w: impl Write) -> Result<()> {
+        writeln!(w, "digraph circuit {{")?;
+        writeln!(w, "  rankdir=TB;")?;
+        for (stage, wires) in self.clusters() {
+            let indent = match stage {
+                Some(bit) => {
+                    writeln!(w, "  subgraph cluster_bit{bit:02} {{")?;
+                    writeln!(w, "    label=\"bit {bit}\";")?;
+                    "    "
+                }
+                None => "  ",
+            };
+            for wire in wires {
+                let name = self.netlist.name(wire);
+                let op = self.netlist.driver(wire).map(|gate| gate.op);
+                let mut attrs = vec![format!("label=\"{}\"", self.label(wire))];
+                if op.is_none() {
+                    attrs.push("shape=box".into());
+                }
+                if self.colour_gates {
+                    attrs.push(format!("style=filled, fillcolor=\"{}\"", fill(op, name)));
+                }
+                if name.starts_with('z') {
+                    attrs.push("peripheries=2".into());
+                }
+                if self.suspects[wire] {
+                    attrs.push("color=red, penwidth=3".into());
+                }
+                writeln!(w, "{indent}\"{name}\" [{}];", attrs.join(", "))?;
+            }
+            if stage.is_some() {
+                writeln!(w, "  }}")?;
+            }
+        }
+        for (from, to) in self.edges() {
+            let (a, b) = (self.netlist.name(from), self.netlist.name(to));
+            match &self.values {
+                Some(values) if values[from] => {
+                    writeln!(w, "  \"{a}\" -> \"{b}\" [color=red];")?;
+                }
+                _ => writeln!(w, "  \"{a}\" -> \"{b}\";")?,
+            }
+        }
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    pub fn write_mermaid(&self, mut w: impl Write) -> Result<()> {
+        // Mermaid ids must be plain words, so nodes are numbered.
+        writeln!(w, "flowchart TB")?;
+        for (stage, wires) in self.clusters() {
+            let indent = match stage {
+                Some(bit) => {
+                    writeln!(w, "  subgraph bit{bit:02} [\"bit {bit}\"]")?;
+                    "    "
+                }
+                None => "  ",
+            };
+            for wire in wires {
+                let label = self.label(wire);
+                match self.netlist.driver(wire) {
+                    Some(_) => writeln!(w, "{indent}w{wire}([\"{label}\"])")?,
+                    None => writeln!(w, "{indent}w{wire}[\"{label}\"]")?,
+                }
+            }
+            if stage.is_some() {
+                writeln!(w, "  end")?;
+            }
+        }
+        for (from, to) in self.edges() {
+            writeln!(w, "  w{from} --> w{to}")?;
+        }
+
+        if self.colour_gates {
+            for (class, op) in [("and", Op::AND), ("or", Op::OR), ("xor", Op::XOR)] {
+                writeln!(w, "  classDef {class} fill:{}", fill(Some(op), ""))?;
+            }
+            writeln!(w, "  classDef x fill:{}", fill(None, "x"))?;
+            writeln!(w, "  classDef y fill:{}", fill(None, "y"))?;
+            for wire in 0..self.netlist.wire_count() {
+                let class = match self.netlist.driver(wire).map(|gate| gate.op) {
+                    Some(Op::AND) => "and",
+                    Some(Op::OR) => "or",
+                    Some(Op::XOR) => "xor",
+                    None if self.netlist.name(wire).starts_with('x') => "x",
+                    None if self.netlist.name(wire).starts_with('y') => "y",
+                    None => continue,
+                };
+                writeln!(w, "  class w{wire} {class}")?;
+            }
+        }
+        // Styled last so the outline wins over the gate colour.
+        for wire in (0..self.netlist.wire_count()).filter(|&wire| self.suspects[wire]) {
+            writeln!(w, "  style w{wire} stroke:#f00,stroke-width:3px")?;
+        }
+        Ok(())
+    }
+
+    pub fn write(&self, format: CircuitFormat, w: impl Write) -> Result<()> {
+        match format {
+            CircuitFormat::Dot => self.write_dot(w),
+            CircuitFormat::Mermaid => self.write_mermaid(w),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day24::{adder, Data};
+
+    fn export(exporter: &CircuitExporter, format: CircuitFormat) -> String {
+        let mut out = vec![];
+        exporter.write(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn half_adder() {
+        let input = adder::ripple_carry_input(1, &[]);
+        let data = Data::parse(&input).unwrap();
+        let mut inputs = data.start.keys().copied().collect::<Vec<_>>();
+        inputs.sort_unstable();
+        let netlist = Netlist::new(inputs, data.operations.iter().cloned()).unwrap();
+        let exporter = CircuitExporter::new(&netlist).overlay(1, 1).unwrap();
+        assert_eq!(
+            export(&exporter, CircuitFormat::Dot),
+            r#"digraph circuit {
+  rankdir=TB;
+  "x00" [label="x00 = 1", shape=box];
+  "y00" [label="y00 = 1", shape=box];
+  "z00" [label="XOR z00 = 0", peripheries=2];
+  "z01" [label="AND z01 = 1", peripheries=2];
+  "x00" -> "z01" [color=red];
+  "y00" -> "z01" [color=red];
+  "x00" -> "z00" [color=red];
+  "y00" -> "z00" [color=red];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn clusters_and_highlights() {
+        let input = adder::ripple_carry_input(3, &[("z01", "p01")]);
+        let data = Data::parse(&input).unwrap();
+        let netlist =
+            Netlist::new(data.start.keys().copied(), data.operations.iter().cloned()).unwrap();
+        let suspects = adder::suspects(&netlist)
+            .unwrap()
+            .iter()
+            .map(|s| s.wire)
+            .collect::<Vec<_>>();
+        let exporter = CircuitExporter::new(&netlist)
+            .colour_gates(true)
+            .cluster_by_bit(true)
+            .highlight(&suspects);
+
+        let dot = export(&exporter, CircuitFormat::Dot);
+        for bit in 0..3 {
+            assert!(
+                dot.contains(&format!("subgraph cluster_bit{bit:02} {{")),
+                "{dot}"
+            );
+        }
+        assert_eq!(dot.matches("penwidth=3").count(), suspects.len());
+        assert!(dot.contains(r##""z01" [label="AND z01", style=filled, fillcolor="#b3de69""##));
+
+        let mermaid = export(&exporter, CircuitFormat::Mermaid);
+        assert!(mermaid.starts_with("flowchart TB\n"));
+        assert_eq!(mermaid.matches("  subgraph ").count(), 3);
+        assert_eq!(mermaid.matches(" --> ").count(), 2 * netlist.gates().len());
+        assert_eq!(mermaid.matches("stroke:#f00").count(), suspects.len());
+        let z01 = netlist.id("z01").unwrap();
+        assert!(mermaid.contains(&format!("  class w{z01} and\n")));
+        assert!("svg".parse::<CircuitFormat>().is_err());
+    }
+
+    #[test]
+    fn unknown_wires_are_ignored() {
+        let input = adder::ripple_carry_input(1, &[]);
+        let data = Data::parse(&input).unwrap();
+        let netlist =
+            Netlist::new(data.start.keys().copied(), data.operations.iter().cloned()).unwrap();
+        let z00 = netlist.id("z00").unwrap();
+        let exporter = CircuitExporter::new(&netlist).highlight(&[z00, netlist.wire_count()]);
+        let dot = export(&exporter, CircuitFormat::Dot);
+        assert_eq!(dot.matches("penwidth=3").count(), 1);
+    }
+}
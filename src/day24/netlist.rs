@@ -0,0 +1,414 @@
+//! A gate level circuit that simulates 64 input vectors at once.
+//!
+//! Wires are interned to ids and gates are kept in topological order, so a
+//! simulation is one pass over the gates.  Each wire holds a `u64` whose bits
+//! are 64 independent lanes, one per input vector.
+//!
+//! Wires named `x`, `y` or `z` followed by a bit number form buses of any
+//! width, with bit 0 the least significant.
+
+use super::{Op, Operation};
+use crate::Result;
+use std::collections::HashMap;
+
+/// A wire's index in the netlist.
+pub type WireId = usize;
+
+/// Lanes evaluated per pass.
+pub const LANES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bus {
+    X,
+    Y,
+    Z,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 3] = [Bus::X, Bus::Y, Bus::Z];
+
+    pub fn prefix(&self) -> char {
+        match self {
+            Bus::X => 'x',
+            Bus::Y => 'y',
+            Bus::Z => 'z',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Gate {
+    pub op: Op,
+    pub a: WireId,
+    pub b: WireId,
+    pub out: WireId,
+}
+
+impl Gate {
+    fn eval(&self, lanes: &[u64]) -> u64 {
+        let (a, b) = (lanes[self.a], lanes[self.b]);
+        match self.op {
+            Op::AND => a & b,
+            Op::OR => a | b,
+            Op::XOR => a ^ b,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Netlist<'a> {
+    names: Vec<&'a str>,
+    ids: HashMap<&'a str, WireId>,
+    /// Gates in topological order.
+    gates: Vec<Gate>,
+    /// The gate driving each wire, None for inputs.
+    driver: Vec<Option<usize>>,
+    /// Wires of each bus, by bit.
+    buses: [Vec<WireId>; 3],
+}
+
+impl<'a> Netlist<'a> {
+    /// Build a netlist from its gates.  `inputs` names extra wires that no
+    /// gate uses, so they can still be set.
+    ///
+    /// Fails if a wire has two drivers, the gates form a cycle, or a bus is
+    /// missing a bit.
+    pub fn new(
+        inputs: impl IntoIterator<Item = &'a str>,
+        operations: impl IntoIterator<Item = Operation<'a>>,
+    ) -> Result<Self> {
+        let mut names = vec![];
+        let mut ids = HashMap::new();
+        let mut intern = |name: &'a str| {
+            *ids.entry(name).or_insert_with(|| {
+                names.push(name);
+                names.len() - 1
+            })
+        };
+        for name in inputs {
+            intern(name);
+        }
+        let gates = operations
+            .into_iter()
+            .map(|op| Gate {
+                op: op.op,
+                a: intern(op.a),
+                b: intern(op.b),
+                out: intern(op.dest),
+            })
+            .collect::<Vec<_>>();
+
+        let mut buses: [Vec<WireId>; 3] = Default::default();
+        for (bus, wires) in Bus::ALL.iter().zip(buses.iter_mut()) {
+            let mut bits = names
+                .iter()
+                .enumerate()
+                .filter_map(|(id, name)| Some((bus_bit(name, bus.prefix())?, id)))
+                .collect::<Vec<_>>();
+            bits.sort_unstable();
+            if let Some((i, _)) = bits.iter().enumerate().find(|(i, (bit, _))| i != bit) {
+                anyhow::bail!("bus {} is missing bit {i}", bus.prefix());
+            }
+            *wires = bits.into_iter().map(|(_, id)| id).collect();
+        }
+
+        let mut netlist = Netlist {
+            names,
+            ids,
+            gates,
+            driver: vec![],
+            buses,
+        };
+        netlist.sort()?;
+        Ok(netlist)
+    }
+
+    /// Put the gates in topological order and index their outputs.
+    fn sort(&mut self) -> Result<()> {
+        let wires = self.names.len();
+        let mut driver = vec![None; wires];
+        for (i, gate) in self.gates.iter().enumerate() {
+            if driver[gate.out].replace(i).is_some() {
+                anyhow::bail!("wire {} has more than one driver", self.names[gate.out]);
+            }
+        }
+
+        // Kahn's algorithm, with the gates reading each wire in a flat list.
+        let mut start = vec![0; wires + 1];
+        for gate in &self.gates {
+            start[gate.a + 1] += 1;
+            start[gate.b + 1] += 1;
+        }
+        for i in 0..wires {
+            start[i + 1] += start[i];
+        }
+        let mut fill = start.clone();
+        let mut readers = vec![0; start[wires]];
+        let mut waiting = vec![0u8; self.gates.len()];
+        for (i, gate) in self.gates.iter().enumerate() {
+            for wire in [gate.a, gate.b] {
+                readers[fill[wire]] = i;
+                fill[wire] += 1;
+                if driver[wire].is_some() {
+                    waiting[i] += 1;
+                }
+            }
+        }
+        let mut ready = (0..self.gates.len())
+            .filter(|&i| waiting[i] == 0)
+            .collect::<Vec<_>>();
+        let mut order = Vec::with_capacity(self.gates.len());
+        while let Some(i) = ready.pop() {
+            order.push(i);
+            let out = self.gates[i].out;
+            for &reader in &readers[start[out]..start[out + 1]] {
+                waiting[reader] -= 1;
+                if waiting[reader] == 0 {
+                    ready.push(reader);
+                }
+            }
+        }
+        if order.len() < self.gates.len() {
+            let stuck = (0..self.gates.len())
+                .filter(|&i| waiting[i] > 0)
+                .map(|i| self.names[self.gates[i].out])
+                .take(5)
+                .collect::<Vec<_>>();
+            anyhow::bail!("gates form a cycle through {}", stuck.join(", "));
+        }
+
+        self.gates = order.into_iter().map(|i| self.gates[i]).collect();
+        for (i, gate) in self.gates.iter().enumerate() {
+            driver[gate.out] = Some(i);
+        }
+        self.driver = driver;
+        Ok(())
+    }
+
+    /// A copy with the outputs of each pair of wires' drivers exchanged.
+    pub fn swapped(&self, pairs: &[(WireId, WireId)]) -> Result<Self> {
+        let mut netlist = self.clone();
+        for &(a, b) in pairs {
+            let (Some(ga), Some(gb)) = (netlist.driver[a], netlist.driver[b]) else {
+                anyhow::bail!(
+                    "can only swap gate outputs, not {} and {}",
+                    self.names[a],
+                    self.names[b]
+                );
+            };
+            netlist.gates[ga].out = b;
+            netlist.gates[gb].out = a;
+            netlist.driver[a] = Some(gb);
+            netlist.driver[b] = Some(ga);
+        }
+        netlist.sort()?;
+        Ok(netlist)
+    }
+
+    pub fn wire_count(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn id(&self, name: &str) -> Option<WireId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: WireId) -> &'a str {
+        self.names[id]
+    }
+
+    /// Gates in topological order.
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    /// The gate driving a wire, None for inputs.
+    pub fn driver(&self, wire: WireId) -> Option<&Gate> {
+        self.driver[wire].map(|i| &self.gates[i])
+    }
+
+    /// Wires of a bus, by bit.
+    pub fn bus(&self, bus: Bus) -> &[WireId] {
+        &self.buses[bus as usize]
+    }
+
+    /// The highest `x` or `y` bit each wire depends on, which is the adder
+    /// stage its gate belongs to.  None for wires that depend on neither.
+    pub fn stages(&self) -> Vec<Option<usize>> {
+        let mut stage = vec![None; self.names.len()];
+        for bus in [Bus::X, Bus::Y] {
+            for (i, &wire) in self.bus(bus).iter().enumerate() {
+                stage[wire] = Some(i);
+            }
+        }
+        for gate in &self.gates {
+            stage[gate.out] = stage[gate.a].max(stage[gate.b]);
+        }
+        stage
+    }
+
+    /// Zeroed lanes for every wire.
+    pub fn lanes(&self) -> Vec<u64> {
+        vec![0; self.names.len()]
+    }
+
+    /// Evaluate every gate over `lanes`, which must have the inputs set.
+    pub fn eval(&self, lanes: &mut [u64]) {
+        for gate in &self.gates {
+            lanes[gate.out] = gate.eval(lanes);
+        }
+    }
+
+    /// Set a bus to up to 64 values, one per lane.  Buses wider than 64 bits
+    /// have their high bits cleared.
+    pub fn set_bus(&self, lanes: &mut [u64], bus: Bus, values: &[u64]) -> Result<()> {
+        if values.len() > LANES {
+            anyhow::bail!("{} values don't fit in {LANES} lanes", values.len());
+        }
+        for (bit, &wire) in self.bus(bus).iter().enumerate() {
+            lanes[wire] = values
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| bit < 64 && (*v >> bit) & 1 == 1)
+                .fold(0, |acc, (lane, _)| acc | 1 << lane);
+        }
+        Ok(())
+    }
+
+    /// The value of a bus in each lane, up to its first 64 bits.
+    pub fn get_bus(&self, lanes: &[u64], bus: Bus) -> [u64; LANES] {
+        let mut values = [0; LANES];
+        for (bit, &wire) in self.bus(bus).iter().enumerate().take(64) {
+            for (lane, value) in values.iter_mut().enumerate() {
+                *value |= ((lanes[wire] >> lane) & 1) << bit;
+            }
+        }
+        values
+    }
+
+    /// `z` for each pair of `x` and `y` values, up to 64 pairs per pass.
+    pub fn run(&self, x: &[u64], y: &[u64]) -> Result<Vec<u64>> {
+        if x.len() != y.len() {
+            anyhow::bail!("{} x values but {} y values", x.len(), y.len());
+        }
+        let mut lanes = self.lanes();
+        let mut z = Vec::with_capacity(x.len());
+        for (x, y) in x.chunks(LANES).zip(y.chunks(LANES)) {
+            lanes.fill(0);
+            self.set_bus(&mut lanes, Bus::X, x)?;
+            self.set_bus(&mut lanes, Bus::Y, y)?;
+            self.eval(&mut lanes);
+            z.extend_from_slice(&self.get_bus(&lanes, Bus::Z)[..x.len()]);
+        }
+        Ok(z)
+    }
+}
+
+/// The bit number of a bus wire like `x07`.
+fn bus_bit(name: &str, prefix: char) -> Option<usize> {
+    let digits = name.strip_prefix(prefix)?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestRng;
+
+    fn op<'a>(a: &'a str, op: Op, b: &'a str, dest: &'a str) -> Operation<'a> {
+        Operation { op, a, b, dest }
+    }
+
+    /// A ripple carry adder with gates in reverse order.
+    fn adder(bits: usize) -> Vec<(Op, String, String, String)> {
+        let mut gates = vec![];
+        let mut carry: Option<String> = None;
+        for i in 0..bits {
+            let (x, y) = (format!("x{i:02}"), format!("y{i:02}"));
+            let sum = format!("s{i:02}");
+            gates.push((Op::XOR, x.clone(), y.clone(), sum.clone()));
+            let gen = format!("g{i:02}");
+            gates.push((Op::AND, x, y, gen.clone()));
+            match carry {
+                None => {
+                    let n = gates.len();
+                    gates[n - 1].3 = format!("c{i:02}");
+                    gates[n - 2].3 = format!("z{i:02}");
+                    carry = Some(format!("c{i:02}"));
+                }
+                Some(c) => {
+                    gates.push((Op::XOR, c.clone(), sum.clone(), format!("z{i:02}")));
+                    let prop = format!("p{i:02}");
+                    gates.push((Op::AND, c, sum, prop.clone()));
+                    let next = if i + 1 == bits {
+                        format!("z{bits:02}")
+                    } else {
+                        format!("c{i:02}")
+                    };
+                    gates.push((Op::OR, gen, prop, next.clone()));
+                    carry = Some(next);
+                }
+            }
+        }
+        gates.reverse();
+        gates
+    }
+
+    fn netlist(gates: &[(Op, String, String, String)]) -> Result<Netlist<'_>> {
+        Netlist::new([], gates.iter().map(|(o, a, b, d)| op(a, *o, b, d)))
+    }
+
+    #[test]
+    fn adds() {
+        let gates = adder(40);
+        let netlist = netlist(&gates).unwrap();
+        assert_eq!(netlist.bus(Bus::X).len(), 40);
+        assert_eq!(netlist.bus(Bus::Z).len(), 41);
+        let mut rng = TestRng(43);
+        let mask = (1 << 40) - 1;
+        let x = (0..100).map(|_| rng.next() & mask).collect::<Vec<_>>();
+        let y = (0..100).map(|_| rng.next() & mask).collect::<Vec<_>>();
+        let z = netlist.run(&x, &y).unwrap();
+        for i in 0..100 {
+            assert_eq!(z[i], x[i] + y[i]);
+        }
+    }
+
+    #[test]
+    fn swaps_and_errors() {
+        let gates = adder(4);
+        let netlist = netlist(&gates).unwrap();
+        let (z1, p1) = (netlist.id("z01").unwrap(), netlist.id("p01").unwrap());
+        let swapped = netlist.swapped(&[(z1, p1)]).unwrap();
+        assert_eq!(swapped.driver(z1).unwrap().op, Op::AND);
+        assert_ne!(swapped.run(&[1], &[1]).unwrap(), [2]);
+        assert_eq!(
+            swapped
+                .swapped(&[(p1, z1)])
+                .unwrap()
+                .run(&[1], &[1])
+                .unwrap(),
+            [2]
+        );
+        let x0 = netlist.id("x00").unwrap();
+        assert!(netlist.swapped(&[(x0, z1)]).is_err());
+
+        // Feeding a carry back into its own bit is a cycle.
+        let c1 = netlist.id("c01").unwrap();
+        let err = netlist
+            .swapped(&[(c1, netlist.id("s01").unwrap())])
+            .unwrap_err();
+        assert!(err.to_string().contains("cycle"), "{err}");
+
+        let two_drivers = [
+            op("x00", Op::AND, "y00", "z00"),
+            op("x00", Op::OR, "y00", "z00"),
+        ];
+        assert!(Netlist::new([], two_drivers).is_err());
+        let gap = [op("x00", Op::AND, "x02", "z00")];
+        assert!(Netlist::new([], gap).is_err());
+    }
+}
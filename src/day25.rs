@@ -1,21 +1,18 @@
 use crate::Result;
 use anyhow::Context as _;
 use aoc_runner_derive::aoc;
+use fit::FitIndex;
+use schematic::{Kind, Schematic};
 use std::fmt::Display;
 
 pub const DAY: u32 = 25;
 
-fn pins_fit<'a>(lock: impl Iterator<Item = &'a u8>, key: impl Iterator<Item = &'a u8>) -> bool {
-    lock.zip(key).all(|(l, k)| l + k <= 5)
-}
+pub mod fit;
+pub mod schematic;
 
 fn solve_part1_impl(input: &Data) -> Result<usize> {
-    let lock_key_combinations = input
-        .locks
-        .iter()
-        .flat_map(|l| input.keys.iter().map(move |k| (l, k)));
-    let fits = lock_key_combinations.filter(|(l, k)| pins_fit(l.iter(), k.iter()));
-    Ok(fits.count())
+    let index = FitIndex::new(&input.keys)?;
+    Ok(index.count_all(&input.locks))
 }
 
 fn solve_part2_impl(_input: &Data) -> Result<usize> {
@@ -37,41 +34,31 @@ fn solve_part2(input: &str) -> Result<usize> {
     solve_part2_impl(&input)
 }
 
-type Profile = Vec<u8>;
-
 /// Problem input
 #[derive(Debug)]
 struct Data {
-    // XXX: Change this to the actual data structure
-    keys: Vec<Profile>,
-    locks: Vec<Profile>,
+    keys: Vec<Schematic>,
+    locks: Vec<Schematic>,
 }
 impl Data {
     fn parse(s: &str) -> Result<Self> {
-        let profiles = s.split("\n\n");
-
         let mut keys = Vec::new();
         let mut locks = Vec::new();
-
-        for profile in profiles {
-            let mut profile = profile.lines();
-            let first_line = profile
-                .next()
-                .ok_or_else(|| anyhow::anyhow!("empty profile"))?;
-
-            let mut code = vec![0u8; first_line.len()];
-
-            for line in profile.take(5) {
-                for (l, c) in line.chars().zip(code.iter_mut()) {
-                    if l == '#' {
-                        *c += 1;
-                    }
-                }
-            }
-            if first_line.starts_with('#') {
-                locks.push(code);
-            } else {
-                keys.push(code);
+        let mut size = None;
+
+        for (i, block) in s.trim().split("\n\n").enumerate() {
+            let schematic = Schematic::parse(block).with_context(|| format!("schematic {i}"))?;
+            let this = (schematic.width(), schematic.height());
+            let (width, height) = *size.get_or_insert(this);
+            anyhow::ensure!(
+                this == (width, height),
+                "schematic {i} is {}x{}, expected {width}x{height}",
+                this.0,
+                this.1
+            );
+            match schematic.kind() {
+                Kind::Lock => locks.push(schematic),
+                Kind::Key => keys.push(schematic),
             }
         }
 
@@ -79,6 +66,21 @@ impl Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -0,0 +1,145 @@
+//! Count the keys that fit each lock without trying every pair.
+//!
+//! For every column and every pin height the index keeps the set of keys
+//! whose pin in that column is no taller, as a bitset over the keys.  Those
+//! sets nest, so each one is the previous height's set plus the keys of
+//! exactly that height, a prefix sum over heights.  A lock leaves room for
+//! `space - pin` in each column, and the keys that fit it are the
+//! intersection of one set per column.
+
+use super::schematic::{Kind, Schematic};
+use crate::Result;
+use anyhow::ensure;
+
+#[derive(Debug, Clone)]
+pub struct FitIndex {
+    width: usize,
+    space: usize,
+    keys: usize,
+    /// `at_most[col][h]` holds the keys with a pin of at most `h` in `col`.
+    at_most: Vec<Vec<Vec<u64>>>,
+}
+
+impl FitIndex {
+    /// Index `keys`, which must all be keys of the same size.
+    pub fn new(keys: &[Schematic]) -> Result<Self> {
+        let (width, space) = keys.first().map_or((0, 0), |k| (k.width(), k.space()));
+        for (i, key) in keys.iter().enumerate() {
+            ensure!(key.kind() == Kind::Key, "schematic {i} is not a key");
+            ensure!(
+                key.width() == width && key.space() == space,
+                "key {i} is {}x{}, expected {width}x{space}",
+                key.width(),
+                key.space()
+            );
+        }
+
+        let words = keys.len().div_ceil(64);
+        let mut at_most = vec![vec![vec![0u64; words]; space + 1]; width];
+        for (i, key) in keys.iter().enumerate() {
+            for (col, &h) in key.heights().iter().enumerate() {
+                at_most[col][h as usize][i / 64] |= 1 << (i % 64);
+            }
+        }
+        for column in &mut at_most {
+            for h in 1..=space {
+                let (lower, rest) = column.split_at_mut(h);
+                for (word, below) in rest[0].iter_mut().zip(&lower[h - 1]) {
+                    *word |= below;
+                }
+            }
+        }
+        Ok(FitIndex {
+            width,
+            space,
+            keys: keys.len(),
+            at_most,
+        })
+    }
+
+    /// The keys fitting `lock` as a bitset, or None if it is a different
+    /// size from the keys.
+    fn fitting(&self, lock: &Schematic) -> Option<Vec<u64>> {
+        if lock.kind() != Kind::Lock
+            || self.keys == 0
+            || lock.width() != self.width
+            || lock.space() != self.space
+        {
+            return None;
+        }
+        let mut fit = vec![u64::MAX; self.keys.div_ceil(64)];
+        for (column, &pin) in self.at_most.iter().zip(lock.heights()) {
+            for (word, allowed) in fit.iter_mut().zip(&column[self.space - pin as usize]) {
+                *word &= allowed;
+            }
+        }
+        Some(fit)
+    }
+
+    /// How many of the keys fit `lock`.
+    pub fn count(&self, lock: &Schematic) -> usize {
+        self.fitting(lock).map_or(0, |fit| {
+            fit.iter().map(|word| word.count_ones() as usize).sum()
+        })
+    }
+
+    /// Indices of the keys that fit `lock`, in order.
+    pub fn keys_fitting(&self, lock: &Schematic) -> Vec<usize> {
+        let Some(fit) = self.fitting(lock) else {
+            return vec![];
+        };
+        (0..self.keys)
+            .filter(|i| fit[i / 64] & (1 << (i % 64)) != 0)
+            .collect()
+    }
+
+    /// Fitting lock and key pairs over all of `locks`.
+    pub fn count_all(&self, locks: &[Schematic]) -> usize {
+        locks.iter().map(|lock| self.count(lock)).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day25::Data, test_data, TestRng};
+
+    #[test]
+    fn example() {
+        let data = Data::parse(&test_data(super::super::DAY).unwrap()).unwrap();
+        let index = FitIndex::new(&data.keys).unwrap();
+        let fitting = data
+            .locks
+            .iter()
+            .map(|lock| index.keys_fitting(lock))
+            .collect::<Vec<_>>();
+        assert_eq!(fitting, [vec![2], vec![1, 2]]);
+        assert!(FitIndex::new(&data.locks).is_err());
+    }
+
+    #[test]
+    fn matches_pairwise() {
+        let mut rng = TestRng(25);
+        let mut random = |kind, n| {
+            (0..n)
+                .map(|_| {
+                    let heights = (0..4).map(|_| rng.below(4) as u8).collect();
+                    Schematic::new(kind, 3, heights).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+        // Enough keys to span several bitset words.
+        let keys = random(Kind::Key, 150);
+        let locks = random(Kind::Lock, 40);
+        let index = FitIndex::new(&keys).unwrap();
+        for lock in &locks {
+            let expected = (0..keys.len())
+                .filter(|&i| lock.fits(&keys[i]))
+                .collect::<Vec<_>>();
+            assert_eq!(index.keys_fitting(lock), expected);
+            assert_eq!(index.count(lock), expected.len());
+        }
+        let wide = Schematic::new(Kind::Lock, 3, vec![0; 5]).unwrap();
+        assert_eq!(index.count(&wide), 0);
+    }
+}
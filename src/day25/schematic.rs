@@ -0,0 +1,181 @@
+//! One lock or key drawn as rows of `#` and `.`.
+//!
+//! A lock has a full top row and pins hanging down from it; a key has a full
+//! bottom row and pins standing up from it.  The rows between are the pin
+//! space, and a pin's height is how many of them it fills.
+
+use crate::Result;
+use anyhow::{bail, ensure};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Lock,
+    Key,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Schematic {
+    kind: Kind,
+    /// Rows between the top and bottom rows.
+    space: usize,
+    heights: Vec<u8>,
+}
+
+impl Schematic {
+    pub fn new(kind: Kind, space: usize, heights: Vec<u8>) -> Result<Self> {
+        ensure!(!heights.is_empty(), "schematic has no columns");
+        ensure!(
+            space <= u8::MAX as usize,
+            "pin space of {space} is too tall"
+        );
+        if let Some(h) = heights.iter().find(|&&h| h as usize > space) {
+            bail!("pin of height {h} does not fit in {space} rows");
+        }
+        Ok(Schematic {
+            kind,
+            space,
+            heights,
+        })
+    }
+
+    /// Read a drawing, inferring its size.  Every row must be as wide as
+    /// the first, and every column one unbroken pin.
+    pub fn parse(s: &str) -> Result<Self> {
+        let rows = s.lines().map(str::as_bytes).collect::<Vec<_>>();
+        ensure!(rows.len() >= 2, "schematic needs a top and a bottom row");
+        let width = rows[0].len();
+        for (i, row) in rows.iter().enumerate() {
+            ensure!(
+                row.len() == width,
+                "row {i} is {} wide, expected {width}",
+                row.len()
+            );
+            if let Some(c) = row.iter().find(|c| !matches!(c, b'#' | b'.')) {
+                bail!("unexpected {:?} in row {i}", *c as char);
+            }
+        }
+        let full = |row: &[u8]| row.iter().all(|&c| c == b'#');
+        let empty = |row: &[u8]| row.iter().all(|&c| c == b'.');
+        let (first, last) = (rows[0], rows[rows.len() - 1]);
+        let kind = if full(first) && empty(last) {
+            Kind::Lock
+        } else if empty(first) && full(last) {
+            Kind::Key
+        } else {
+            bail!("schematic is neither a lock nor a key");
+        };
+
+        let space = rows.len() - 2;
+        let heights = (0..width)
+            .map(|col| {
+                let column = rows[1..=space].iter().map(|row| row[col] == b'#');
+                let filled = column.clone().filter(|&pin| pin).count();
+                // A lock's pin fills the top rows, a key's the bottom ones.
+                let expected = (0..space).map(|row| match kind {
+                    Kind::Lock => row < filled,
+                    Kind::Key => row >= space - filled,
+                });
+                ensure!(column.eq(expected), "column {col} is broken");
+                Ok(filled as u8)
+            })
+            .collect::<Result<_>>()?;
+        Schematic::new(kind, space, heights)
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn heights(&self) -> &[u8] {
+        &self.heights
+    }
+
+    pub fn width(&self) -> usize {
+        self.heights.len()
+    }
+
+    /// Rows in the drawing, including the top and bottom rows.
+    pub fn height(&self) -> usize {
+        self.space + 2
+    }
+
+    /// Rows in the pin space.
+    pub fn space(&self) -> usize {
+        self.space
+    }
+
+    /// Whether `key` fits this lock without pins overlapping.
+    pub fn fits(&self, key: &Schematic) -> bool {
+        self.kind == Kind::Lock
+            && key.kind == Kind::Key
+            && self.space == key.space
+            && self.heights.len() == key.heights.len()
+            && self
+                .heights
+                .iter()
+                .zip(&key.heights)
+                .all(|(&l, &k)| l as usize + k as usize <= self.space)
+    }
+}
+
+impl fmt::Display for Schematic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..self.height() {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            for &h in &self.heights {
+                let filled = match self.kind {
+                    Kind::Lock => row <= h as usize,
+                    Kind::Key => row >= self.height() - 1 - h as usize,
+                };
+                write!(f, "{}", if filled { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCK: &str = "#####\n.####\n.####\n.####\n.#.#.\n.#...\n.....";
+    const KEY: &str = ".....\n#....\n#....\n#...#\n#.#.#\n#.###\n#####";
+
+    #[test]
+    fn round_trip() {
+        let lock = Schematic::parse(LOCK).unwrap();
+        assert_eq!(lock.kind(), Kind::Lock);
+        assert_eq!(lock.heights(), [0, 5, 3, 4, 3]);
+        assert_eq!((lock.width(), lock.height()), (5, 7));
+        assert_eq!(lock.to_string(), LOCK);
+
+        let key = Schematic::parse(KEY).unwrap();
+        assert_eq!(key.kind(), Kind::Key);
+        assert_eq!(key.heights(), [5, 0, 2, 1, 3]);
+        assert_eq!(key.to_string(), KEY);
+        assert!(!lock.fits(&key));
+
+        let small = Schematic::parse("###\n#.#\n...\n...").unwrap();
+        assert_eq!((small.width(), small.space()), (3, 2));
+        assert!(small.fits(&Schematic::parse("...\n...\n.#.\n###").unwrap()));
+        assert!(!small.fits(&Schematic::parse("...\n#..\n#..\n###").unwrap()));
+    }
+
+    #[test]
+    fn invalid() {
+        for (drawing, error) in [
+            ("#####", "top and a bottom"),
+            ("###\n#.\n...", "row 1 is 2 wide"),
+            ("###\n#x#\n...", "unexpected 'x'"),
+            ("###\n#.#\n###", "neither"),
+            ("###\n.##\n#..\n...", "column 0 is broken"),
+        ] {
+            let e = Schematic::parse(drawing).unwrap_err().to_string();
+            assert!(e.contains(error), "{drawing:?}: {e}");
+        }
+        assert!(Schematic::new(Kind::Key, 3, vec![4]).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::Result;
+use crate::{Result, SumResults as _};
 use anyhow::Context as _;
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::{fmt::Display, str::FromStr};
@@ -16,13 +16,18 @@ fn parse(input: &str) -> Result<Data> {
 /// Solution to part 1
 #[aoc(day3, part1)]
 fn solve_part1(input: &Data) -> Result<usize> {
-    Ok(input.numbers.iter().map(|(a, b, _)| a * b).sum())
+    input.numbers.iter().copied().map(product).sum_results()
 }
 
 /// Solution to part 2
 #[aoc(day3, part2)]
 fn solve_part2(input: &Data) -> Result<usize> {
-    Ok(input.numbers_with_dos().map(|(a, b, _)| a * b).sum())
+    input.numbers_with_dos().map(product).sum_results()
+}
+
+fn product((a, b, _): (usize, usize, usize)) -> Result<usize> {
+    a.checked_mul(b)
+        .ok_or_else(|| anyhow::anyhow!("mul({a},{b}) overflowed"))
 }
 
 /// Problem input
@@ -65,20 +70,19 @@ impl FromStr for Data {
 
     fn from_str(s: &str) -> Result<Self> {
         // regex that looks for many statements of mul(##,##)
-        let r = regex::Regex::new(r"mul\((\d+),(\d+)\)").unwrap();
-        let doit = regex::Regex::new(r"do()").unwrap();
-        let dont = regex::Regex::new(r"don't()").unwrap();
+        let r = regex::Regex::new(r"mul\((\d+),(\d+)\)")?;
+        let doit = regex::Regex::new(r"do()")?;
+        let dont = regex::Regex::new(r"don't()")?;
         let numbers = r
             .find_iter(s)
             .map(|f| {
-                let c = r.captures(f.as_str()).unwrap();
-                (
-                    c.get(1).unwrap().as_str().parse().unwrap(),
-                    c.get(2).unwrap().as_str().parse().unwrap(),
-                    f.start(),
-                )
+                let c = r
+                    .captures(f.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("no numbers in {:?}", f.as_str()))?;
+                let (_, [a, b]) = c.extract();
+                Ok((a.parse()?, b.parse()?, f.start()))
             })
-            .collect();
+            .collect::<Result<_>>()?;
         let dos = doit.find_iter(s).map(|f| f.start()).collect();
 
         let donts = dont.find_iter(s).map(|f| f.start()).collect();
@@ -91,6 +95,21 @@ impl FromStr for Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(&parse(input)?)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(&parse(input)?)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(&parse(input).unwrap()).unwrap()
@@ -30,19 +30,24 @@ fn solve_part1(input: &Data) -> Result<usize> {
 /// Solution to part 2
 #[aoc(day5, part2)]
 fn solve_part2(input: &Data) -> Result<usize> {
-    Ok(input
+    input
         .updates
         .iter()
         .filter(|update| !input.order_rules.as_slice().validate(update))
         .map(|update| {
             let mut update = update.clone();
+            // Every swap puts one more pair in order, so rules that keep
+            // swapping past that must contradict each other.
+            let mut passes = update.len() * update.len() / 2;
             while input.order_rules.as_slice().fix(&mut update) {
                 // Keep fixing until we can't fix anymore.
+                passes = passes
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow::anyhow!("rules never settle on {update:?}"))?;
             }
-            update
+            Ok(update[update.len() / 2])
         })
-        .map(|update| update[update.len() / 2])
-        .sum())
+        .sum()
 }
 
 type PageNumber = usize;
@@ -187,6 +192,21 @@ impl FromStr for Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(&parse(input)?)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(&parse(input)?)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(&parse(input).unwrap()).unwrap()
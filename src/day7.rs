@@ -1,4 +1,4 @@
-use crate::Result;
+use crate::{CheckedSum as _, Result};
 use anyhow::Context as _;
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools as _;
@@ -19,7 +19,7 @@ fn parse(input: &str) -> Result<Data> {
 #[aoc(day7, part1)]
 fn solve_part1(input: &Data) -> Result<i64> {
     const OPERATORS: [Operators; 2] = [Operators::Add, Operators::Multiply];
-    Ok(input
+    input
         .equations
         .iter()
         .filter(|e| {
@@ -27,22 +27,24 @@ fn solve_part1(input: &Data) -> Result<i64> {
             count > 0
         })
         .map(|e| e.result)
-        .sum())
+        .checked_sum()
+        .ok_or_else(|| anyhow::anyhow!("sum overflowed"))
 }
 
 /// Solution to part 2
 #[aoc(day7, part2)]
 fn solve_part2(input: &Data) -> Result<i64> {
     const OPERATORS: [Operators; 3] = [Operators::Add, Operators::Multiply, Operators::Concat];
-    Ok(input
+    input
         .equations
         .par_iter()
         .filter(|e| {
             let count = valid_equation(e, &OPERATORS);
             count > 0
         })
-        .map(|e| e.result)
-        .sum())
+        .map(|e| Some(e.result))
+        .try_reduce(|| 0, i64::checked_add)
+        .ok_or_else(|| anyhow::anyhow!("sum overflowed"))
 }
 
 #[derive(Debug)]
@@ -62,26 +64,16 @@ fn valid_equation(e: &Equation, operators: &[Operators]) -> usize {
         e.terms.len() - 1,
         combinations.clone().collect::<Vec<_>>()
     );
-    let valid_combinations = combinations.map(|combo| {
-        let mut combo = combo.into_iter();
-        let result = e.terms.iter().fold(None, |acc, term| match (acc, term) {
-            (None, _) => Some(*term),
-            (Some(acc), term) => {
-                let operator = combo.next().unwrap();
-
-                match operator {
-                    Operators::Add => Some(acc + term),
-                    Operators::Multiply => Some(acc * term),
-                    Operators::Concat => {
-                        let left = acc.to_string();
-                        let right = term.to_string();
-                        let result = format!("{}{}", left, right).parse().unwrap();
-                        Some(result)
-                    }
-                }
-            }
-        });
-        result.unwrap()
+    // A combination that overflows can't add up to the result.
+    let valid_combinations = combinations.filter_map(|combo| {
+        let (first, rest) = e.terms.split_first()?;
+        rest.iter()
+            .zip(combo)
+            .try_fold(*first, |acc, (&term, operator)| match operator {
+                Operators::Add => acc.checked_add(term),
+                Operators::Multiply => acc.checked_mul(term),
+                Operators::Concat => format!("{}{}", acc, term).parse().ok(),
+            })
     });
     valid_combinations.filter(|&r| r == e.result).count()
 }
@@ -106,6 +98,7 @@ impl FromStr for Equation {
             .split_whitespace()
             .map(|t| Ok(t.parse()?))
             .collect::<Result<Vec<_>>>()?;
+        anyhow::ensure!(!terms.is_empty(), "No terms");
         Ok(Self { result, terms })
     }
 }
@@ -127,6 +120,21 @@ impl FromStr for Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(&parse(input)?)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(&parse(input)?)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(&parse(input).unwrap()).unwrap()
@@ -58,7 +58,11 @@ fn parse_nodes(s: &str) -> impl Iterator<Item = Result<Node>> + '_ + Clone {
 
 fn parse_maxxy(s: &str) -> Result<glam::IVec2> {
     let max_y = s.lines().count();
-    let max_x = s.lines().next().unwrap().len();
+    let max_x = s
+        .lines()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("empty map"))?
+        .len();
     Ok(glam::IVec2::new(max_x.try_into()?, max_y.try_into()?))
 }
 
@@ -73,6 +77,21 @@ impl FromStr for Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::from_str(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
@@ -145,6 +164,7 @@ where
 #[cfg(test)]
 mod tests {
     use crate::test_data;
+    use proptest::prelude::*;
     use test_log::test;
 
     use super::*;
@@ -164,4 +184,37 @@ mod tests {
         let res = super::pair_combinations([0, 1, 2, 3].into_iter()).collect::<Vec<_>>();
         assert_eq!(res, vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)])
     }
+
+    /// A map of mostly empty cells with antennas on a few frequencies.
+    fn antenna_map() -> impl Strategy<Value = String> {
+        let cell = prop_oneof![8 => Just('.'), 1 => Just('a'), 1 => Just('B'), 1 => Just('0')];
+        (1..16usize, 1..16usize).prop_flat_map(move |(w, h)| {
+            proptest::collection::vec(proptest::collection::vec(cell.clone(), w), h).prop_map(
+                |rows| {
+                    rows.iter()
+                        .map(|row| row.iter().collect::<String>())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                },
+            )
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn no_std_agrees(map in antenna_map()) {
+            let data = Data::from_str(&map).unwrap();
+            let no_std = DataNoStd::new(&map);
+            prop_assert_eq!(
+                day8_impl::solve_part1_impl(&data).unwrap(),
+                day8_impl::solve_part1_impl(&no_std).unwrap()
+            );
+            prop_assert_eq!(
+                day8_impl::solve_part2_impl(&data).unwrap(),
+                day8_impl::solve_part2_impl(&no_std).unwrap()
+            );
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{CheckedSum as _, Result, SumResults as _};
+use crate::{Result, SumResults as _};
 use anyhow::Context as _;
 use aoc_runner_derive::aoc;
 use std::fmt::Display;
@@ -20,7 +20,9 @@ fn solve_part1_impl(input: &Data) -> Result<u64> {
     let len = input.blocks.len();
     let mut blocks = input.blocks.clone();
     let mut forward = 0usize;
-    let mut backward = len - 1;
+    let Some(mut backward) = len.checked_sub(1) else {
+        return Ok(0);
+    };
 
     'outer: loop {
         // Find the next block to move from the rear
@@ -64,9 +66,12 @@ fn solve_part1_impl(input: &Data) -> Result<u64> {
         .iter()
         .enumerate()
         .filter(|(_, c)| c.is_some())
-        .map(|(i, c)| (i as u64 * c.unwrap()))
-        .checked_sum()
-        .ok_or_else(|| anyhow::anyhow!("add overflowed"))
+        .map(|(i, c)| {
+            (i as u64)
+                .checked_mul(c.unwrap())
+                .ok_or_else(|| anyhow::anyhow!("mul overflowed"))
+        })
+        .sum_results()
 }
 
 fn solve_part2_impl(input: &Data) -> Result<u64> {
@@ -158,7 +163,7 @@ struct Data {
 
 impl Data {
     fn parse(s: &str) -> Result<Self> {
-        let mut input = s.chars();
+        let mut input = s.trim_end().chars();
         let mut blocks = vec![];
         let mut index = 0u64;
         while let Some(c) = input.next() {
@@ -180,6 +185,21 @@ impl Data {
     }
 }
 
+/// Parse the input without solving, so parsing can be benchmarked on its own.
+pub fn parse_input(input: &str) -> Result<impl Sized + '_> {
+    Data::parse(input)
+}
+
+/// Part 1 as a string, failing instead of panicking on a bad input.
+pub fn try_part1(input: &str) -> Result<String> {
+    Ok(solve_part1(input)?.to_string())
+}
+
+/// Part 2 as a string, failing instead of panicking on a bad input.
+pub fn try_part2(input: &str) -> Result<String> {
+    Ok(solve_part2(input)?.to_string())
+}
+
 /// codspeed compatible function
 pub fn part1(input: &str) -> impl Display {
     solve_part1(input).unwrap()
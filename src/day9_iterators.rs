@@ -37,7 +37,7 @@ pub fn part1_generator(s: &str) -> Result<impl Iterator<Item = Block> + '_> {
 
     let forward = disk_map_to_blocks(forward_disk_generator(digits.clone())).enumerate();
 
-    let last_id = (s.len() + 1) / 2 - 1;
+    let last_id = s.len().div_ceil(2) - 1;
     let block_len: usize = digits.clone().map(usize::from).sum();
 
     let backward_ids = {
@@ -47,7 +47,7 @@ pub fn part1_generator(s: &str) -> Result<impl Iterator<Item = Block> + '_> {
 
     let mut backward = disk_map_to_blocks(disk_generator(
         digits.rev(),
-        if s.len() % 2 == 0 {
+        if s.len().is_multiple_of(2) {
             DiskMap::Empty(Default::default())
         } else {
             DiskMap::Data(Default::default())
@@ -188,6 +188,7 @@ pub fn blocks_to_string(blocks: impl Iterator<Item = Block>) -> String {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
     use test_log::test;
 
     use crate::test_data;
@@ -232,4 +233,31 @@ mod tests {
     // fn part2_example() {
     //     assert_eq!(solve_part2(&test_data(super::DAY).unwrap()).unwrap(), 2858);
     // }
+
+    /// A disk map like the puzzle's: files of 1 to 9 blocks with gaps of 0
+    /// to 9 between them.
+    fn disk_map() -> impl Strategy<Value = String> {
+        proptest::collection::vec((1..=9u8, 0..=9u8), 1..40).prop_map(|files| {
+            let mut map = files
+                .iter()
+                .flat_map(|(file, gap)| [file, gap])
+                .map(|d| char::from(b'0' + d))
+                .collect::<String>();
+            // The last file has no gap after it.
+            map.pop();
+            map
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn agrees_with_day9(map in disk_map()) {
+            prop_assert_eq!(
+                solve_part1(&map).unwrap().to_string(),
+                crate::day9::part1(&map).to_string()
+            );
+        }
+    }
 }
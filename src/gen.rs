@@ -0,0 +1,845 @@
+//! Seeded generators for puzzle inputs of any size.
+//!
+//! [`generate`] takes a day, a seed and a size and returns input text in
+//! the puzzle's format.  What the size counts differs by day: the side of
+//! the grid for the grid puzzles, the number of lines, machines or
+//! equations for the list puzzles, instructions for day 17 and gates for
+//! day 24.  [`puzzle_size`] gives the size of the real inputs.
+//!
+//! The same seed and size always give the same text.  Inputs are built to
+//! have an answer: the guard in day 6 walks off the map, the maze in day 16
+//! and the track in day 20 connect start and end, day 17's program has a
+//! quine, day 18's exit is open after the first bytes fall and day 24's
+//! adder is fixed by four swaps.
+
+use crate::Result;
+use anyhow::{bail, ensure};
+use std::collections::{HashMap, HashSet};
+
+/// SplitMix64, small and good enough for test data.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A value in `0..n`, or 0 if `n` is 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+
+    /// A value in `lo..=hi`.
+    pub fn between(&mut self, lo: usize, hi: usize) -> usize {
+        lo + self.below(hi - lo + 1)
+    }
+
+    /// True one time in `n`.
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// The size of the real puzzle input for `day`, in that day's units.
+pub fn puzzle_size(day: u32) -> Result<usize> {
+    Ok(match day {
+        1 | 2 => 1000,
+        3 => 700,
+        4 | 12 => 140,
+        5 => 200,
+        6 => 130,
+        7 => 850,
+        8 => 50,
+        9 => 10000,
+        10 => 45,
+        11 => 8,
+        13 => 320,
+        14 => 500,
+        15 => 50,
+        16 | 20 => 141,
+        17 => 8,
+        18 => 71,
+        19 => 400,
+        21 => 5,
+        22 => 2000,
+        23 => 520,
+        24 => 222,
+        25 => 500,
+        _ => bail!("no generator for day {day}"),
+    })
+}
+
+/// Input text for `day`, without a trailing newline as aoc-runner trims it.
+///
+/// `size` is the side of the grid for days 4, 6, 8, 10, 12, 15, 16, 18 and
+/// 20, files for day 9, instructions for day 17 and gates for day 24.  For
+/// the other days it counts lines or records: numbers, reports,
+/// instructions, updates, equations, stones, machines, robots, designs,
+/// codes, buyers, computers or schematics.
+pub fn generate(day: u32, seed: u64, size: usize) -> Result<String> {
+    let generator = match day {
+        1 => day1,
+        2 => day2,
+        3 => day3,
+        4 => day4,
+        5 => day5,
+        6 => day6,
+        7 => day7,
+        8 => day8,
+        9 => day9,
+        10 => day10,
+        11 => day11,
+        12 => day12,
+        13 => day13,
+        14 => day14,
+        15 => day15,
+        16 => day16,
+        17 => day17,
+        18 => day18,
+        19 => day19,
+        20 => day20,
+        21 => day21,
+        22 => day22,
+        23 => day23,
+        24 => day24,
+        25 => day25,
+        _ => bail!("no generator for day {day}"),
+    };
+    Ok(generator(seed, size)?.trim_end().to_string())
+}
+
+/// A `side` by `side` grid with each cell from `cell`.
+fn grid(side: usize, mut cell: impl FnMut(usize, usize) -> char) -> String {
+    (0..side)
+        .map(|y| (0..side).map(|x| cell(x, y)).collect::<String>() + "\n")
+        .collect()
+}
+
+fn render(cells: &[Vec<char>]) -> String {
+    cells
+        .iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+/// A perfect maze in a `side` by `side` grid of `#` and `.`, with passages
+/// on odd coordinates.  `side` is rounded down to an odd number.
+fn maze(rng: &mut Rng, side: usize) -> Vec<Vec<char>> {
+    let side = (side.max(5) - 1) | 1;
+    let mut cells = vec![vec!['#'; side]; side];
+    let mut stack = vec![(1usize, 1usize)];
+    cells[1][1] = '.';
+    while let Some(&(x, y)) = stack.last() {
+        let mut steps = [(2isize, 0isize), (-2, 0), (0, 2), (0, -2)]
+            .into_iter()
+            .filter_map(|(dx, dy)| {
+                let (nx, ny) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+                (nx < side - 1 && ny < side - 1 && cells[ny][nx] == '#').then_some((nx, ny))
+            })
+            .collect::<Vec<_>>();
+        if steps.is_empty() {
+            stack.pop();
+            continue;
+        }
+        rng.shuffle(&mut steps);
+        let (nx, ny) = steps[0];
+        cells[(y + ny) / 2][(x + nx) / 2] = '.';
+        cells[ny][nx] = '.';
+        stack.push((nx, ny));
+    }
+    cells
+}
+
+/// `size` lines of two location ids.
+fn day1(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    // Draw the right list partly from the left so similarity is non-zero.
+    let left = (0..size)
+        .map(|_| rng.between(10000, 99999))
+        .collect::<Vec<_>>();
+    Ok((0..size)
+        .map(|i| {
+            let right = if rng.one_in(3) {
+                rng.pick(&left)
+            } else {
+                rng.between(10000, 99999)
+            };
+            format!("{}   {right}\n", left[i])
+        })
+        .collect())
+}
+
+/// `size` reports of five to eight levels, some safe.
+fn day2(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    Ok((0..size)
+        .map(|_| {
+            let len = rng.between(5, 8);
+            let rising = rng.one_in(2);
+            let mut level = rng.between(10, 80) as isize;
+            let mut levels = vec![level];
+            for _ in 1..len {
+                let step = if rng.one_in(8) {
+                    rng.between(0, 5) as isize
+                } else {
+                    rng.between(1, 3) as isize
+                };
+                level += if rising { step } else { -step };
+                levels.push(level);
+            }
+            let levels = levels.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+            levels.join(" ") + "\n"
+        })
+        .collect())
+}
+
+/// `size` instructions, valid and broken, in corrupted memory.
+fn day3(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let junk = [
+        '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '[', ']', ' ', ',', '?',
+    ];
+    let mut memory = String::new();
+    for i in 0..size {
+        let (a, b) = (rng.between(1, 999), rng.between(1, 999));
+        memory += &match rng.below(10) {
+            0 => "do()".to_string(),
+            1 => "don't()".to_string(),
+            2 => format!("mul({a}, {b})"),
+            3 => format!("mul[{a},{b})"),
+            4 => format!("mul({a},{b}"),
+            _ => format!("mul({a},{b})"),
+        };
+        for _ in 0..rng.below(6) {
+            memory.push(rng.pick(&junk));
+        }
+        if i % 100 == 99 {
+            memory.push('\n');
+        }
+    }
+    Ok(memory + "\n")
+}
+
+/// A word search `size` letters square.
+fn day4(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    Ok(grid(size, |_, _| rng.pick(&['X', 'M', 'A', 'S'])))
+}
+
+/// Ordering rules between pages and `size` updates, some in order.
+fn day5(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let mut pages = (10..100).collect::<Vec<usize>>();
+    rng.shuffle(&mut pages);
+    pages.truncate(49);
+    // Every pair of pages has a rule, so any update has one right order.
+    let mut rules = vec![];
+    for (i, a) in pages.iter().enumerate() {
+        for b in &pages[i + 1..] {
+            rules.push(format!("{a}|{b}\n"));
+        }
+    }
+    rng.shuffle(&mut rules);
+    let updates = (0..size).map(|_| {
+        let len = rng.between(2, 11) * 2 + 1;
+        let mut update = pages.clone();
+        rng.shuffle(&mut update);
+        update.truncate(len);
+        if rng.one_in(2) {
+            update.sort_by_key(|p| pages.iter().position(|q| q == p));
+        }
+        let update = update.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        update.join(",") + "\n"
+    });
+    Ok(rules.concat() + "\n" + &updates.collect::<String>())
+}
+
+/// A lab `size` cells square whose guard walks off the map.
+fn day6(seed: u64, size: usize) -> Result<String> {
+    ensure!(size >= 2, "day 6 needs a lab of at least 2x2");
+    let mut rng = Rng::new(seed);
+    for _ in 0..1000 {
+        let mut cells = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| if rng.one_in(12) { '#' } else { '.' })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let start = (rng.below(size), rng.below(size));
+        cells[start.1][start.0] = '^';
+        if guard_leaves(&cells, start) {
+            return Ok(render(&cells));
+        }
+    }
+    bail!("no lab found where the guard leaves")
+}
+
+fn guard_leaves(cells: &[Vec<char>], start: (usize, usize)) -> bool {
+    let size = cells.len();
+    let (mut pos, mut dir) = (start, (0isize, -1isize));
+    let mut seen = HashSet::new();
+    while seen.insert((pos, dir)) {
+        let next = (
+            pos.0.checked_add_signed(dir.0),
+            pos.1.checked_add_signed(dir.1),
+        );
+        let (Some(x), Some(y)) = next else {
+            return true;
+        };
+        if x >= size || y >= size {
+            return true;
+        }
+        if cells[y][x] == '#' {
+            dir = (-dir.1, dir.0);
+        } else {
+            pos = (x, y);
+        }
+    }
+    false
+}
+
+/// `size` calibration equations, most of them true.
+///
+/// Like the puzzle's, each equation has at most 18 digits of numbers, so
+/// even joining them all fits in an `i64`.
+fn day7(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    Ok((0..size)
+        .map(|_| {
+            let mut numbers = vec![];
+            let mut digits = 0;
+            while numbers.len() < 12 {
+                let n = match rng.below(6) {
+                    0 => rng.between(100, 999),
+                    1 | 2 => rng.between(10, 99),
+                    _ => rng.between(1, 9),
+                };
+                digits += n.to_string().len();
+                if digits > 18 || (numbers.len() >= 3 && rng.one_in(4)) {
+                    break;
+                }
+                numbers.push(n as u64);
+            }
+            let mut total = numbers[0];
+            for &n in &numbers[1..] {
+                total = match rng.below(3) {
+                    0 => total + n,
+                    1 => total * n,
+                    _ => format!("{total}{n}").parse().expect("at most 18 digits"),
+                };
+            }
+            if rng.one_in(3) {
+                total += 1;
+            }
+            let numbers = numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+            format!("{total}: {}\n", numbers.join(" "))
+        })
+        .collect())
+}
+
+/// A roof `size` cells square with antennas on a few frequencies.
+fn day8(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let frequencies = ['a', 'B', 'c', '0', '7', 'Z'];
+    Ok(grid(size, |_, _| {
+        if rng.one_in(25) {
+            rng.pick(&frequencies)
+        } else {
+            '.'
+        }
+    }))
+}
+
+/// A disk map of `size` files.
+fn day9(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let mut map = String::new();
+    for i in 0..size {
+        map.push(char::from(b'0' + rng.between(1, 9) as u8));
+        if i + 1 < size {
+            map.push(char::from(b'0' + rng.between(0, 9) as u8));
+        }
+    }
+    Ok(map)
+}
+
+/// A topographic map `size` cells square of gentle slopes.
+fn day10(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let mut heights = vec![vec![0u8; size]; size];
+    // Each cell is one above or below a neighbour, so trails are common.
+    for y in 0..size {
+        for x in 0..size {
+            let base = match (x, y) {
+                (0, 0) => rng.below(10) as u8,
+                (0, _) => heights[y - 1][x],
+                _ => heights[y][x - 1],
+            };
+            heights[y][x] = match rng.below(3) {
+                0 => base.saturating_sub(1),
+                1 => (base + 1).min(9),
+                _ => rng.below(10) as u8,
+            };
+        }
+    }
+    Ok(grid(size, |x, y| char::from(b'0' + heights[y][x])))
+}
+
+/// `size` stones.
+fn day11(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let stones = (0..size)
+        .map(|_| rng.below(10_000_000).to_string())
+        .collect::<Vec<_>>();
+    Ok(stones.join(" ") + "\n")
+}
+
+/// A garden `size` plots square of blotchy regions.
+fn day12(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let mut plots = vec![vec!['A'; size]; size];
+    for y in 0..size {
+        for x in 0..size {
+            plots[y][x] = match rng.below(4) {
+                0 if x > 0 => plots[y][x - 1],
+                1 if y > 0 => plots[y - 1][x],
+                _ => char::from(b'A' + rng.below(26) as u8),
+            };
+        }
+    }
+    Ok(render(&plots))
+}
+
+/// `size` claw machines, most of them winnable.
+fn day13(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let machines = (0..size).map(|_| {
+        let a = (rng.between(10, 99), rng.between(10, 99));
+        let b = (rng.between(10, 99), rng.between(10, 99));
+        let (pa, pb) = (rng.below(100), rng.below(100));
+        let mut prize = (a.0 * pa + b.0 * pb, a.1 * pa + b.1 * pb);
+        if rng.one_in(3) {
+            prize = (prize.0 + rng.below(50), prize.1 + rng.below(50));
+        }
+        format!(
+            "Button A: X+{}, Y+{}\nButton B: X+{}, Y+{}\nPrize: X={}, Y={}\n",
+            a.0, a.1, b.0, b.1, prize.0, prize.1
+        )
+    });
+    Ok(machines.collect::<Vec<_>>().join("\n"))
+}
+
+/// `size` robots on the puzzle's 101 by 103 floor.
+fn day14(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    Ok((0..size)
+        .map(|_| {
+            let (x, y) = (rng.below(101), rng.below(103));
+            let vx = rng.below(201) as isize - 100;
+            let vy = rng.below(201) as isize - 100;
+            format!("p={x},{y} v={vx},{vy}\n")
+        })
+        .collect())
+}
+
+/// A warehouse `size` cells square and `20 * size` moves.
+fn day15(seed: u64, size: usize) -> Result<String> {
+    ensure!(size >= 3, "day 15 needs a warehouse of at least 3x3");
+    let mut rng = Rng::new(seed);
+    let mut cells = (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    if x == 0 || y == 0 || x == size - 1 || y == size - 1 || rng.one_in(12) {
+                        '#'
+                    } else if rng.one_in(3) {
+                        'O'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let robot = (rng.between(1, size - 2), rng.between(1, size - 2));
+    cells[robot.1][robot.0] = '@';
+    let moves = (0..20 * size)
+        .map(|i| {
+            let step = rng.pick(&['<', '>', '^', 'v']);
+            if i % 70 == 69 {
+                format!("{step}\n")
+            } else {
+                step.to_string()
+            }
+        })
+        .collect::<String>();
+    Ok(render(&cells) + "\n" + &moves + "\n")
+}
+
+/// A maze `size` cells square with loops, start bottom left and end top
+/// right.
+fn day16(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let mut cells = maze(&mut rng, size);
+    let side = cells.len();
+    // Knock out some inner walls so there are several best paths.
+    for (y, row) in cells.iter_mut().enumerate().take(side - 1).skip(1) {
+        for (x, cell) in row.iter_mut().enumerate().take(side - 1).skip(1) {
+            if *cell == '#' && (x + y) % 2 == 1 && rng.one_in(8) {
+                *cell = '.';
+            }
+        }
+    }
+    cells[side - 2][1] = 'S';
+    cells[1][side - 2] = 'E';
+    Ok(render(&cells))
+}
+
+/// A program of `size` instructions that has a quine.
+///
+/// Like the puzzle's, the program takes A's low three bits into B, mixes B
+/// and C, prints B, shifts A and loops.  The quine needs A to have one octal
+/// digit per number printed, and a leading digit that prints the final 0,
+/// so `size` must be between 5 and 10.
+fn day17(seed: u64, size: usize) -> Result<String> {
+    use crate::day17::{quine, vm::Registers};
+    ensure!(
+        (5..=10).contains(&size),
+        "day 17 programs have 5 to 10 instructions"
+    );
+    let mut rng = Rng::new(seed);
+    for _ in 0..10_000 {
+        let mut program = vec![2, 4];
+        let mut c_set = false;
+        for _ in 4..size {
+            let (opcode, operand) = match rng.below(3) {
+                0 => (1, rng.below(8)),
+                1 => {
+                    c_set = true;
+                    (7, 5)
+                }
+                _ if c_set => (4, rng.below(8)),
+                _ => (1, rng.below(8)),
+            };
+            program.extend([opcode as u64, operand as u64]);
+        }
+        program.extend([0, 3, 5, 5, 3, 0]);
+        let registers = Registers { a: 0, b: 0, c: 0 };
+        if quine::find_quine(&program, registers).is_ok() {
+            let program = program.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+            return Ok(format!(
+                "Register A: {}\nRegister B: 0\nRegister C: 0\n\nProgram: {}\n",
+                rng.below(1 << 30),
+                program.join(",")
+            ));
+        }
+    }
+    bail!("no program with a quine found")
+}
+
+/// Bytes falling into a memory space `size` cells square, with a header.
+///
+/// Every cell but the start and exit eventually falls, so the exit is cut
+/// off, and the header's `fall_count` stops halfway to that.
+fn day18(seed: u64, size: usize) -> Result<String> {
+    use crate::day18::{solve_part2_with, Scenario};
+    ensure!(size >= 3, "day 18 needs a memory space of at least 3x3");
+    let mut rng = Rng::new(seed);
+    let mut bytes = (0..size * size)
+        .map(|i| (i % size, i / size))
+        .filter(|&(x, y)| (x, y) != (0, 0) && (x, y) != (size - 1, size - 1))
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut bytes);
+    let body = bytes
+        .iter()
+        .map(|(x, y)| format!("{x},{y}\n"))
+        .collect::<String>();
+    let scenario = Scenario {
+        size: (size, size),
+        fall_count: 0,
+    };
+    let blocking = solve_part2_with(&body, scenario)?;
+    let index = bytes
+        .iter()
+        .position(|(x, y)| format!("{x},{y}") == blocking)
+        .unwrap_or_default();
+    Ok(format!(
+        "size={size}x{size}\nfall_count={}\n\n{body}",
+        index / 2
+    ))
+}
+
+/// Towels and `size` designs, most of them possible.
+fn day19(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let colours = ['w', 'u', 'b', 'r', 'g'];
+    let mut towels = HashSet::new();
+    while towels.len() < 40 {
+        let len = rng.between(1, 6);
+        // Leave out a one-stripe towel so some designs are impossible.
+        let towel = (0..len).map(|_| rng.pick(&colours)).collect::<String>();
+        if towel != "g" {
+            towels.insert(towel);
+        }
+    }
+    let mut towels = towels.into_iter().collect::<Vec<_>>();
+    towels.sort();
+    let designs = (0..size).map(|_| {
+        let mut design = String::new();
+        while design.len() < 40 {
+            design += &towels[rng.below(towels.len())];
+        }
+        if rng.one_in(4) {
+            design.push('g');
+        }
+        design + "\n"
+    });
+    Ok(towels.join(", ") + "\n\n" + &designs.collect::<String>())
+}
+
+/// A single race track winding through a grid `size` cells square.
+fn day20(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let open = maze(&mut rng, size);
+    let side = open.len();
+    // Keep only the maze's one path from start to end.
+    let (start, end) = ((1, side - 2), (side - 2, 1));
+    let mut came_from = HashMap::from([(start, start)]);
+    let mut queue = std::collections::VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        for next in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if open[next.1][next.0] == '.' && !came_from.contains_key(&next) {
+                came_from.insert(next, (x, y));
+                queue.push_back(next);
+            }
+        }
+    }
+    let mut cells = vec![vec!['#'; side]; side];
+    let mut at = end;
+    while at != start {
+        cells[at.1][at.0] = '.';
+        at = came_from[&at];
+    }
+    cells[start.1][start.0] = 'S';
+    cells[end.1][end.0] = 'E';
+    Ok(render(&cells))
+}
+
+/// `size` door codes.
+fn day21(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    Ok((0..size)
+        .map(|_| format!("{:03}A\n", rng.below(1000)))
+        .collect())
+}
+
+/// `size` buyers' initial secret numbers.
+fn day22(seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    Ok((0..size)
+        .map(|_| format!("{}\n", rng.below(1 << 24)))
+        .collect())
+}
+
+/// A network of `size` computers, with one party bigger than the rest.
+fn day23(seed: u64, size: usize) -> Result<String> {
+    ensure!(
+        (4..=676).contains(&size),
+        "day 23 networks have 4 to 676 computers"
+    );
+    let mut rng = Rng::new(seed);
+    let mut names = (0..676)
+        .map(|i| {
+            format!(
+                "{}{}",
+                char::from(b'a' + i as u8 / 26),
+                char::from(b'a' + (i % 26) as u8)
+            )
+        })
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut names);
+    names.truncate(size);
+
+    let mut edges = HashSet::new();
+    let mut connect = |a: usize, b: usize| {
+        if a != b {
+            edges.insert((a.min(b), a.max(b)));
+        }
+    };
+    let party = (size / 4).clamp(3, 13);
+    for a in 0..party {
+        for b in a + 1..party {
+            connect(a, b);
+        }
+    }
+    // Sparse random links elsewhere, which rarely close bigger cliques.
+    for a in 0..size {
+        for _ in 0..(party / 2).max(1) {
+            connect(a, rng.below(size));
+        }
+    }
+    let mut lines = edges
+        .iter()
+        .map(|&(a, b)| format!("{}-{}\n", names[a], names[b]))
+        .collect::<Vec<_>>();
+    lines.sort();
+    rng.shuffle(&mut lines);
+    Ok(lines.concat())
+}
+
+/// A ripple carry adder of about `size` gates with four pairs of outputs
+/// swapped.
+fn day24(seed: u64, size: usize) -> Result<String> {
+    use crate::day24::adder::ripple_carry_input;
+    // A bit of the adder is five gates, and each swap gets its own stretch
+    // of bits so repairs stay independent.
+    let bits = (size + 3) / 5;
+    ensure!(
+        (16..64).contains(&bits),
+        "day 24 adders have 77 to 317 gates"
+    );
+    let mut rng = Rng::new(seed);
+    let stretch = bits / 4;
+    let mut swaps = vec![];
+    for (i, kind) in [0, 1, 2, 3].into_iter().enumerate() {
+        let bit = i * stretch + rng.between(1, stretch - 3);
+        swaps.push(match kind {
+            0 => (format!("z{bit:02}"), format!("p{bit:02}")),
+            1 => (format!("s{bit:02}"), format!("g{bit:02}")),
+            2 => (format!("z{bit:02}"), format!("c{bit:02}")),
+            _ => (format!("c{bit:02}"), format!("g{:02}", bit + 1)),
+        });
+    }
+    rng.shuffle(&mut swaps);
+    let swaps = swaps
+        .iter()
+        .map(|(a, b)| (a.as_str(), b.as_str()))
+        .collect::<Vec<_>>();
+    let text = ripple_carry_input(bits, &swaps);
+    // Give the inputs values, which the generated adder leaves at zero.
+    Ok(text
+        .lines()
+        .map(|line| match line.strip_suffix(": 0") {
+            Some(wire) => format!("{wire}: {}\n", rng.below(2)),
+            None => format!("{line}\n"),
+        })
+        .collect())
+}
+
+/// `size` lock and key schematics, five pins wide and tall.
+fn day25(seed: u64, size: usize) -> Result<String> {
+    use crate::day25::schematic::{Kind, Schematic};
+    let mut rng = Rng::new(seed);
+    let schematics = (0..size)
+        .map(|_| {
+            let kind = if rng.one_in(2) { Kind::Lock } else { Kind::Key };
+            let heights = (0..5).map(|_| rng.below(6) as u8).collect();
+            Ok(Schematic::new(kind, 5, heights)?.to_string() + "\n")
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(schematics.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `f` on generated input, reporting a panic as the day and seed.
+    fn solves(day: u32, seed: u64, size: usize, f: fn(&str) -> String) -> String {
+        let input = generate(day, seed, size).unwrap();
+        std::panic::catch_unwind(|| f(&input))
+            .unwrap_or_else(|_| panic!("day {day} seed {seed} size {size}:\n{input}"))
+    }
+
+    #[test]
+    fn deterministic() {
+        for day in 1..=25 {
+            let size = match day {
+                17 => 6,
+                24 => 100,
+                _ => 12,
+            };
+            assert_eq!(
+                generate(day, 7, size).unwrap(),
+                generate(day, 7, size).unwrap()
+            );
+        }
+        assert!(generate(26, 0, 10).is_err());
+    }
+
+    #[test]
+    fn every_day_solves() {
+        macro_rules! check {
+            ($($day:ident: $size:expr),* $(,)?) => {$(
+                let day = crate::$day::DAY;
+                for seed in 0..3 {
+                    solves(day, seed, $size, |s| crate::$day::part1(s).to_string());
+                    // Day 14's part 2 looks for a picture random robots never draw.
+                    if day != 14 {
+                        solves(day, seed, $size, |s| crate::$day::part2(s).to_string());
+                    }
+                }
+            )*};
+        }
+        // Day 2's parts return their errors instead of panicking.
+        for seed in 0..3 {
+            solves(2, seed, 50, |s| crate::day2::part1(s).unwrap().to_string());
+            solves(2, seed, 50, |s| crate::day2::part2(s).unwrap().to_string());
+        }
+        check!(
+            day1: 50, day3: 50, day4: 20, day5: 20, day6: 20, day7: 8, day8: 20,
+            day9: 50, day10: 20, day11: 2, day12: 20, day13: 20, day14: 20, day15: 12,
+            day16: 21, day17: 8, day18: 9, day19: 20, day20: 21, day21: 5, day22: 20,
+            day23: 40, day24: 80, day25: 20,
+        );
+    }
+
+    #[test]
+    fn sizes() {
+        let lab = day6(1, 30).unwrap();
+        assert_eq!(lab.lines().count(), 30);
+        assert!(lab.lines().all(|line| line.len() == 30));
+        assert_eq!(day7(1, 30).unwrap().lines().count(), 30);
+        for len in 5..=10 {
+            let program = day17(len as u64, len).unwrap();
+            let program = program.rsplit_once(": ").unwrap().1.trim();
+            assert_eq!(program.split(',').count(), 2 * len);
+        }
+        assert!(day17(0, 4).is_err());
+        assert!(day17(0, 11).is_err());
+        let adder = day24(1, 222).unwrap();
+        assert_eq!(adder.lines().filter(|l| l.contains("->")).count(), 222);
+    }
+
+    #[test]
+    fn adder_swaps_are_found() {
+        for seed in 0..2 {
+            let swapped = solves(24, seed, 160, |s| crate::day24::part2(s).to_string());
+            assert_eq!(swapped.split(',').count(), 8);
+        }
+    }
+}
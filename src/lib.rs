@@ -26,6 +26,8 @@ pub mod day7;
 pub mod day8;
 pub mod day9;
 pub mod day9_iterators;
+pub mod gen;
+pub mod search;
 
 pub use anyhow::Result;
 use aoc_runner_derive::aoc_lib;
@@ -35,6 +37,45 @@ type Direction = (isize, isize);
 
 aoc_lib! { year = 2024 }
 
+/// Either part of any day as a string.  A malformed input is an error rather
+/// than a panic, so inputs can be solved in a long running process.
+pub fn solve(day: u32, part: u32, input: &str) -> Result<String> {
+    type Part = fn(&str) -> Result<String>;
+    let (part1, part2): (Part, Part) = match day {
+        1 => (day1::try_part1, day1::try_part2),
+        2 => (day2::try_part1, day2::try_part2),
+        3 => (day3::try_part1, day3::try_part2),
+        4 => (day4::try_part1, day4::try_part2),
+        5 => (day5::try_part1, day5::try_part2),
+        6 => (day6::try_part1, day6::try_part2),
+        7 => (day7::try_part1, day7::try_part2),
+        8 => (day8::try_part1, day8::try_part2),
+        9 => (day9::try_part1, day9::try_part2),
+        10 => (day10::try_part1, day10::try_part2),
+        11 => (day11::try_part1, day11::try_part2),
+        12 => (day12::try_part1, day12::try_part2),
+        13 => (day13::try_part1, day13::try_part2),
+        14 => (day14::try_part1, day14::try_part2),
+        15 => (day15::try_part1, day15::try_part2),
+        16 => (day16::try_part1, day16::try_part2),
+        17 => (day17::try_part1, day17::try_part2),
+        18 => (day18::try_part1, day18::try_part2),
+        19 => (day19::try_part1, day19::try_part2),
+        20 => (day20::try_part1, day20::try_part2),
+        21 => (day21::try_part1, day21::try_part2),
+        22 => (day22::try_part1, day22::try_part2),
+        23 => (day23::try_part1, day23::try_part2),
+        24 => (day24::try_part1, day24::try_part2),
+        25 => (day25::try_part1, day25::try_part2),
+        _ => anyhow::bail!("no solution for day {day}"),
+    };
+    match part {
+        1 => part1(input),
+        2 => part2(input),
+        _ => anyhow::bail!("day {day} has no part {part}"),
+    }
+}
+
 /// A trait for things that can provide a cell reference given a position.
 pub trait GetCell<T> {
     /// Get a reference to the cell at the given position.
@@ -118,6 +159,84 @@ pub fn test_data(day: u32) -> Result<String> {
     ))?)
 }
 
+/// Optional `key=value` settings at the top of an input, ended by a blank line.
+///
+/// Lets an input carry the parameters that differ between the puzzle and its
+/// examples, such as the map size.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Header<'a> {
+    fields: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Header<'a> {
+    /// Split the header off an input.  An input whose first line isn't a
+    /// `key=value` pair has no header.
+    pub fn split(input: &'a str) -> Result<(Header<'a>, &'a str)> {
+        let first = input.lines().next().unwrap_or_default();
+        if !first.contains('=') {
+            return Ok((Header::default(), input));
+        }
+        let (header, rest) = input
+            .split_once("\n\n")
+            .ok_or_else(|| anyhow::anyhow!("header must be followed by a blank line"))?;
+        let fields = header
+            .lines()
+            .map(|line| {
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("invalid header line {line:?}"))?;
+                Ok((key.trim(), value.trim()))
+            })
+            .collect::<Result<_>>()?;
+        Ok((Header { fields }, rest))
+    }
+
+    /// The parsed value of a key, if present.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.fields
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| {
+                value
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("invalid {key} {value:?}: {e}"))
+            })
+            .transpose()
+    }
+
+    /// Fail on any key not in `known`, to catch typos.
+    pub fn ensure_known(&self, known: &[&str]) -> Result<()> {
+        match self.fields.iter().find(|(k, _)| !known.contains(k)) {
+            Some((key, _)) => {
+                anyhow::bail!("unknown header key {key:?}, expected one of {known:?}")
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// A small xorshift generator so tests are repeatable without a rand dependency.
+#[cfg(test)]
+pub(crate) struct TestRng(pub u64);
+#[cfg(test)]
+impl TestRng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    /// A value in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
 // checked_add functions on u16, i16, u32, i32, etc are not defined as a trait.
 // This is our own definition of checked_add that is implemented for a few types used
 // in the solutions.  Other types can be added as needed.
@@ -139,6 +258,11 @@ impl CheckedAdd<usize> for usize {
         self.checked_add(rhs)
     }
 }
+impl CheckedAdd<i64> for i64 {
+    fn checked_add(self, rhs: i64) -> Option<i64> {
+        self.checked_add(rhs)
+    }
+}
 
 pub trait CountResults<T, E> {
     fn count_results(self) -> Result<usize, E>;
@@ -302,3 +426,203 @@ where
 {
     line.chars().map(T::try_from)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    /// Characters the puzzles give meaning to, plus a few they don't.
+    const ALPHABET: &[u8] = b"0123456789#.,:=-+|^v<>@OSEAXYZmul()do'nt[]p xyzabcgrwub\n\n\n";
+
+    /// Integers at the edges of the types inputs are parsed into.
+    const BOUNDARY_INTS: &[&str] = &[
+        "0",
+        "1",
+        "255",
+        "65536",
+        "2147483648",
+        "4294967295",
+        "4294967296",
+        "9223372036854775807",
+        "9223372036854775808",
+        "18446744073709551615",
+        "18446744073709551616",
+    ];
+
+    /// Inputs that once panicked or hung, by day.
+    const REGRESSIONS: &[(u32, &str)] = &[
+        (13, "garbage"),
+        (
+            13,
+            "Button A: X+1, Y+1\nButton B: X+0, Y+0\n\
+             Prize: X=9223372036854775807, Y=9223372036854775807",
+        ),
+        (
+            13,
+            "Button A: X+1, Y+1\nButton B: X+1, Y+2\n\
+             Prize: X=18446744073709551615, Y=18446744073709551615",
+        ),
+        (18, "size=18446744073709551615x18446744073709551615\n\n1,1"),
+        (18, "size=100000x100000\n\n1,1"),
+        (20, "###\n#.#\n#.#S.....E\n###"),
+        (20, "#\n#S......E\n#"),
+        (20, "####\n#S.#\n#..#\n#.........E"),
+        (22, "sequence_length=18446744073709551615\n\n1\n2"),
+    ];
+
+    /// Header values at and just past what each day accepts, by day and key.
+    fn limit_headers() -> Vec<(u32, &'static str, String)> {
+        use day22::market::{MAX_SEQUENCE_LENGTH, MAX_WINDOW};
+        let side = day18::MAX_CELLS.isqrt();
+        let mut headers = vec![
+            (18, "size", format!("{side}x{side}")),
+            (18, "size", format!("{}x{side}", side + 1)),
+            (22, "sequence_length", MAX_SEQUENCE_LENGTH.to_string()),
+            (22, "sequence_length", (MAX_SEQUENCE_LENGTH + 1).to_string()),
+            (22, "window", MAX_WINDOW.to_string()),
+            (22, "window", (MAX_WINDOW + 1).to_string()),
+        ];
+        for n in BOUNDARY_INTS {
+            headers.push((18, "size", format!("{n}x{n}")));
+            headers.push((18, "size", format!("1x{n}")));
+            for (day, key) in [
+                (18, "fall_count"),
+                (20, "min_saving"),
+                (22, "sequence_length"),
+                (22, "window"),
+            ] {
+                headers.push((day, key, n.to_string()));
+            }
+        }
+        headers
+    }
+
+    /// `input` with `key=value` added to its header, which wins over any
+    /// earlier value of the key.
+    fn with_header(input: &str, key: &str, value: &str) -> String {
+        let has_header = input.lines().next().is_some_and(|l| l.contains('='));
+        match input.split_once("\n\n") {
+            Some((header, rest)) if has_header => format!("{header}\n{key}={value}\n\n{rest}"),
+            _ => format!("{key}={value}\n\n{input}"),
+        }
+    }
+
+    /// A copy of `input` with a few random edits.  Besides bytes, the edits
+    /// change the length of a row and swap numbers for boundary integers,
+    /// which byte edits alone rarely get to.
+    fn mutate(input: &str, rng: &mut TestRng) -> String {
+        let mut bytes = input.as_bytes().to_vec();
+        for _ in 0..1 + rng.below(3) {
+            let at = rng.below(bytes.len() as u64 + 1) as usize;
+            let len = (rng.below(8) as usize).min(bytes.len() - at);
+            match rng.below(8) {
+                0 => {
+                    bytes.drain(at..at + len);
+                }
+                1 => {
+                    let copy = bytes[at..at + len].to_vec();
+                    bytes.splice(at..at, copy);
+                }
+                2 => bytes.truncate(at),
+                3 => {
+                    let digits = (0..1 + rng.below(20)).map(|_| b'0' + rng.below(10) as u8);
+                    bytes.splice(at..at, digits.collect::<Vec<_>>());
+                }
+                4 => {
+                    // Lengthen or shorten the row `at` is in.
+                    let end = bytes[at..]
+                        .iter()
+                        .position(|&b| b == b'\n')
+                        .map_or(bytes.len(), |i| at + i);
+                    let start = bytes[..end]
+                        .iter()
+                        .rposition(|&b| b == b'\n')
+                        .map_or(0, |i| i + 1);
+                    let len = 1 + rng.below(3) as usize;
+                    if rng.below(2) == 0 {
+                        bytes.drain(end.saturating_sub(len).max(start)..end);
+                    } else {
+                        let c = if end > start { bytes[end - 1] } else { b'.' };
+                        bytes.splice(end..end, std::iter::repeat_n(c, len));
+                    }
+                }
+                5 => {
+                    // Swap the number at or after `at` for a boundary integer.
+                    let Some(start) = bytes[at..].iter().position(u8::is_ascii_digit) else {
+                        continue;
+                    };
+                    let start = at + start;
+                    let end = bytes[start..]
+                        .iter()
+                        .position(|b| !b.is_ascii_digit())
+                        .map_or(bytes.len(), |i| start + i);
+                    let n = BOUNDARY_INTS[rng.below(BOUNDARY_INTS.len() as u64) as usize];
+                    bytes.splice(start..end, n.bytes());
+                }
+                _ => {
+                    let c = ALPHABET[rng.below(ALPHABET.len() as u64) as usize];
+                    if at < bytes.len() {
+                        bytes[at] = c;
+                    } else {
+                        bytes.push(c);
+                    }
+                }
+            }
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    #[test]
+    fn garbage_never_panics() {
+        let panics = (1..=25u32)
+            .into_par_iter()
+            .flat_map_iter(|day| {
+                let mut rng = TestRng(0x9e37_79b9 + day as u64);
+                let mut corpus = vec![String::new(), "\n".into(), test_data(day).unwrap()];
+                // Generated inputs bring the headers and layouts the
+                // examples lack.  Adders can't be made much smaller.
+                let size = match day {
+                    24 => 80,
+                    _ => gen::puzzle_size(day).unwrap().clamp(5, 10),
+                };
+                corpus.extend((0..2).filter_map(|seed| gen::generate(day, seed, size).ok()));
+                let mut inputs = corpus.clone();
+                inputs.extend(
+                    REGRESSIONS
+                        .iter()
+                        .filter(|(d, _)| *d == day)
+                        .map(|(_, input)| input.to_string()),
+                );
+                for (_, key, value) in limit_headers().into_iter().filter(|(d, ..)| *d == day) {
+                    let base = &corpus[rng.below(corpus.len() as u64) as usize];
+                    inputs.push(with_header(base, key, &value));
+                }
+                for _ in 0..60 {
+                    let base = &corpus[rng.below(corpus.len() as u64) as usize];
+                    inputs.push(mutate(base, &mut rng));
+                }
+                inputs.push(
+                    (0..200)
+                        .map(|_| ALPHABET[rng.below(ALPHABET.len() as u64) as usize] as char)
+                        .collect(),
+                );
+                inputs.into_iter().flat_map(move |input| {
+                    [1, 2].into_iter().filter_map(move |part| {
+                        let solved = catch_unwind(AssertUnwindSafe(|| solve(day, part, &input)));
+                        solved
+                            .is_err()
+                            .then(|| format!("day {day} part {part}: {input:?}"))
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        assert!(
+            panics.is_empty(),
+            "{} panics:\n{}",
+            panics.len(),
+            panics.join("\n")
+        );
+    }
+}
@@ -0,0 +1,579 @@
+//! Shortest path searches over implicit graphs.
+//!
+//! These grew out of day 16's hand written Dijkstra.  Unlike the `pathfinding`
+//! crate's `dijkstra`, the searches here remember every predecessor that reaches
+//! a node at its best cost, so every shortest path can be counted or walked
+//! (what `pathfinding::directed::astar::astar_bag` provides).
+//!
+//! Graphs are described the same way as in `pathfinding`: a start node and a
+//! `successors` function returning `(node, cost)` pairs.
+
+use pathfinding::num_traits::Zero;
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, HashSet},
+    hash::Hash,
+};
+
+/// An entry in the visit queue.
+///
+/// Ordered by cost alone, and reversed so that the `BinaryHeap` max-heap pops
+/// the cheapest visit first.
+struct Visit<C, N> {
+    total_cost: C,
+    node: N,
+}
+impl<C: Ord, N> PartialEq for Visit<C, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.total_cost == other.total_cost
+    }
+}
+impl<C: Ord, N> Eq for Visit<C, N> {}
+impl<C: Ord, N> PartialOrd for Visit<C, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<C: Ord, N> Ord for Visit<C, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.total_cost.cmp(&self.total_cost)
+    }
+}
+
+/// Best cost to a node and every node that reaches it at that cost.
+struct Marking<N, C> {
+    cost: C,
+    prev_nodes: Vec<N>,
+}
+
+/// The result of [`dijkstra_all`]: every shortest path from the start to the
+/// cheapest goals.
+pub struct ShortestPaths<N, C> {
+    start: N,
+    markings: HashMap<N, Marking<N, C>>,
+    /// Nodes in the order they were settled, so predecessors come first.
+    settled: Vec<N>,
+    goals: Vec<N>,
+    cost: C,
+}
+
+impl<N, C> ShortestPaths<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Zero + Ord + Copy,
+{
+    /// Cost of every shortest path.
+    pub fn cost(&self) -> C {
+        self.cost
+    }
+
+    /// Every goal reached at the shortest cost.
+    pub fn goals(&self) -> &[N] {
+        &self.goals
+    }
+
+    fn prev_nodes(&self, node: &N) -> &[N] {
+        self.markings
+            .get(node)
+            .map(|m| m.prev_nodes.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// One shortest path, from the start to a goal inclusive.
+    pub fn path(&self) -> Vec<N> {
+        let mut path = vec![];
+        let mut node = self.goals.first();
+        while let Some(n) = node {
+            path.push(n.clone());
+            node = self.prev_nodes(n).first();
+        }
+        path.reverse();
+        path
+    }
+
+    /// Lazily walk every shortest path, each from the start to a goal inclusive.
+    pub fn paths(&self) -> AllPaths<'_, N, C> {
+        AllPaths {
+            paths: self,
+            goals: self.goals.iter(),
+            stack: vec![],
+        }
+    }
+
+    /// Number of distinct shortest paths, without walking them.
+    pub fn count_paths(&self) -> u128 {
+        let mut counts = HashMap::new();
+        counts.insert(&self.start, 1u128);
+        for node in self.settled.iter() {
+            let count = self
+                .prev_nodes(node)
+                .iter()
+                .map(|p| counts.get(p).copied().unwrap_or(0))
+                .sum::<u128>();
+            counts.entry(node).or_insert(count);
+        }
+        self.goals
+            .iter()
+            .map(|g| counts.get(g).copied().unwrap_or(0))
+            .sum()
+    }
+
+    /// Every node that lies on at least one shortest path.
+    pub fn nodes_on_paths(&self) -> HashSet<N> {
+        let mut seen = HashSet::new();
+        let mut to_visit = self.goals.clone();
+        while let Some(node) = to_visit.pop() {
+            if seen.insert(node.clone()) {
+                to_visit.extend(self.prev_nodes(&node).iter().cloned());
+            }
+        }
+        seen
+    }
+}
+
+/// Iterator over every path of a [`ShortestPaths`].
+pub struct AllPaths<'a, N, C> {
+    paths: &'a ShortestPaths<N, C>,
+    goals: std::slice::Iter<'a, N>,
+    /// The path being walked, from a goal back towards the start, with the
+    /// index of the next predecessor to try at each node.
+    stack: Vec<(&'a N, usize)>,
+}
+
+impl<N, C> Iterator for AllPaths<'_, N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Zero + Ord + Copy,
+{
+    type Item = Vec<N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some((node, next_prev)) = self.stack.last_mut() else {
+                self.stack.push((self.goals.next()?, 0));
+                continue;
+            };
+            let prev_nodes = self.paths.prev_nodes(node);
+            if prev_nodes.is_empty() {
+                // Only the start has no predecessors.
+                let path = self.stack.iter().rev().map(|(n, _)| (*n).clone()).collect();
+                self.stack.pop();
+                return Some(path);
+            }
+            match prev_nodes.get(*next_prev) {
+                Some(prev) => {
+                    *next_prev += 1;
+                    self.stack.push((prev, 0));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Dijkstra's algorithm, keeping every equal cost predecessor.
+///
+/// Stops as soon as everything left in the queue costs more than the cheapest
+/// goal, so every goal reachable at that cost is returned.  Returns None if no
+/// goal can be reached.
+///
+/// Predecessors are only recorded on nodes that haven't been settled yet,
+/// which keeps zero cost cycles from producing endless paths.  With zero cost
+/// edges between nodes of equal cost some equal paths can be missed.
+pub fn dijkstra_all<N, C, FN, IN, FS>(
+    start: &N,
+    mut successors: FN,
+    mut success: FS,
+) -> Option<ShortestPaths<N, C>>
+where
+    N: Eq + Hash + Clone,
+    C: Zero + Ord + Copy,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FS: FnMut(&N) -> bool,
+{
+    let mut visit_queue = BinaryHeap::new();
+    visit_queue.push(Visit {
+        total_cost: C::zero(),
+        node: start.clone(),
+    });
+
+    let mut markings = HashMap::new();
+    markings.insert(
+        start.clone(),
+        Marking {
+            cost: C::zero(),
+            prev_nodes: vec![],
+        },
+    );
+
+    let mut visited = HashSet::new();
+    let mut settled = vec![];
+    let mut goals = vec![];
+    let mut goal_cost = None;
+
+    while let Some(Visit { total_cost, node }) = visit_queue.pop() {
+        if goal_cost.is_some_and(|goal_cost| total_cost > goal_cost) {
+            break;
+        }
+        if !visited.insert(node.clone()) {
+            continue;
+        }
+        settled.push(node.clone());
+
+        if success(&node) {
+            goal_cost = Some(total_cost);
+            goals.push(node.clone());
+        }
+
+        for (next, cost) in successors(&node) {
+            if visited.contains(&next) {
+                continue;
+            }
+            let this_cost = total_cost + cost;
+            match markings.entry(next.clone()) {
+                Entry::Occupied(mut e) => {
+                    let marking = e.get_mut();
+                    match this_cost.cmp(&marking.cost) {
+                        Ordering::Less => {
+                            marking.cost = this_cost;
+                            marking.prev_nodes = vec![node.clone()];
+                        }
+                        Ordering::Equal => {
+                            if !marking.prev_nodes.contains(&node) {
+                                marking.prev_nodes.push(node.clone());
+                            }
+                            continue;
+                        }
+                        Ordering::Greater => continue,
+                    }
+                }
+                Entry::Vacant(e) => {
+                    e.insert(Marking {
+                        cost: this_cost,
+                        prev_nodes: vec![node.clone()],
+                    });
+                }
+            }
+            visit_queue.push(Visit {
+                total_cost: this_cost,
+                node: next,
+            });
+        }
+    }
+
+    Some(ShortestPaths {
+        start: start.clone(),
+        markings,
+        settled,
+        goals,
+        cost: goal_cost?,
+    })
+}
+
+/// Shortest cost to each of `goals`, stopping once all of them are settled.
+/// Goals that can't be reached are left out.
+pub fn dijkstra_goals<N, C, FN, IN>(
+    start: &N,
+    mut successors: FN,
+    goals: impl IntoIterator<Item = N>,
+) -> HashMap<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Zero + Ord + Copy,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+{
+    let mut remaining = goals.into_iter().collect::<HashSet<_>>();
+    let mut found = HashMap::new();
+    let mut costs = HashMap::from([(start.clone(), C::zero())]);
+    let mut visit_queue = BinaryHeap::from([Visit {
+        total_cost: C::zero(),
+        node: start.clone(),
+    }]);
+    let mut visited = HashSet::new();
+
+    while let Some(Visit { total_cost, node }) = visit_queue.pop() {
+        if remaining.is_empty() {
+            break;
+        }
+        if !visited.insert(node.clone()) {
+            continue;
+        }
+        if remaining.remove(&node) {
+            found.insert(node.clone(), total_cost);
+        }
+        for (next, cost) in successors(&node) {
+            let this_cost = total_cost + cost;
+            if costs.get(&next).is_none_or(|c| this_cost < *c) {
+                costs.insert(next.clone(), this_cost);
+                visit_queue.push(Visit {
+                    total_cost: this_cost,
+                    node: next,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// One direction of a bidirectional search.
+struct Frontier<N, C> {
+    costs: HashMap<N, (C, Option<N>)>,
+    visit_queue: BinaryHeap<Visit<C, N>>,
+    visited: HashSet<N>,
+}
+
+impl<N, C> Frontier<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Zero + Ord + Copy,
+{
+    fn new(starts: impl IntoIterator<Item = N>) -> Self {
+        let mut frontier = Frontier {
+            costs: HashMap::new(),
+            visit_queue: BinaryHeap::new(),
+            visited: HashSet::new(),
+        };
+        for start in starts {
+            frontier.costs.insert(start.clone(), (C::zero(), None));
+            frontier.visit_queue.push(Visit {
+                total_cost: C::zero(),
+                node: start,
+            });
+        }
+        frontier
+    }
+
+    fn peek_cost(&self) -> Option<C> {
+        self.visit_queue.peek().map(|v| v.total_cost)
+    }
+
+    /// Settle the cheapest node, relaxing its neighbours.  Every neighbour
+    /// that the other frontier has reached is a candidate meeting point.
+    fn step<IN>(
+        &mut self,
+        other: &Frontier<N, C>,
+        neighbours: impl FnOnce(&N) -> IN,
+        best: &mut Option<(C, N)>,
+    ) where
+        IN: IntoIterator<Item = (N, C)>,
+    {
+        let Some(Visit { total_cost, node }) = self.visit_queue.pop() else {
+            return;
+        };
+        if !self.visited.insert(node.clone()) {
+            return;
+        }
+        for (next, cost) in neighbours(&node) {
+            let this_cost = total_cost + cost;
+            if self.costs.get(&next).is_some_and(|(c, _)| *c <= this_cost) {
+                continue;
+            }
+            self.costs
+                .insert(next.clone(), (this_cost, Some(node.clone())));
+            if let Some((other_cost, _)) = other.costs.get(&next) {
+                let through = this_cost + *other_cost;
+                if best.as_ref().is_none_or(|(c, _)| through < *c) {
+                    *best = Some((through, next.clone()));
+                }
+            }
+            self.visit_queue.push(Visit {
+                total_cost: this_cost,
+                node: next,
+            });
+        }
+    }
+
+    /// Walk parents from `node` back to where this frontier started.
+    fn trace(&self, node: &N) -> Vec<N> {
+        let mut path = vec![node.clone()];
+        while let Some((_, Some(prev))) = self.costs.get(path.last().expect("never empty")) {
+            path.push(prev.clone());
+        }
+        path
+    }
+}
+
+/// Dijkstra's algorithm run from both ends at once, meeting in the middle.
+///
+/// `predecessors` is the reverse of `successors`: for a node it returns every
+/// node with an edge into it and that edge's cost.  Any of `goals` ends the
+/// path.  Returns one shortest path, start to goal inclusive, and its cost.
+pub fn bidirectional_dijkstra<N, C, FN, IN, FP, IP>(
+    start: &N,
+    goals: impl IntoIterator<Item = N>,
+    mut successors: FN,
+    mut predecessors: FP,
+) -> Option<(Vec<N>, C)>
+where
+    N: Eq + Hash + Clone,
+    C: Zero + Ord + Copy,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FP: FnMut(&N) -> IP,
+    IP: IntoIterator<Item = (N, C)>,
+{
+    let mut forward = Frontier::new([start.clone()]);
+    let mut backward = Frontier::new(goals);
+    if backward.costs.contains_key(start) {
+        return Some((vec![start.clone()], C::zero()));
+    }
+
+    let mut best: Option<(C, N)> = None;
+    while let (Some(f), Some(b)) = (forward.peek_cost(), backward.peek_cost()) {
+        // Nothing left in either queue can beat the best meeting found.
+        if best.as_ref().is_some_and(|(c, _)| f + b >= *c) {
+            break;
+        }
+        if f <= b {
+            forward.step(&backward, &mut successors, &mut best);
+        } else {
+            backward.step(&forward, &mut predecessors, &mut best);
+        }
+    }
+
+    let (cost, meet) = best?;
+    let mut path = forward.trace(&meet);
+    path.reverse();
+    path.extend(backward.trace(&meet).into_iter().skip(1));
+    Some((path, cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_xy, Position, TestRng as Rng};
+
+    /// Random grid where 0 is a wall and anything else is the cost to enter.
+    fn random_maze(rng: &mut Rng, size: usize) -> Vec<Vec<u32>> {
+        let mut maze = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| match rng.below(10) {
+                        0..=2 => 0,
+                        // Lots of cost 1 cells so there are many equal paths.
+                        3..=7 => 1,
+                        n => n as u32,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        maze[0][0] = 1;
+        maze[size - 1][size - 1] = 1;
+        maze
+    }
+
+    const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+    fn successors(maze: &[Vec<u32>], xy: &Position) -> Vec<(Position, u32)> {
+        DIRECTIONS
+            .iter()
+            .filter_map(|d| {
+                let next = add_xy(xy, d)?;
+                let cost = *maze.get(next.1)?.get(next.0)?;
+                (cost > 0).then_some((next, cost))
+            })
+            .collect()
+    }
+
+    /// Reverse edges: leaving `xy` towards a neighbour costs what entering `xy` does.
+    fn predecessors(maze: &[Vec<u32>], xy: &Position) -> Vec<(Position, u32)> {
+        let cost = maze[xy.1][xy.0];
+        successors(maze, xy)
+            .into_iter()
+            .map(|(next, _)| (next, cost))
+            .collect()
+    }
+
+    #[test]
+    fn visit_ordering() {
+        let mut heap = BinaryHeap::new();
+        for cost in [1, 3, 2] {
+            heap.push(Visit {
+                total_cost: cost,
+                node: cost,
+            });
+        }
+        let nodes = std::iter::from_fn(|| heap.pop().map(|v| v.node)).collect::<Vec<_>>();
+        assert_eq!(nodes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn diamond_paths() {
+        // Two equal routes from 0 to 3 and a more expensive direct edge.
+        let edges = |n: &u32| match n {
+            0 => vec![(1, 1), (2, 1), (3, 5)],
+            1 | 2 => vec![(3, 1)],
+            _ => vec![],
+        };
+        let found = dijkstra_all(&0, edges, |n| *n == 3).unwrap();
+        assert_eq!(found.cost(), 2);
+        assert_eq!(found.count_paths(), 2);
+        let mut paths = found.paths().collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, vec![vec![0, 1, 3], vec![0, 2, 3]]);
+        assert_eq!(found.nodes_on_paths().len(), 4);
+        assert!(dijkstra_all(&3, edges, |n| *n == 0).is_none());
+    }
+
+    #[test]
+    fn matches_pathfinding_on_random_mazes() {
+        let mut rng = Rng(0x5eed);
+        for _ in 0..200 {
+            let size = 2 + rng.below(12) as usize;
+            let maze = random_maze(&mut rng, size);
+            let end = (size - 1, size - 1);
+
+            let expected = pathfinding::directed::astar::astar_bag(
+                &(0, 0),
+                |xy| successors(&maze, xy),
+                |_| 0,
+                |xy| *xy == end,
+            );
+            let found = dijkstra_all(&(0, 0), |xy| successors(&maze, xy), |xy| *xy == end);
+            let bidirectional = bidirectional_dijkstra(
+                &(0, 0),
+                [end],
+                |xy| successors(&maze, xy),
+                |xy| predecessors(&maze, xy),
+            );
+
+            let Some((solutions, cost)) = expected else {
+                assert!(found.is_none());
+                assert!(bidirectional.is_none());
+                continue;
+            };
+            let found = found.unwrap();
+            let expected_paths = solutions.collect::<HashSet<_>>();
+            assert_eq!(found.cost(), cost);
+            assert_eq!(found.count_paths(), expected_paths.len() as u128);
+            assert_eq!(found.paths().collect::<HashSet<_>>(), expected_paths);
+            assert!(expected_paths.contains(&found.path()));
+
+            let (path, bidirectional_cost) = bidirectional.unwrap();
+            assert_eq!(bidirectional_cost, cost);
+            assert_eq!(path.first(), Some(&(0, 0)));
+            assert_eq!(path.last(), Some(&end));
+            let walked = path.windows(2).map(|w| maze[w[1].1][w[1].0]).sum::<u32>();
+            assert_eq!(walked, cost);
+        }
+    }
+
+    #[test]
+    fn goals_match_pathfinding() {
+        let mut rng = Rng(42);
+        for _ in 0..50 {
+            let maze = random_maze(&mut rng, 10);
+            let all =
+                pathfinding::directed::dijkstra::dijkstra_all(&(0, 0), |xy| successors(&maze, xy));
+            let goals = [(9, 9), (0, 9), (9, 0), (5, 5)];
+            let found = dijkstra_goals(&(0, 0), |xy| successors(&maze, xy), goals);
+            for goal in goals {
+                assert_eq!(found.get(&goal), all.get(&goal).map(|(_, c)| c));
+            }
+        }
+    }
+}